@@ -0,0 +1,196 @@
+//! Typed items for the fluent builders and outputs of [`aws_sdk_dynamodb`].
+//!
+//! serde_dynamo's [`Serializer`](crate::Serializer) and [`Deserializer`](crate::Deserializer)
+//! work with rusoto's [`AttributeValue`]. The extension traits here convert to and from the SDK's
+//! own `AttributeValue` on the way, so requests can be built from Rust types and responses read
+//! back into them:
+//!
+//! ```
+//! use aws_sdk_dynamodb::Client;
+//! use serde_dynamo::aws_sdk::{ItemFrom, KeyFrom, TypedItem};
+//! # use serde_derive::{Deserialize, Serialize};
+//! #
+//! # #[derive(Serialize, Deserialize)]
+//! # struct User { id: String, name: String }
+//! # #[derive(Serialize)]
+//! # struct UserKey<'a> { id: &'a str }
+//! #
+//! # async fn rename(client: &Client, mut user: User) -> Result<(), Box<dyn std::error::Error>> {
+//!
+//! client.put_item().table_name("users").item_from(&user)?.send().await?;
+//!
+//! let output = client
+//!     .get_item()
+//!     .table_name("users")
+//!     .key_from(&UserKey { id: &user.id })?
+//!     .send()
+//!     .await?;
+//! let user: Option<User> = output.typed_item()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The conversions between rusoto's attribute values and the SDK's that these use are in
+//! [`aws_sdk_convert`](crate::aws_sdk_convert), which the `aws-sdk-convert` feature enables on
+//! its own.
+
+pub use crate::aws_sdk_convert::SdkItem;
+use crate::aws_sdk_convert::{from_sdk_item, to_sdk_item, to_sdk_value};
+use crate::{from_item, to_item, Result};
+use aws_sdk_dynamodb::operation::delete_item::builders::DeleteItemFluentBuilder;
+use aws_sdk_dynamodb::operation::delete_item::DeleteItemOutput;
+use aws_sdk_dynamodb::operation::get_item::builders::GetItemFluentBuilder;
+use aws_sdk_dynamodb::operation::get_item::GetItemOutput;
+use aws_sdk_dynamodb::operation::put_item::builders::PutItemFluentBuilder;
+use aws_sdk_dynamodb::operation::put_item::PutItemOutput;
+use aws_sdk_dynamodb::operation::query::builders::QueryFluentBuilder;
+use aws_sdk_dynamodb::operation::query::QueryOutput;
+use aws_sdk_dynamodb::operation::scan::builders::ScanFluentBuilder;
+use aws_sdk_dynamodb::operation::scan::ScanOutput;
+use aws_sdk_dynamodb::operation::update_item::builders::UpdateItemFluentBuilder;
+use aws_sdk_dynamodb::operation::update_item::UpdateItemOutput;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(test)]
+mod tests;
+
+/// Builders of requests that store a whole item.
+pub trait ItemFrom: Sized {
+    /// Set the request's item to `item`, serialized with [`to_item`].
+    fn item_from<T: Serialize + ?Sized>(self, item: &T) -> Result<Self>;
+}
+
+/// Builders of requests that address an item by its key.
+pub trait KeyFrom: Sized {
+    /// Set the request's key to `key`, serialized with [`to_item`].
+    fn key_from<K: Serialize + ?Sized>(self, key: &K) -> Result<Self>;
+}
+
+/// Builders of requests with expressions.
+pub trait ExpressionValuesFrom: Sized {
+    /// Add the fields of `values` to the request's `ExpressionAttributeValues`.
+    ///
+    /// `values` has to serialize to a map, such as a struct, whose keys are the placeholders.
+    /// Keys without the leading `:` get one, so a struct's field `id` fills in `:id`.
+    fn expression_values_from<V: Serialize + ?Sized>(self, values: &V) -> Result<Self>;
+}
+
+/// Outputs that carry a single item.
+pub trait TypedItem {
+    /// The output's item, deserialized with [`from_item`].
+    ///
+    /// For writes, this is the `Attributes` the request asked for with `ReturnValues`.
+    fn typed_item<T: DeserializeOwned>(&self) -> Result<Option<T>>;
+}
+
+/// Outputs that carry a page of items.
+pub trait TypedItems {
+    /// The output's items, deserialized with [`from_item`].
+    fn typed_items<T: DeserializeOwned>(&self) -> Result<Vec<T>>;
+}
+
+macro_rules! impl_item_from {
+    ($($builder:ty),*) => {
+        $(
+            impl ItemFrom for $builder {
+                fn item_from<T: Serialize + ?Sized>(self, item: &T) -> Result<Self> {
+                    Ok(self.set_item(Some(to_sdk_item(to_item(item)?))))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_key_from {
+    ($($builder:ty),*) => {
+        $(
+            impl KeyFrom for $builder {
+                fn key_from<K: Serialize + ?Sized>(self, key: &K) -> Result<Self> {
+                    Ok(self.set_key(Some(to_sdk_item(to_item(key)?))))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_expression_values_from {
+    ($($builder:ty),*) => {
+        $(
+            impl ExpressionValuesFrom for $builder {
+                fn expression_values_from<V: Serialize + ?Sized>(self, values: &V) -> Result<Self> {
+                    let mut merged = self
+                        .get_expression_attribute_values()
+                        .clone()
+                        .unwrap_or_default();
+                    merged.extend(expression_values(values)?);
+                    Ok(self.set_expression_attribute_values(Some(merged)))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_typed_item {
+    ($($output:ty => $item:ident),*) => {
+        $(
+            impl TypedItem for $output {
+                fn typed_item<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+                    self.$item()
+                        .map(|item| from_item(from_sdk_item(item.clone())?))
+                        .transpose()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_typed_items {
+    ($($output:ty),*) => {
+        $(
+            impl TypedItems for $output {
+                fn typed_items<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+                    self.items()
+                        .iter()
+                        .map(|item| from_item(from_sdk_item(item.clone())?))
+                        .collect()
+                }
+            }
+        )*
+    };
+}
+
+impl_item_from!(PutItemFluentBuilder);
+impl_key_from!(
+    GetItemFluentBuilder,
+    UpdateItemFluentBuilder,
+    DeleteItemFluentBuilder
+);
+impl_expression_values_from!(
+    PutItemFluentBuilder,
+    UpdateItemFluentBuilder,
+    DeleteItemFluentBuilder,
+    QueryFluentBuilder,
+    ScanFluentBuilder
+);
+impl_typed_item!(
+    GetItemOutput => item,
+    PutItemOutput => attributes,
+    UpdateItemOutput => attributes,
+    DeleteItemOutput => attributes
+);
+impl_typed_items!(QueryOutput, ScanOutput);
+
+fn expression_values<V: Serialize + ?Sized>(values: &V) -> Result<SdkItem> {
+    Ok(to_item(values)?
+        .into_iter()
+        .map(|(placeholder, value)| {
+            let placeholder = if placeholder.starts_with(':') {
+                placeholder
+            } else {
+                format!(":{}", placeholder)
+            };
+            (placeholder, to_sdk_value(value))
+        })
+        .collect())
+}
@@ -0,0 +1,123 @@
+use super::*;
+use aws_sdk_dynamodb::config::{AsyncSleep, BehaviorVersion, Region, SharedAsyncSleep, Sleep};
+use aws_sdk_dynamodb::primitives::Blob;
+use aws_sdk_dynamodb::types::AttributeValue as SdkAttributeValue;
+use aws_sdk_dynamodb::{Client, Config};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct User {
+    id: String,
+    name: String,
+    #[serde(with = "serde_bytes")]
+    avatar: Vec<u8>,
+    tags: Vec<String>,
+}
+
+fn arthur() -> User {
+    User {
+        id: String::from("arthur"),
+        name: String::from("Arthur Dent"),
+        avatar: vec![0, 1, 255],
+        tags: vec![String::from("human")],
+    }
+}
+
+/// The SDK insists on a way to sleep, which the tests never need.
+#[derive(Debug)]
+struct NeverSleep;
+
+impl AsyncSleep for NeverSleep {
+    fn sleep(&self, _: Duration) -> Sleep {
+        Sleep::new(std::future::pending())
+    }
+}
+
+/// A client that is only used to make builders, and never sends anything.
+fn client() -> Client {
+    Client::from_conf(
+        Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .sleep_impl(SharedAsyncSleep::new(NeverSleep))
+            .build(),
+    )
+}
+
+#[test]
+fn builders() {
+    let put = client().put_item().item_from(&arthur()).unwrap();
+    let item = put.as_input().get_item().clone().unwrap();
+    assert_eq!(
+        item["name"],
+        SdkAttributeValue::S(String::from("Arthur Dent"))
+    );
+    assert_eq!(
+        item["avatar"],
+        SdkAttributeValue::B(Blob::new(vec![0, 1, 255]))
+    );
+
+    let get = client()
+        .get_item()
+        .key_from(&json!({ "id": "arthur" }))
+        .unwrap();
+    assert_eq!(
+        get.as_input().get_key().clone().unwrap()["id"],
+        SdkAttributeValue::S(String::from("arthur"))
+    );
+
+    #[derive(Serialize)]
+    struct Values {
+        id: &'static str,
+        #[serde(rename = ":min")]
+        min: u8,
+    }
+    let query = client()
+        .query()
+        .expression_values_from(&Values {
+            id: "arthur",
+            min: 3,
+        })
+        .unwrap()
+        .expression_values_from(&json!({ "max": 7 }))
+        .unwrap();
+    let values = query
+        .as_input()
+        .get_expression_attribute_values()
+        .clone()
+        .unwrap();
+    let mut placeholders: Vec<_> = values.keys().cloned().collect();
+    placeholders.sort();
+    assert_eq!(placeholders, vec![":id", ":max", ":min"]);
+    assert_eq!(values[":max"], SdkAttributeValue::N(String::from("7")));
+
+    assert!(client().get_item().key_from(&"arthur").is_err());
+}
+
+#[test]
+fn outputs() {
+    let item = to_sdk_item(to_item(arthur()).unwrap());
+    let output = GetItemOutput::builder()
+        .set_item(Some(item.clone()))
+        .build();
+    assert_eq!(output.typed_item::<User>().unwrap(), Some(arthur()));
+    assert_eq!(
+        GetItemOutput::builder()
+            .build()
+            .typed_item::<User>()
+            .unwrap(),
+        None
+    );
+
+    let output = QueryOutput::builder()
+        .items(item.clone())
+        .items(item)
+        .build();
+    assert_eq!(
+        output.typed_items::<User>().unwrap(),
+        vec![arthur(), arthur()]
+    );
+    assert!(output.typed_items::<u32>().is_err());
+}
@@ -0,0 +1,148 @@
+//! Lossless conversions between rusoto's [`AttributeValue`] and [`aws_sdk_dynamodb`]'s.
+//!
+//! Code moving from rusoto to the SDK one table at a time can convert attribute values and items
+//! between the two directly. Unlike a round trip through a Rust type or JSON, these keep string,
+//! number and binary sets, and binary values, as they are.
+//!
+//! Both types belong to other crates, so the `From` and `TryFrom` implementations go through
+//! [`Rusoto`], a wrapper around rusoto's side of the conversion:
+//!
+//! ```
+//! use rusoto_dynamodb::AttributeValue;
+//! use serde_dynamo::aws_sdk_convert::{Rusoto, SdkItem};
+//! use std::collections::HashMap;
+//! use std::convert::TryFrom;
+//!
+//! let mut item = HashMap::new();
+//! item.insert(
+//!     "tags".to_string(),
+//!     AttributeValue { ss: Some(vec!["a".to_string()]), ..AttributeValue::default() },
+//! );
+//!
+//! let sdk = SdkItem::from(Rusoto(item.clone()));
+//! assert_eq!(Rusoto::try_from(sdk).unwrap(), Rusoto(item));
+//! ```
+//!
+//! [`to_sdk_value`], [`from_sdk_value`], [`to_sdk_item`] and [`from_sdk_item`] do the same as
+//! plain functions.
+
+use crate::error::{Error, ErrorImpl};
+use crate::{AttributeValue, Item, Result};
+use aws_sdk_dynamodb::primitives::Blob;
+use aws_sdk_dynamodb::types::AttributeValue as SdkAttributeValue;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+#[cfg(test)]
+mod tests;
+
+/// An item the way the SDK represents it.
+pub type SdkItem = HashMap<String, SdkAttributeValue>;
+
+/// One of rusoto's attribute values or items, wrapped so that it converts to and from the SDK's
+/// with `From` and `TryFrom`.
+#[repr(transparent)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rusoto<T>(pub T);
+
+impl From<Rusoto<AttributeValue>> for SdkAttributeValue {
+    fn from(value: Rusoto<AttributeValue>) -> Self {
+        to_sdk_value(value.0)
+    }
+}
+
+impl From<Rusoto<Item>> for SdkItem {
+    fn from(item: Rusoto<Item>) -> Self {
+        to_sdk_item(item.0)
+    }
+}
+
+impl TryFrom<SdkAttributeValue> for Rusoto<AttributeValue> {
+    type Error = Error;
+
+    fn try_from(value: SdkAttributeValue) -> Result<Self> {
+        from_sdk_value(value).map(Rusoto)
+    }
+}
+
+impl TryFrom<SdkItem> for Rusoto<Item> {
+    type Error = Error;
+
+    fn try_from(item: SdkItem) -> Result<Self> {
+        from_sdk_item(item).map(Rusoto)
+    }
+}
+
+/// Convert rusoto's item to the SDK's, without losing anything.
+pub fn to_sdk_item(item: Item) -> SdkItem {
+    item.into_iter()
+        .map(|(name, value)| (name, to_sdk_value(value)))
+        .collect()
+}
+
+/// Convert rusoto's attribute value to the SDK's, without losing anything: sets stay sets, and
+/// binary stays binary.
+///
+/// A value with none of its fields set becomes `NULL`.
+pub fn to_sdk_value(value: AttributeValue) -> SdkAttributeValue {
+    if let Some(s) = value.s {
+        SdkAttributeValue::S(s)
+    } else if let Some(n) = value.n {
+        SdkAttributeValue::N(n)
+    } else if let Some(b) = value.b {
+        SdkAttributeValue::B(Blob::new(b.to_vec()))
+    } else if let Some(ss) = value.ss {
+        SdkAttributeValue::Ss(ss)
+    } else if let Some(ns) = value.ns {
+        SdkAttributeValue::Ns(ns)
+    } else if let Some(bs) = value.bs {
+        SdkAttributeValue::Bs(bs.into_iter().map(|b| Blob::new(b.to_vec())).collect())
+    } else if let Some(l) = value.l {
+        SdkAttributeValue::L(l.into_iter().map(to_sdk_value).collect())
+    } else if let Some(m) = value.m {
+        SdkAttributeValue::M(to_sdk_item(m))
+    } else if let Some(bool) = value.bool {
+        SdkAttributeValue::Bool(bool)
+    } else {
+        SdkAttributeValue::Null(value.null.unwrap_or(true))
+    }
+}
+
+/// Convert the SDK's item to rusoto's, without losing anything.
+///
+/// Fails if any of its values is of a type the SDK didn't recognize.
+pub fn from_sdk_item(item: SdkItem) -> Result<Item> {
+    item.into_iter()
+        .map(|(name, value)| Ok((name, from_sdk_value(value)?)))
+        .collect()
+}
+
+/// Convert the SDK's attribute value to rusoto's, without losing anything.
+///
+/// Fails on a type of value the SDK didn't recognize, which it represents as `Unknown`.
+pub fn from_sdk_value(value: SdkAttributeValue) -> Result<AttributeValue> {
+    let mut converted = AttributeValue::default();
+    match value {
+        SdkAttributeValue::S(s) => converted.s = Some(s),
+        SdkAttributeValue::N(n) => converted.n = Some(n),
+        SdkAttributeValue::B(b) => converted.b = Some(b.into_inner().into()),
+        SdkAttributeValue::Ss(ss) => converted.ss = Some(ss),
+        SdkAttributeValue::Ns(ns) => converted.ns = Some(ns),
+        SdkAttributeValue::Bs(bs) => {
+            converted.bs = Some(bs.into_iter().map(|b| b.into_inner().into()).collect())
+        }
+        SdkAttributeValue::L(l) => {
+            converted.l = Some(l.into_iter().map(from_sdk_value).collect::<Result<_>>()?)
+        }
+        SdkAttributeValue::M(m) => converted.m = Some(from_sdk_item(m)?),
+        SdkAttributeValue::Bool(bool) => converted.bool = Some(bool),
+        SdkAttributeValue::Null(null) => converted.null = Some(null),
+        _ => {
+            return Err(ErrorImpl::Message(String::from(
+                "Unknown type of attribute value, which the SDK didn't recognize",
+            ))
+            .into())
+        }
+    }
+    Ok(converted)
+}
@@ -0,0 +1,101 @@
+use super::*;
+use crate::to_item;
+use serde_json::json;
+
+#[test]
+fn conversions() {
+    let item = to_item(json!({
+        "s": "text",
+        "n": 42,
+        "bool": true,
+        "null": null,
+        "l": [1, "two", [3]],
+        "m": { "nested": { "deeper": false } },
+    }))
+    .unwrap();
+    let mut item = item;
+    let blob = |bytes: &[u8]| bytes.to_vec().into();
+    item.insert(
+        String::from("b"),
+        AttributeValue {
+            b: Some(blob(&[0, 159])),
+            ..AttributeValue::default()
+        },
+    );
+    for (name, value) in [
+        (
+            "ss",
+            AttributeValue {
+                ss: Some(vec![String::from("a"), String::from("b")]),
+                ..AttributeValue::default()
+            },
+        ),
+        (
+            "ns",
+            AttributeValue {
+                ns: Some(vec![String::from("1"), String::from("2.5")]),
+                ..AttributeValue::default()
+            },
+        ),
+        (
+            "bs",
+            AttributeValue {
+                bs: Some(vec![blob(&[1]), blob(&[2, 3])]),
+                ..AttributeValue::default()
+            },
+        ),
+    ] {
+        item.insert(String::from(name), value);
+    }
+
+    let sdk = to_sdk_item(item.clone());
+    assert_eq!(
+        sdk["ns"],
+        SdkAttributeValue::Ns(vec![String::from("1"), String::from("2.5")])
+    );
+    assert_eq!(sdk["null"], SdkAttributeValue::Null(true));
+    assert_eq!(from_sdk_item(sdk).unwrap(), item);
+}
+
+#[test]
+fn conversion_edge_cases() {
+    assert_eq!(
+        to_sdk_value(AttributeValue::default()),
+        SdkAttributeValue::Null(true)
+    );
+    let null = from_sdk_value(SdkAttributeValue::Null(false)).unwrap();
+    assert_eq!(null.null, Some(false));
+    assert_eq!(to_sdk_value(null), SdkAttributeValue::Null(false));
+
+    let nested = SdkAttributeValue::L(vec![SdkAttributeValue::M(
+        vec![(
+            String::from("bs"),
+            SdkAttributeValue::Bs(vec![Blob::new(vec![7])]),
+        )]
+        .into_iter()
+        .collect(),
+    )]);
+    assert_eq!(
+        to_sdk_value(from_sdk_value(nested.clone()).unwrap()),
+        nested
+    );
+}
+
+#[test]
+fn from_and_try_from() {
+    let value = AttributeValue {
+        bs: Some(vec![vec![1].into(), vec![2, 3].into()]),
+        ..AttributeValue::default()
+    };
+    let sdk = SdkAttributeValue::from(Rusoto(value.clone()));
+    assert_eq!(
+        sdk,
+        SdkAttributeValue::Bs(vec![Blob::new(vec![1]), Blob::new(vec![2, 3])])
+    );
+    assert_eq!(Rusoto::try_from(sdk).unwrap(), Rusoto(value.clone()));
+
+    let mut item = Item::new();
+    item.insert(String::from("bs"), value);
+    let sdk = SdkItem::from(Rusoto(item.clone()));
+    assert_eq!(Rusoto::try_from(sdk).unwrap(), Rusoto(item));
+}
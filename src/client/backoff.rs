@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+/// How long to wait between retries of a request DynamoDB didn't finish.
+///
+/// Retries wait a random time between zero and an exponentially growing cap ("full jitter"), so
+/// that many clients throttled at once don't retry in lockstep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backoff {
+    /// The cap on the first retry's delay, doubled for every retry after it
+    pub base: Duration,
+    /// The most any retry waits
+    pub max_delay: Duration,
+    /// How many times a request is sent before giving up on it
+    pub max_attempts: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            base: Duration::from_millis(50),
+            max_delay: Duration::from_secs(5),
+            max_attempts: 10,
+        }
+    }
+}
+
+impl Backoff {
+    /// The delay before retry number `retry`, counting from zero.
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        let cap = self
+            .base
+            .checked_mul(1 << retry.min(31))
+            .map_or(self.max_delay, |cap| cap.min(self.max_delay));
+        let nanos = cap.as_nanos() as u64;
+        if nanos == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos(fastrand::u64(..=nanos))
+    }
+
+    pub(crate) async fn wait(&self, retry: u32) {
+        tokio::time::sleep(self.delay(retry)).await
+    }
+}
@@ -0,0 +1,409 @@
+use super::{comparable_key, Backoff, ComparableKey};
+use crate::error::ErrorImpl;
+use crate::{from_item, Item, Result};
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::{BatchGetItemError, BatchGetItemInput, DynamoDb, KeysAndAttributes};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The most keys DynamoDB accepts in one BatchGetItem call.
+pub const MAX_BATCH_GETS: usize = 100;
+
+/// Reads items by key from one or more tables with BatchGetItem, and retries the keys DynamoDB
+/// leaves unprocessed.
+///
+/// Keys are split into calls of at most 100, and a key requested twice is only sent once. Each
+/// table's items come back as the type its keys were added with, paired with the key they were
+/// requested by. Keys DynamoDB still leaves unprocessed after every attempt are reported by
+/// [`BatchGetResults::unprocessed`], alongside the items that were read.
+///
+/// ```
+/// # use rusoto_dynamodb::DynamoDb;
+/// use serde_dynamo::client::BatchGetter;
+/// # use serde_derive::{Deserialize, Serialize};
+/// #
+/// # #[derive(Serialize)]
+/// # struct UserKey { id: String }
+/// # #[derive(Deserialize)]
+/// # struct User { id: String, name: String }
+/// # #[derive(Serialize)]
+/// # struct TeamKey { id: String }
+/// # #[derive(Deserialize)]
+/// # struct Team { id: String, name: String }
+/// #
+/// # async fn read(
+/// #     client: &dyn DynamoDb,
+/// #     user_keys: Vec<UserKey>,
+/// #     team_keys: Vec<TeamKey>,
+/// # ) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let mut batch = BatchGetter::new();
+/// let users = batch.table::<_, User>("users", user_keys)?;
+/// let teams = batch.table::<_, Team>("teams", team_keys)?;
+///
+/// let mut results = batch.execute(client).await?;
+/// for (key, user) in results.take(users)? {
+///     match user {
+///         Some(user) => println!("{}: {}", key.id, user.name),
+///         None => println!("{}: not found", key.id),
+///     }
+/// }
+/// let teams = results.take(teams)?;
+/// for unprocessed in results.unprocessed() {
+///     eprintln!("{}: not read", unprocessed.table_name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BatchGetter {
+    /// Tells this getter's [`BatchGetKeys`] apart from other getters'
+    id: u64,
+    tables: Vec<Table>,
+    consistent_read: bool,
+    backoff: Backoff,
+}
+
+#[derive(Debug, Clone)]
+struct Table {
+    name: String,
+    key_names: Vec<String>,
+    keys: Vec<Item>,
+}
+
+/// The keys added to a [`BatchGetter`] for one table, to take their items from
+/// [`BatchGetResults`] with.
+///
+/// They only work with the results of the getter they were added to.
+#[derive(Debug)]
+pub struct BatchGetKeys<K, T> {
+    batch: u64,
+    table: usize,
+    start: usize,
+    keys: Vec<K>,
+    item: PhantomData<fn() -> T>,
+}
+
+/// The items a [`BatchGetter`] read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchGetResults {
+    batch: u64,
+    /// For each table, what became of each key, in the order the keys were added
+    tables: Vec<Vec<Slot>>,
+    unprocessed: Vec<UnprocessedKey>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Slot {
+    /// The key was read, and had this item or none
+    Read(Option<Item>),
+    /// DynamoDB left the key unprocessed on every attempt
+    Unprocessed,
+}
+
+/// A key a [`BatchGetter`] gave up on, because DynamoDB left it unprocessed on every attempt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnprocessedKey {
+    /// The table read from
+    pub table_name: String,
+    /// The key
+    pub key: Item,
+}
+
+impl BatchGetResults {
+    /// Whether every key was read.
+    pub fn is_complete(&self) -> bool {
+        self.unprocessed.is_empty()
+    }
+
+    /// The keys DynamoDB left unprocessed on every attempt, each once, whichever table they
+    /// were added for.
+    pub fn unprocessed(&self) -> &[UnprocessedKey] {
+        &self.unprocessed
+    }
+
+    /// Take the items read for `keys`, each paired with its key, in the order they were added.
+    ///
+    /// Keys that were left unprocessed are left out, since whether they have an item is
+    /// unknown.
+    ///
+    /// Fails if `keys` were added to a different [`BatchGetter`], or if an item can't be
+    /// deserialized as a `T`.
+    pub fn take<K, T>(&mut self, keys: BatchGetKeys<K, T>) -> Result<Vec<(K, Option<T>)>>
+    where
+        T: DeserializeOwned,
+    {
+        let items = match self.tables.get_mut(keys.table) {
+            Some(items) if keys.batch == self.batch => {
+                &mut items[keys.start..keys.start + keys.keys.len()]
+            }
+            _ => {
+                return Err(ErrorImpl::Message(String::from(
+                    "The keys were added to a different BatchGetter",
+                ))
+                .into())
+            }
+        };
+        keys.keys
+            .into_iter()
+            .zip(items)
+            .filter_map(|(key, slot)| match slot {
+                Slot::Read(item) => Some(item.take().map(from_item).transpose().map(|t| (key, t))),
+                Slot::Unprocessed => None,
+            })
+            .collect()
+    }
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+impl Default for BatchGetter {
+    fn default() -> Self {
+        BatchGetter {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            tables: Vec::new(),
+            consistent_read: false,
+            backoff: Backoff::default(),
+        }
+    }
+}
+
+impl Clone for BatchGetter {
+    /// A copy of the getter's keys. Keys added to the copy only work with the copy's results.
+    fn clone(&self) -> Self {
+        BatchGetter {
+            tables: self.tables.clone(),
+            consistent_read: self.consistent_read,
+            backoff: self.backoff.clone(),
+            ..BatchGetter::default()
+        }
+    }
+}
+
+impl BatchGetter {
+    /// A batch without any keys.
+    pub fn new() -> Self {
+        BatchGetter::default()
+    }
+
+    /// Use `backoff` to retry unprocessed keys, instead of [`Backoff::default`].
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Make strongly consistent reads, instead of eventually consistent ones.
+    pub fn consistent_read(mut self, consistent_read: bool) -> Self {
+        self.consistent_read = consistent_read;
+        self
+    }
+
+    /// Add keys to read from a table, whose items are deserialized as `T`.
+    ///
+    /// Every key of a table has to have the same attributes.
+    pub fn table<K, T>(
+        &mut self,
+        table_name: &str,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Result<BatchGetKeys<K, T>>
+    where
+        K: Serialize,
+    {
+        let keys: Vec<K> = keys.into_iter().collect();
+        let items = keys
+            .iter()
+            .map(crate::to_item)
+            .collect::<Result<Vec<_>>>()?;
+
+        // Check every key before adding any
+        let position = self.tables.iter().position(|t| t.name == table_name);
+        let mut key_names = position
+            .map(|index| &self.tables[index])
+            .filter(|table| !table.keys.is_empty())
+            .map(|table| table.key_names.clone());
+        for item in &items {
+            let mut names: Vec<_> = item.keys().cloned().collect();
+            names.sort();
+            match &key_names {
+                None => key_names = Some(names),
+                Some(key_names) if *key_names != names => {
+                    return Err(ErrorImpl::Validation(String::from(
+                        "The provided key element does not match the schema",
+                    ))
+                    .into())
+                }
+                Some(_) => {}
+            }
+        }
+
+        let index = position.unwrap_or_else(|| {
+            self.tables.push(Table {
+                name: table_name.to_string(),
+                key_names: Vec::new(),
+                keys: Vec::new(),
+            });
+            self.tables.len() - 1
+        });
+        let table = &mut self.tables[index];
+        if let Some(key_names) = key_names {
+            table.key_names = key_names;
+        }
+        let start = table.keys.len();
+        table.keys.extend(items);
+        Ok(BatchGetKeys {
+            batch: self.id,
+            table: index,
+            start,
+            keys,
+            item: PhantomData,
+        })
+    }
+
+    /// Read every key, retrying unprocessed ones.
+    ///
+    /// Fails if a call fails for any reason other than throttling.
+    pub async fn execute<D>(
+        self,
+        client: &D,
+    ) -> Result<BatchGetResults, RusotoError<BatchGetItemError>>
+    where
+        D: DynamoDb + ?Sized,
+    {
+        let backoff = self.backoff;
+        let consistent_read = self.consistent_read;
+        let tables = self.tables;
+        let indexes: HashMap<&str, usize> = tables
+            .iter()
+            .enumerate()
+            .map(|(index, table)| (table.name.as_str(), index))
+            .collect();
+
+        // Every distinct key, with the number of times it has been sent
+        let mut queue = VecDeque::new();
+        for (index, table) in tables.iter().enumerate() {
+            let mut seen = HashSet::new();
+            for key in &table.keys {
+                let comparable = comparable_key(key, &table.key_names);
+                if seen.insert(comparable.clone()) {
+                    queue.push_back((index, comparable, key.clone(), 0));
+                }
+            }
+        }
+
+        let mut found: Vec<HashMap<ComparableKey, Item>> = vec![HashMap::new(); tables.len()];
+        let mut exhausted = HashSet::new();
+        let mut unprocessed_keys = Vec::new();
+        let mut retries = 0;
+        while !queue.is_empty() {
+            let batch: Vec<_> = queue.drain(..MAX_BATCH_GETS.min(queue.len())).collect();
+            let mut request_items: HashMap<String, KeysAndAttributes> = HashMap::new();
+            for (index, _, key, _) in &batch {
+                request_items
+                    .entry(tables[*index].name.clone())
+                    .or_insert_with(|| KeysAndAttributes {
+                        consistent_read: Some(consistent_read),
+                        ..KeysAndAttributes::default()
+                    })
+                    .keys
+                    .push(key.clone());
+            }
+
+            let unprocessed = match client
+                .batch_get_item(BatchGetItemInput {
+                    request_items,
+                    ..BatchGetItemInput::default()
+                })
+                .await
+            {
+                Ok(output) => {
+                    for (table_name, items) in output.responses.unwrap_or_default() {
+                        let index = match indexes.get(table_name.as_str()) {
+                            Some(&index) => index,
+                            None => continue,
+                        };
+                        for item in items {
+                            let comparable = comparable_key(&item, &tables[index].key_names);
+                            found[index].insert(comparable, item);
+                        }
+                    }
+                    let mut unprocessed = HashSet::new();
+                    for (table_name, request) in output.unprocessed_keys.unwrap_or_default() {
+                        if let Some(&index) = indexes.get(table_name.as_str()) {
+                            for key in &request.keys {
+                                let comparable = comparable_key(key, &tables[index].key_names);
+                                unprocessed.insert((index, comparable));
+                            }
+                        }
+                    }
+                    unprocessed
+                }
+                Err(err) if is_throttled(&err) => batch
+                    .iter()
+                    .map(|(index, comparable, _, _)| (*index, comparable.clone()))
+                    .collect(),
+                Err(err) => return Err(err),
+            };
+
+            let mut retry = Vec::new();
+            for (index, comparable, key, attempts) in batch {
+                if unprocessed.contains(&(index, comparable.clone())) {
+                    if attempts + 1 >= backoff.max_attempts {
+                        unprocessed_keys.push(UnprocessedKey {
+                            table_name: tables[index].name.clone(),
+                            key,
+                        });
+                        exhausted.insert((index, comparable));
+                    } else {
+                        retry.push((index, comparable, key, attempts + 1));
+                    }
+                }
+            }
+            if retry.is_empty() {
+                retries = 0;
+            } else {
+                for entry in retry.into_iter().rev() {
+                    queue.push_front(entry);
+                }
+                backoff.wait(retries).await;
+                retries += 1;
+            }
+        }
+
+        let tables = tables
+            .iter()
+            .enumerate()
+            .zip(found)
+            .map(|((index, table), found)| {
+                table
+                    .keys
+                    .iter()
+                    .map(|key| {
+                        let comparable = comparable_key(key, &table.key_names);
+                        if exhausted.contains(&(index, comparable.clone())) {
+                            Slot::Unprocessed
+                        } else {
+                            Slot::Read(found.get(&comparable).cloned())
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(BatchGetResults {
+            batch: self.id,
+            tables,
+            unprocessed: unprocessed_keys,
+        })
+    }
+}
+
+fn is_throttled(err: &RusotoError<BatchGetItemError>) -> bool {
+    matches!(
+        err,
+        RusotoError::Service(BatchGetItemError::ProvisionedThroughputExceeded(_))
+            | RusotoError::Service(BatchGetItemError::RequestLimitExceeded(_))
+            | RusotoError::Service(BatchGetItemError::InternalServerError(_))
+    )
+}
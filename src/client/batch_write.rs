@@ -0,0 +1,341 @@
+use super::{comparable_key, Backoff, ComparableKey, MAX_ITEM_SIZE, MAX_REQUEST_SIZE};
+use crate::error::ErrorImpl;
+use crate::size::item_size;
+use crate::{Item, Result};
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::{
+    BatchWriteItemError, BatchWriteItemInput, DeleteRequest, DynamoDb, PutRequest, WriteRequest,
+};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display};
+
+/// The most requests DynamoDB accepts in one BatchWriteItem call.
+pub const MAX_BATCH_WRITES: usize = 25;
+
+/// Splits puts and deletes into BatchWriteItem calls, and retries what DynamoDB leaves
+/// unprocessed.
+///
+/// Every call stays within DynamoDB's limits: at most 25 requests, at most 16 MB, and no key
+/// written twice. Writes to the same key are sent in the order they were added. Items DynamoDB
+/// returns as `UnprocessedItems`, and calls it throttles, are retried with [`Backoff`].
+///
+/// Finding duplicates needs each table's key, so tables are declared with
+/// [`key`](BatchWriter::key) before writing to them.
+///
+/// ```
+/// # use rusoto_dynamodb::DynamoDb;
+/// use serde_dynamo::client::BatchWriter;
+/// # use serde_derive::Serialize;
+/// #
+/// # #[derive(Serialize)]
+/// # struct User { id: String, name: String }
+/// #
+/// # async fn write(client: &dyn DynamoDb, users: Vec<User>) -> serde_dynamo::Result<()> {
+///
+/// let mut batch = BatchWriter::new().key("users", "id", None);
+/// for user in &users {
+///     batch.put("users", user)?;
+/// }
+/// let report = batch.execute(client).await;
+/// for failed in &report.failed {
+///     eprintln!("{}: {}", failed.table_name, failed.reason);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BatchWriter {
+    keys: HashMap<String, Vec<String>>,
+    pending: Vec<Pending>,
+    backoff: Backoff,
+}
+
+#[derive(Debug, Clone)]
+struct Pending {
+    table_name: String,
+    /// The written key, in a form that can be compared
+    key: ComparableKey,
+    request: WriteRequest,
+    size: usize,
+    attempts: u32,
+}
+
+/// What happened to the writes of a [`BatchWriter`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchWriteReport {
+    /// How many writes succeeded
+    pub written: usize,
+    /// The writes that didn't
+    pub failed: Vec<FailedWrite>,
+}
+
+impl BatchWriteReport {
+    /// Whether every write succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// A write a [`BatchWriter`] gave up on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedWrite {
+    /// The table written to
+    pub table_name: String,
+    /// The put or delete
+    pub request: WriteRequest,
+    /// Why it failed
+    pub reason: WriteFailure,
+}
+
+/// Why a write failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteFailure {
+    /// DynamoDB left the write unprocessed on every attempt
+    Unprocessed,
+    /// DynamoDB rejected the call the write was part of, with this message
+    Rejected(String),
+}
+
+impl Display for WriteFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteFailure::Unprocessed => f.write_str("Unprocessed after every attempt"),
+            WriteFailure::Rejected(message) => f.write_str(message),
+        }
+    }
+}
+
+impl BatchWriter {
+    /// A batch without any writes.
+    pub fn new() -> Self {
+        BatchWriter::default()
+    }
+
+    /// Declare the key attributes of a table.
+    pub fn key(mut self, table_name: &str, partition_key: &str, sort_key: Option<&str>) -> Self {
+        let key = std::iter::once(partition_key)
+            .chain(sort_key)
+            .map(String::from)
+            .collect();
+        self.keys.insert(table_name.to_string(), key);
+        self
+    }
+
+    /// Use `backoff` to retry unprocessed writes, instead of [`Backoff::default`].
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Add a put of `item`.
+    ///
+    /// Fails if `item` doesn't serialize to an item with the table's key, or is larger than
+    /// DynamoDB allows.
+    pub fn put<T: Serialize>(&mut self, table_name: &str, item: T) -> Result<()> {
+        let item = crate::to_item(item)?;
+        let size = item_size(&item);
+        if size > MAX_ITEM_SIZE {
+            return Err(ErrorImpl::Validation(String::from(
+                "Item size has exceeded the maximum allowed size",
+            ))
+            .into());
+        }
+        let key = self.key_of(table_name, &item)?;
+        self.push(
+            table_name,
+            key,
+            size,
+            WriteRequest {
+                put_request: Some(PutRequest { item }),
+                delete_request: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Add a delete of the item with the given key.
+    pub fn delete<K: Serialize>(&mut self, table_name: &str, key: K) -> Result<()> {
+        let key = crate::to_item(key)?;
+        let comparable = self.key_of(table_name, &key)?;
+        if key.len() != self.keys[table_name].len() {
+            return Err(ErrorImpl::Validation(String::from(
+                "The provided key element does not match the schema",
+            ))
+            .into());
+        }
+        self.push(
+            table_name,
+            comparable,
+            item_size(&key),
+            WriteRequest {
+                put_request: None,
+                delete_request: Some(DeleteRequest { key }),
+            },
+        );
+        Ok(())
+    }
+
+    /// How many writes have been added.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether no writes have been added.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn key_of(&self, table_name: &str, item: &Item) -> Result<ComparableKey> {
+        let key = self.keys.get(table_name).ok_or_else(|| {
+            ErrorImpl::Message(format!("No key declared for table {}", table_name)).into()
+        })?;
+        if let Some(name) = key.iter().find(|name| !item.contains_key(*name)) {
+            return Err(ErrorImpl::Validation(format!(
+                "One or more parameter values were invalid: Missing the key {} in the item",
+                name
+            ))
+            .into());
+        }
+        Ok(comparable_key(item, key))
+    }
+
+    fn push(&mut self, table_name: &str, key: ComparableKey, size: usize, request: WriteRequest) {
+        self.pending.push(Pending {
+            table_name: table_name.to_string(),
+            key,
+            request,
+            size,
+            attempts: 0,
+        });
+    }
+
+    /// The BatchWriteItem calls the writes would be sent in, if DynamoDB processed all of them.
+    pub fn chunks(&self) -> Vec<BatchWriteItemInput> {
+        let mut queue: VecDeque<_> = self.pending.iter().cloned().collect();
+        let mut chunks = Vec::new();
+        while !queue.is_empty() {
+            chunks.push(input(&next_batch(&mut queue)));
+        }
+        chunks
+    }
+
+    /// Send every write, retrying unprocessed ones, and report the writes that failed.
+    pub async fn execute<D>(self, client: &D) -> BatchWriteReport
+    where
+        D: DynamoDb + ?Sized,
+    {
+        let backoff = self.backoff;
+        let mut queue: VecDeque<_> = self.pending.into_iter().collect();
+        let mut report = BatchWriteReport::default();
+        let mut failed = Vec::new();
+        let mut retries = 0;
+
+        while !queue.is_empty() {
+            let batch = next_batch(&mut queue);
+            let (mut unprocessed, rejected) = match client.batch_write_item(input(&batch)).await {
+                Ok(output) => (output.unprocessed_items.unwrap_or_default(), None),
+                Err(err) if is_throttled(&err) => (input(&batch).request_items, None),
+                Err(err) => (HashMap::new(), Some(err.to_string())),
+            };
+
+            let mut retry = Vec::new();
+            for mut pending in batch {
+                if let Some(message) = &rejected {
+                    failed.push((pending, WriteFailure::Rejected(message.clone())));
+                    continue;
+                }
+                let requests = unprocessed.entry(pending.table_name.clone()).or_default();
+                match requests.iter().position(|r| *r == pending.request) {
+                    Some(index) => {
+                        requests.swap_remove(index);
+                        pending.attempts += 1;
+                        if pending.attempts >= backoff.max_attempts {
+                            failed.push((pending, WriteFailure::Unprocessed));
+                        } else {
+                            retry.push(pending);
+                        }
+                    }
+                    None => report.written += 1,
+                }
+            }
+
+            if retry.is_empty() {
+                retries = 0;
+            } else {
+                // Unprocessed writes go first, so writes to one key stay in order
+                for pending in retry.into_iter().rev() {
+                    queue.push_front(pending);
+                }
+                backoff.wait(retries).await;
+                retries += 1;
+            }
+        }
+
+        report.failed = failed
+            .into_iter()
+            .map(|(pending, reason)| FailedWrite {
+                table_name: pending.table_name,
+                request: pending.request,
+                reason,
+            })
+            .collect();
+        report
+    }
+}
+
+/// Take the next batch from the front of the queue, leaving behind writes that would break one
+/// of DynamoDB's limits.
+fn next_batch(queue: &mut VecDeque<Pending>) -> Vec<Pending> {
+    let mut batch = Vec::new();
+    let mut size = 0;
+    let mut keys = HashSet::new();
+    let mut skipped = VecDeque::new();
+
+    while let Some(pending) = queue.pop_front() {
+        if batch.len() == MAX_BATCH_WRITES {
+            queue.push_front(pending);
+            break;
+        }
+        let key = (pending.table_name.clone(), pending.key.clone());
+        // A write to a skipped key has to wait for the skipped write, to stay in order
+        let blocked = skipped
+            .iter()
+            .any(|s: &Pending| s.table_name == key.0 && s.key == key.1);
+        if blocked || keys.contains(&key) || size + pending.size > MAX_REQUEST_SIZE {
+            skipped.push_back(pending);
+            continue;
+        }
+        size += pending.size;
+        keys.insert(key);
+        batch.push(pending);
+    }
+
+    while let Some(pending) = skipped.pop_back() {
+        queue.push_front(pending);
+    }
+    batch
+}
+
+fn input(batch: &[Pending]) -> BatchWriteItemInput {
+    let mut request_items: HashMap<String, Vec<WriteRequest>> = HashMap::new();
+    for pending in batch {
+        request_items
+            .entry(pending.table_name.clone())
+            .or_default()
+            .push(pending.request.clone());
+    }
+    BatchWriteItemInput {
+        request_items,
+        ..BatchWriteItemInput::default()
+    }
+}
+
+fn is_throttled(err: &RusotoError<BatchWriteItemError>) -> bool {
+    matches!(
+        err,
+        RusotoError::Service(BatchWriteItemError::ProvisionedThroughputExceeded(_))
+            | RusotoError::Service(BatchWriteItemError::RequestLimitExceeded(_))
+            | RusotoError::Service(BatchWriteItemError::InternalServerError(_))
+    )
+}
@@ -0,0 +1,131 @@
+//! Helpers that drive rusoto's [`DynamoDb`](rusoto_dynamodb::DynamoDb) client with typed data.
+//!
+//! DynamoDB's batch, transaction and paging APIs come with limits and partial results that every
+//! caller has to handle: at most 25 writes per BatchWriteItem, unprocessed items to retry, no
+//! item touched twice in a transaction, and so on. The helpers here take Rust types, turn them
+//! into [`Item`]s, and deal with those details.
+//!
+//! They work with any implementation of the trait, including `MockDynamoDb` with the `mock`
+//! feature, and wait between retries with tokio's timer, so they need a tokio runtime.
+
+use crate::error::ErrorImpl;
+use crate::expression::Expression;
+use crate::number::Number;
+use crate::{AttributeValue, Item, Result};
+use std::collections::HashMap;
+
+mod backoff;
+mod batch_get;
+mod batch_write;
+mod paginate;
+mod table;
+mod transaction;
+
+#[cfg(all(test, feature = "mock"))]
+mod tests;
+
+pub use backoff::Backoff;
+pub use batch_get::{BatchGetKeys, BatchGetResults, BatchGetter, UnprocessedKey, MAX_BATCH_GETS};
+pub use batch_write::{BatchWriteReport, BatchWriter, FailedWrite, WriteFailure, MAX_BATCH_WRITES};
+pub use paginate::{Items, ItemsError, Paginate};
+pub use table::{KeySchema, Table, TableError};
+pub use transaction::{
+    FromTransactGet, TransactGetResults, TransactGetter, TransactWriter, MAX_TRANSACT_ITEMS,
+};
+
+/// The largest item DynamoDB stores, in bytes.
+pub const MAX_ITEM_SIZE: usize = 400 * 1024;
+
+/// The largest batch request DynamoDB accepts, in bytes.
+pub const MAX_REQUEST_SIZE: usize = 16 * 1024 * 1024;
+
+/// The value of a key attribute, compared the way DynamoDB compares keys: numbers by value, so
+/// that `1` and `1.0` are the same key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum KeyPart {
+    Missing,
+    S(String),
+    N(Number),
+    B(Vec<u8>),
+    /// A value DynamoDB won't accept in a key, compared by its debug form
+    Other(String),
+}
+
+impl KeyPart {
+    fn new(value: Option<&AttributeValue>) -> Self {
+        let value = match value {
+            Some(value) => value,
+            None => return KeyPart::Missing,
+        };
+        if let Some(s) = &value.s {
+            return KeyPart::S(s.clone());
+        }
+        if let Some(n) = value.n.as_ref().and_then(|n| Number::parse(n).ok()) {
+            return KeyPart::N(n);
+        }
+        if let Some(b) = &value.b {
+            return KeyPart::B(b.to_vec());
+        }
+        KeyPart::Other(format!("{:?}", value))
+    }
+}
+
+/// The values of an item's key attributes, in a form that can be compared and hashed.
+pub(crate) type ComparableKey = Vec<KeyPart>;
+
+/// The comparable form of `item`'s key, made of the attributes in `key_names`.
+pub(crate) fn comparable_key(item: &Item, key_names: &[String]) -> ComparableKey {
+    key_names
+        .iter()
+        .map(|name| KeyPart::new(item.get(name)))
+        .collect()
+}
+
+type Names = Option<HashMap<String, String>>;
+type Values = Option<HashMap<String, AttributeValue>>;
+
+/// The placeholder maps shared by an action's expressions, or `None` where they are empty.
+///
+/// Fails if two expressions use the same placeholder for different things.
+pub(crate) fn placeholders(expressions: &[Option<&Expression>]) -> Result<(Names, Values)> {
+    let mut names = HashMap::new();
+    let mut values = HashMap::new();
+    for expression in expressions.iter().flatten() {
+        for (placeholder, name) in &expression.names {
+            if names.insert(placeholder, name).is_some_and(|n| n != name) {
+                return Err(conflict(placeholder));
+            }
+        }
+        for (placeholder, value) in &expression.values {
+            if values
+                .insert(placeholder, value)
+                .is_some_and(|v| v != value)
+            {
+                return Err(conflict(placeholder));
+            }
+        }
+    }
+    let names = Some(names).filter(|names| !names.is_empty()).map(|names| {
+        names
+            .into_iter()
+            .map(|(p, n)| (p.clone(), n.clone()))
+            .collect()
+    });
+    let values = Some(values)
+        .filter(|values| !values.is_empty())
+        .map(|values| {
+            values
+                .into_iter()
+                .map(|(p, v)| (p.clone(), v.clone()))
+                .collect()
+        });
+    Ok((names, values))
+}
+
+fn conflict(placeholder: &str) -> crate::Error {
+    ErrorImpl::InvalidExpression(format!(
+        "placeholder {} stands for different things in the update and its condition",
+        placeholder
+    ))
+    .into()
+}
@@ -0,0 +1,335 @@
+// The errors are rusoto's, which are large, passed along as its calls return them
+#![allow(clippy::result_large_err)]
+
+use crate::{from_item, Item};
+use futures_util::future::{self, BoxFuture, FutureExt};
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::{ConsumedCapacity, DynamoDb, QueryError, QueryInput, ScanError, ScanInput};
+use serde::de::DeserializeOwned;
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+
+/// Streams of the items of Query and Scan calls, following `LastEvaluatedKey` from page to page.
+///
+/// It is implemented for every [`DynamoDb`] client that can be shared between threads, such as
+/// `DynamoDbClient`. Each page is requested when the stream reaches it, and its items are
+/// deserialized with [`from_item`].
+///
+/// The input's `Limit` is the size of each page, as it is for DynamoDB. To stop after a number
+/// of items across pages, use [`Items::max_items`].
+///
+/// ```
+/// # use rusoto_dynamodb::{DynamoDb, QueryInput};
+/// use futures::TryStreamExt;
+/// use serde_dynamo::client::Paginate;
+/// # use serde_derive::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # struct Post { author: String, title: String }
+/// #
+/// # async fn list<D: DynamoDb + Sync>(client: &D) -> Result<(), Box<dyn std::error::Error>> {
+/// # let input = QueryInput::default();
+///
+/// let mut posts = client.query_items::<Post>(input).max_items(50);
+/// while let Some(post) = posts.try_next().await? {
+///     println!("{}", post.title);
+/// }
+/// println!("{} capacity units", posts.capacity_units());
+/// # Ok(())
+/// # }
+/// ```
+pub trait Paginate {
+    /// The items of a Query, across every page.
+    fn query_items<T>(&self, input: QueryInput) -> Items<'_, T, QueryError>
+    where
+        T: DeserializeOwned;
+
+    /// The items of a Scan, across every page.
+    fn scan_items<T>(&self, input: ScanInput) -> Items<'_, T, ScanError>
+    where
+        T: DeserializeOwned;
+
+    /// The items of a parallel Scan, split into `total_segments` segments that are read
+    /// concurrently.
+    ///
+    /// Items arrive in whichever order their segments return them.
+    fn parallel_scan_items<T>(
+        &self,
+        input: ScanInput,
+        total_segments: u32,
+    ) -> Items<'_, T, ScanError>
+    where
+        T: DeserializeOwned;
+}
+
+impl<D> Paginate for D
+where
+    D: DynamoDb + Sync + ?Sized,
+{
+    fn query_items<T>(&self, input: QueryInput) -> Items<'_, T, QueryError>
+    where
+        T: DeserializeOwned,
+    {
+        Items::new(|progress| query(self, input, progress))
+    }
+
+    fn scan_items<T>(&self, input: ScanInput) -> Items<'_, T, ScanError>
+    where
+        T: DeserializeOwned,
+    {
+        Items::new(|progress| scan(self, input, progress))
+    }
+
+    fn parallel_scan_items<T>(
+        &self,
+        input: ScanInput,
+        total_segments: u32,
+    ) -> Items<'_, T, ScanError>
+    where
+        T: DeserializeOwned,
+    {
+        Items::new(|progress| {
+            let segments = (0..total_segments.max(1)).map(|segment| {
+                let input = ScanInput {
+                    segment: Some(segment as i64),
+                    total_segments: Some(total_segments.max(1) as i64),
+                    ..input.clone()
+                };
+                scan(self, input, progress.clone())
+            });
+            stream::select_all(segments).boxed()
+        })
+    }
+}
+
+fn query<'a, D>(
+    client: &'a D,
+    input: QueryInput,
+    progress: Arc<Mutex<Progress>>,
+) -> BoxStream<'a, Result<Item, RusotoError<QueryError>>>
+where
+    D: DynamoDb + Sync + ?Sized,
+{
+    let page_size = input.limit;
+    paginate(progress, page_size, move |start, limit| {
+        let request = client.query(QueryInput {
+            exclusive_start_key: start,
+            limit,
+            ..input.clone()
+        });
+        request
+            .map(|output| {
+                output.map(|output| Page {
+                    items: output.items.unwrap_or_default(),
+                    last_evaluated_key: output.last_evaluated_key,
+                    consumed_capacity: output.consumed_capacity,
+                })
+            })
+            .boxed()
+    })
+}
+
+fn scan<'a, D>(
+    client: &'a D,
+    input: ScanInput,
+    progress: Arc<Mutex<Progress>>,
+) -> BoxStream<'a, Result<Item, RusotoError<ScanError>>>
+where
+    D: DynamoDb + Sync + ?Sized,
+{
+    let page_size = input.limit;
+    paginate(progress, page_size, move |start, limit| {
+        let request = client.scan(ScanInput {
+            exclusive_start_key: start,
+            limit,
+            ..input.clone()
+        });
+        request
+            .map(|output| {
+                output.map(|output| Page {
+                    items: output.items.unwrap_or_default(),
+                    last_evaluated_key: output.last_evaluated_key,
+                    consumed_capacity: output.consumed_capacity,
+                })
+            })
+            .boxed()
+    })
+}
+
+/// A stream of the items of a Query or Scan, deserialized as `T`.
+///
+/// Made by the methods of [`Paginate`]. It stops at the first failed call, after yielding its
+/// error. An item that can't be deserialized yields an error of its own, without stopping the
+/// stream.
+pub struct Items<'a, T, E> {
+    stream: BoxStream<'a, Result<Item, RusotoError<E>>>,
+    progress: Arc<Mutex<Progress>>,
+    item: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug, Default)]
+struct Progress {
+    /// How many more items to yield, if the stream has a maximum
+    remaining: Option<usize>,
+    consumed_capacity: Vec<ConsumedCapacity>,
+}
+
+impl<'a, T, E> Items<'a, T, E> {
+    fn new(
+        stream: impl FnOnce(Arc<Mutex<Progress>>) -> BoxStream<'a, Result<Item, RusotoError<E>>>,
+    ) -> Self {
+        let progress = Arc::new(Mutex::new(Progress::default()));
+        Items {
+            stream: stream(progress.clone()),
+            progress,
+            item: PhantomData,
+        }
+    }
+
+    /// Stop after `max_items` items, without requesting more of them than that.
+    pub fn max_items(self, max_items: usize) -> Self {
+        lock(&self.progress).remaining = Some(max_items);
+        self
+    }
+
+    /// The `ConsumedCapacity` of every page so far, if the input asked for it with
+    /// `ReturnConsumedCapacity`.
+    pub fn consumed_capacity(&self) -> Vec<ConsumedCapacity> {
+        lock(&self.progress).consumed_capacity.clone()
+    }
+
+    /// The capacity units every page so far consumed, in total.
+    pub fn capacity_units(&self) -> f64 {
+        lock(&self.progress)
+            .consumed_capacity
+            .iter()
+            .filter_map(|capacity| capacity.capacity_units)
+            .sum()
+    }
+}
+
+impl<T, E> fmt::Debug for Items<'_, T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Items")
+            .field("progress", &*lock(&self.progress))
+            .finish()
+    }
+}
+
+impl<T, E> Stream for Items<'_, T, E>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<T, ItemsError<E>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.poll_next_unpin(cx).map(|item| {
+            item.map(|item| match item {
+                Ok(item) => from_item(item).map_err(ItemsError::Item),
+                Err(err) => Err(ItemsError::Request(err)),
+            })
+        })
+    }
+}
+
+/// Why [`Items`] couldn't yield an item.
+#[derive(Debug, PartialEq)]
+pub enum ItemsError<E> {
+    /// Requesting a page failed
+    Request(RusotoError<E>),
+    /// An item couldn't be deserialized
+    Item(crate::Error),
+}
+
+impl<E: StdError + 'static> Display for ItemsError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemsError::Request(err) => Display::fmt(err, f),
+            ItemsError::Item(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for ItemsError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            ItemsError::Request(err) => Some(err),
+            ItemsError::Item(err) => Some(err),
+        }
+    }
+}
+
+struct Page {
+    items: Vec<Item>,
+    last_evaluated_key: Option<Item>,
+    consumed_capacity: Option<ConsumedCapacity>,
+}
+
+enum State {
+    Start,
+    Next(Item),
+    Done,
+}
+
+/// Request pages with `fetch`, starting each from the last one's `LastEvaluatedKey`, until there
+/// are no more or `progress` has no items left.
+fn paginate<'a, E, F>(
+    progress: Arc<Mutex<Progress>>,
+    page_size: Option<i64>,
+    mut fetch: F,
+) -> BoxStream<'a, Result<Item, RusotoError<E>>>
+where
+    E: Send + 'a,
+    F: FnMut(Option<Item>, Option<i64>) -> BoxFuture<'a, Result<Page, RusotoError<E>>> + Send + 'a,
+{
+    stream::unfold(State::Start, move |state| {
+        let start = match state {
+            State::Start => None,
+            State::Next(key) => Some(key),
+            State::Done => return future::ready(None).left_future(),
+        };
+        // Don't ask for more items than the stream has left to yield
+        let remaining = lock(&progress).remaining;
+        if remaining == Some(0) {
+            return future::ready(None).left_future();
+        }
+        let limit = match (page_size, remaining) {
+            (Some(size), Some(remaining)) => Some(size.min(remaining as i64)),
+            (None, Some(remaining)) => Some(remaining as i64),
+            (size, None) => size,
+        };
+
+        let progress = progress.clone();
+        fetch(start, limit)
+            .map(move |page| {
+                let page = match page {
+                    Ok(page) => page,
+                    Err(err) => return Some((vec![Err(err)], State::Done)),
+                };
+                let mut progress = lock(&progress);
+                progress.consumed_capacity.extend(page.consumed_capacity);
+                let mut items = page.items;
+                if let Some(remaining) = &mut progress.remaining {
+                    // Other segments of a parallel scan may have used some up meanwhile
+                    items.truncate(*remaining);
+                    *remaining -= items.len();
+                }
+                let state = page.last_evaluated_key.map_or(State::Done, State::Next);
+                Some((items.into_iter().map(Ok).collect(), state))
+            })
+            .right_future()
+    })
+    .flat_map(stream::iter)
+    .boxed()
+}
+
+fn lock(progress: &Mutex<Progress>) -> MutexGuard<'_, Progress> {
+    progress
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,352 @@
+// The errors are rusoto's, which are large, passed along as its calls return them
+#![allow(clippy::result_large_err)]
+
+use super::{placeholders, ItemsError, Paginate};
+use crate::error::ErrorImpl;
+use crate::expression::{diff, Condition, KeyCondition};
+use crate::{from_item, to_item, Item};
+use futures_util::stream::TryStreamExt;
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::{
+    DeleteItemError, DeleteItemInput, DynamoDb, GetItemError, GetItemInput, PutItemError,
+    PutItemInput, QueryError, QueryInput, UpdateItemError, UpdateItemInput,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+
+/// The key attributes of a table: a partition key, and optionally a sort key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySchema {
+    /// The partition key's attribute name
+    pub partition_key: String,
+    /// The sort key's attribute name, if the table has one
+    pub sort_key: Option<String>,
+}
+
+impl KeySchema {
+    /// A key of only a partition key.
+    pub fn new(partition_key: impl Into<String>) -> Self {
+        KeySchema {
+            partition_key: partition_key.into(),
+            sort_key: None,
+        }
+    }
+
+    /// Add a sort key to the key.
+    pub fn sort_key(mut self, sort_key: impl Into<String>) -> Self {
+        self.sort_key = Some(sort_key.into());
+        self
+    }
+
+    fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.partition_key.as_str()).chain(self.sort_key.as_deref())
+    }
+}
+
+/// A table whose items are `T`s, read and written through a rusoto client.
+///
+/// Items and keys are converted with [`to_item`] and [`from_item`], and keys are checked against
+/// the table's [`KeySchema`] before they are sent. Every method fails with a [`TableError`].
+///
+/// ```
+/// # use rusoto_dynamodb::DynamoDb;
+/// use serde_dynamo::client::{KeySchema, Table};
+/// # use serde_derive::{Deserialize, Serialize};
+/// #
+/// # #[derive(Serialize, Deserialize)]
+/// # struct User { id: String, name: String, age: u8 }
+/// # #[derive(Serialize)]
+/// # struct UserKey<'a> { id: &'a str }
+/// # #[derive(Serialize)]
+/// # struct Birthday { age: u8 }
+/// #
+/// # async fn birthday<D: DynamoDb + Sync>(client: D) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let users = Table::<User, _>::new(client, "users", KeySchema::new("id"));
+/// if let Some(user) = users.get(UserKey { id: "arthur" }).await? {
+///     users
+///         .update(UserKey { id: &user.id }, Birthday { age: user.age + 1 })
+///         .await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Table<T, D> {
+    client: D,
+    table_name: String,
+    key: KeySchema,
+    consistent_read: bool,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<T, D> fmt::Debug for Table<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("table_name", &self.table_name)
+            .field("key", &self.key)
+            .field("consistent_read", &self.consistent_read)
+            .finish()
+    }
+}
+
+impl<T, D> Table<T, D>
+where
+    T: Serialize + DeserializeOwned,
+    D: DynamoDb + Sync,
+{
+    /// The table `table_name`, with the given key.
+    pub fn new(client: D, table_name: impl Into<String>, key: KeySchema) -> Self {
+        Table {
+            client,
+            table_name: table_name.into(),
+            key,
+            consistent_read: false,
+            item: PhantomData,
+        }
+    }
+
+    /// Make strongly consistent reads, instead of eventually consistent ones.
+    pub fn consistent_read(mut self, consistent_read: bool) -> Self {
+        self.consistent_read = consistent_read;
+        self
+    }
+
+    /// The client the table is read and written with.
+    pub fn client(&self) -> &D {
+        &self.client
+    }
+
+    /// The item with the given key, if there is one.
+    pub async fn get<K: Serialize>(&self, key: K) -> Result<Option<T>, TableError> {
+        let key = self.key(key)?;
+        let output = self
+            .client
+            .get_item(GetItemInput {
+                table_name: self.table_name.clone(),
+                key,
+                consistent_read: Some(self.consistent_read),
+                ..GetItemInput::default()
+            })
+            .await?;
+        Ok(output.item.map(from_item).transpose()?)
+    }
+
+    /// Store `item`, replacing any item with the same key.
+    pub async fn put(&self, item: &T) -> Result<(), TableError> {
+        let item = self.item(item)?;
+        self.client
+            .put_item(PutItemInput {
+                table_name: self.table_name.clone(),
+                item,
+                ..PutItemInput::default()
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Store `item`, unless an item with the same key exists.
+    ///
+    /// Fails with [`TableError::ConditionalCheckFailed`] if one does.
+    pub async fn put_if_absent(&self, item: &T) -> Result<(), TableError> {
+        let item = self.item(item)?;
+        let condition = Condition::attribute_not_exists(self.key.partition_key.as_str()).build()?;
+        self.client
+            .put_item(PutItemInput {
+                table_name: self.table_name.clone(),
+                item,
+                expression_attribute_names: condition.expression_attribute_names(),
+                expression_attribute_values: condition.expression_attribute_values(),
+                condition_expression: Some(condition.expression),
+                ..PutItemInput::default()
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Delete the item with the given key, returning it if there was one.
+    pub async fn delete<K: Serialize>(&self, key: K) -> Result<Option<T>, TableError> {
+        let key = self.key(key)?;
+        let output = self
+            .client
+            .delete_item(DeleteItemInput {
+                table_name: self.table_name.clone(),
+                key,
+                return_values: Some(String::from("ALL_OLD")),
+                ..DeleteItemInput::default()
+            })
+            .await?;
+        Ok(output.attributes.map(from_item).transpose()?)
+    }
+
+    /// Set the attributes of `patch` on the item with the given key, and return the updated item.
+    ///
+    /// Attributes missing from `patch` are left as they are. The item has to exist already: if it
+    /// doesn't, this fails with [`TableError::ConditionalCheckFailed`], instead of creating an
+    /// item with only the patched attributes.
+    pub async fn update<K, P>(&self, key: K, patch: P) -> Result<T, TableError>
+    where
+        K: Serialize,
+        P: Serialize,
+    {
+        let key = self.key(key)?;
+        let patch = to_item(patch)?;
+        if let Some(name) = self.key.names().find(|name| patch.contains_key(*name)) {
+            return Err(TableError::Item(
+                ErrorImpl::Validation(format!(
+                    "One or more parameter values were invalid: Cannot update attribute {}. This attribute is part of the key",
+                    name
+                ))
+                .into(),
+            ));
+        }
+
+        let mut patched = key.clone();
+        patched.extend(patch);
+        let update = diff(&key, &patched)?;
+        let condition = Condition::attribute_exists(self.key.partition_key.as_str()).build()?;
+        let (names, values) = placeholders(&[update.as_ref(), Some(&condition)])?;
+        let output = self
+            .client
+            .update_item(UpdateItemInput {
+                table_name: self.table_name.clone(),
+                key,
+                update_expression: update.map(|update| update.expression),
+                condition_expression: Some(condition.expression),
+                expression_attribute_names: names,
+                expression_attribute_values: values,
+                return_values: Some(String::from("ALL_NEW")),
+                ..UpdateItemInput::default()
+            })
+            .await?;
+        Ok(from_item(output.attributes.unwrap_or_default())?)
+    }
+
+    /// Every item matching `key_condition`, across all pages.
+    pub async fn query(&self, key_condition: KeyCondition) -> Result<Vec<T>, TableError> {
+        let mut input = QueryInput {
+            table_name: self.table_name.clone(),
+            consistent_read: Some(self.consistent_read),
+            ..QueryInput::default()
+        };
+        key_condition.apply_to(&mut input)?;
+        Ok(self.client.query_items(input).try_collect().await?)
+    }
+
+    /// Convert a key, checking that it has exactly the table's key attributes.
+    fn key<K: Serialize>(&self, key: K) -> crate::Result<Item> {
+        let key = to_item(key)?;
+        if key.len() != self.key.names().count() || !self.key.names().all(|n| key.contains_key(n)) {
+            return Err(ErrorImpl::Validation(String::from(
+                "The provided key element does not match the schema",
+            ))
+            .into());
+        }
+        Ok(key)
+    }
+
+    /// Convert an item, checking that it has the table's key attributes.
+    fn item(&self, item: &T) -> crate::Result<Item> {
+        let item = to_item(item)?;
+        if let Some(name) = self.key.names().find(|name| !item.contains_key(*name)) {
+            return Err(ErrorImpl::Validation(format!(
+                "One or more parameter values were invalid: Missing the key {} in the item",
+                name
+            ))
+            .into());
+        }
+        Ok(item)
+    }
+}
+
+/// Why a [`Table`] call failed.
+#[derive(Debug)]
+pub enum TableError {
+    /// The condition of a write failed, such as [`Table::put_if_absent`] finding an item
+    ConditionalCheckFailed(String),
+    /// The table doesn't exist
+    ResourceNotFound(String),
+    /// DynamoDB throttled the request: `ProvisionedThroughputExceeded` or `RequestLimitExceeded`
+    Throttled(String),
+    /// An item or key couldn't be converted, or doesn't match the table's key
+    Item(crate::Error),
+    /// The request failed in any other way
+    Request(Box<dyn StdError + Send + Sync>),
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::ConditionalCheckFailed(message)
+            | TableError::ResourceNotFound(message)
+            | TableError::Throttled(message) => f.write_str(message),
+            TableError::Item(err) => Display::fmt(err, f),
+            TableError::Request(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl StdError for TableError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            TableError::Item(err) => Some(err),
+            TableError::Request(err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
+
+impl From<crate::Error> for TableError {
+    fn from(err: crate::Error) -> Self {
+        TableError::Item(err)
+    }
+}
+
+impl From<ItemsError<QueryError>> for TableError {
+    fn from(err: ItemsError<QueryError>) -> Self {
+        match err {
+            ItemsError::Request(err) => err.into(),
+            ItemsError::Item(err) => err.into(),
+        }
+    }
+}
+
+macro_rules! from_rusoto_error {
+    ($($error:ident $({ $conditional:ident })?),*) => {
+        $(
+            impl From<RusotoError<$error>> for TableError {
+                fn from(err: RusotoError<$error>) -> Self {
+                    match err {
+                        $(RusotoError::Service($error::$conditional(message)) => {
+                            TableError::ConditionalCheckFailed(message)
+                        })?
+                        RusotoError::Service($error::ResourceNotFound(message)) => {
+                            TableError::ResourceNotFound(message)
+                        }
+                        RusotoError::Service($error::ProvisionedThroughputExceeded(message))
+                        | RusotoError::Service($error::RequestLimitExceeded(message)) => {
+                            TableError::Throttled(message)
+                        }
+                        err => TableError::Request(Box::new(err)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_rusoto_error!(
+    GetItemError,
+    PutItemError {
+        ConditionalCheckFailed
+    },
+    DeleteItemError {
+        ConditionalCheckFailed
+    },
+    UpdateItemError {
+        ConditionalCheckFailed
+    },
+    QueryError
+);
@@ -0,0 +1,770 @@
+use super::*;
+use crate::expression::{Condition, Expression, KeyCondition, SortKeyCondition};
+use crate::mock::MockDynamoDb;
+use crate::to_attribute_value;
+use futures::{StreamExt, TryStreamExt};
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::{
+    AttributeDefinition, BatchGetItemError, CreateTableInput, DynamoDb, KeySchemaElement,
+    PutItemInput, QueryError, QueryInput, ScanInput, TransactWriteItemsError,
+};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+use std::future::Future;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Post {
+    author: String,
+    at: u32,
+}
+
+fn post(author: &str, at: u32) -> Post {
+    Post {
+        author: author.to_string(),
+        at,
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+fn create_table(dynamodb: &MockDynamoDb, table_name: &str, key: &[(&str, &str)]) {
+    dynamodb
+        .add_table(CreateTableInput {
+            table_name: table_name.to_string(),
+            key_schema: key
+                .iter()
+                .zip(&["HASH", "RANGE"])
+                .map(|((name, _), key_type)| KeySchemaElement {
+                    attribute_name: name.to_string(),
+                    key_type: key_type.to_string(),
+                })
+                .collect(),
+            attribute_definitions: key
+                .iter()
+                .map(|(name, attribute_type)| AttributeDefinition {
+                    attribute_name: name.to_string(),
+                    attribute_type: attribute_type.to_string(),
+                })
+                .collect(),
+            ..CreateTableInput::default()
+        })
+        .unwrap();
+}
+
+fn posts() -> MockDynamoDb {
+    let dynamodb = MockDynamoDb::new();
+    create_table(&dynamodb, "posts", &[("author", "S"), ("at", "N")]);
+    dynamodb
+}
+
+fn fast() -> Backoff {
+    Backoff {
+        base: Duration::from_millis(1),
+        max_delay: Duration::from_millis(1),
+        max_attempts: 10,
+    }
+}
+
+fn writer() -> BatchWriter {
+    BatchWriter::new()
+        .key("posts", "author", Some("at"))
+        .backoff(Backoff {
+            base: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            max_attempts: 3,
+        })
+}
+
+#[test]
+fn backoff_delays() {
+    let backoff = Backoff::default();
+    for retry in 0..40 {
+        let cap = (backoff.base * 2u32.pow(retry.min(20))).min(backoff.max_delay);
+        assert!(backoff.delay(retry) <= cap);
+    }
+}
+
+#[test]
+fn batch_write_chunks() {
+    let mut batch = writer();
+    for at in 0..60 {
+        batch.put("posts", post("arthur", at)).unwrap();
+    }
+    let sizes: Vec<_> = batch
+        .chunks()
+        .iter()
+        .map(|chunk| chunk.request_items["posts"].len())
+        .collect();
+    assert_eq!(sizes, vec![25, 25, 10]);
+
+    // Writes to one key go to separate calls, in order
+    let mut batch = writer();
+    batch.put("posts", post("arthur", 1)).unwrap();
+    batch.put("posts", post("arthur", 2)).unwrap();
+    batch
+        .delete("posts", json!({ "author": "arthur", "at": 1 }))
+        .unwrap();
+    batch.put("posts", post("arthur", 3)).unwrap();
+    let chunks = batch.chunks();
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].request_items["posts"].len(), 3);
+    assert!(chunks[1].request_items["posts"][0].delete_request.is_some());
+}
+
+#[test]
+fn comparable_keys_compare_numbers_by_value() {
+    let key = |at: &str| -> Item {
+        let mut key = crate::to_item(json!({ "author": "arthur" })).unwrap();
+        key.insert(
+            String::from("at"),
+            AttributeValue {
+                n: Some(at.to_string()),
+                ..AttributeValue::default()
+            },
+        );
+        key
+    };
+    let names = [String::from("author"), String::from("at")];
+    assert_eq!(
+        comparable_key(&key("1"), &names),
+        comparable_key(&key("1.0"), &names)
+    );
+    assert_eq!(
+        comparable_key(&key("100"), &names),
+        comparable_key(&key("1E2"), &names)
+    );
+    assert_ne!(
+        comparable_key(&key("1"), &names),
+        comparable_key(&key("10"), &names)
+    );
+}
+
+#[test]
+fn batch_write_errors() {
+    let mut batch = writer();
+    assert_eq!(
+        batch
+            .put("users", post("arthur", 1))
+            .unwrap_err()
+            .to_string(),
+        "No key declared for table users"
+    );
+    assert_eq!(
+        batch
+            .put("posts", json!({ "author": "arthur" }))
+            .unwrap_err()
+            .to_string(),
+        "One or more parameter values were invalid: Missing the key at in the item"
+    );
+    assert!(batch
+        .delete("posts", json!({ "author": "arthur", "at": 1, "likes": 2 }))
+        .is_err());
+    let large = json!({ "author": "arthur", "at": 1, "body": "x".repeat(MAX_ITEM_SIZE) });
+    assert_eq!(
+        batch.put("posts", large).unwrap_err().to_string(),
+        "Item size has exceeded the maximum allowed size"
+    );
+    assert!(batch.is_empty());
+}
+
+#[test]
+fn batch_write_retries_unprocessed_items() {
+    let dynamodb = posts();
+    dynamodb.limit_batches(Some(10));
+
+    let mut batch = writer().backoff(fast());
+    for at in 0..60 {
+        batch.put("posts", post("arthur", at)).unwrap();
+    }
+    batch
+        .delete("posts", json!({ "author": "arthur", "at": 0 }))
+        .unwrap();
+    let report = block_on(batch.execute(&dynamodb));
+    assert!(report.is_complete(), "{:?}", report.failed);
+    assert_eq!(report.written, 61);
+    assert_eq!(dynamodb.items("posts").unwrap().len(), 59);
+}
+
+#[test]
+fn batch_write_reports_failures() {
+    let dynamodb = posts();
+    dynamodb.limit_batches(Some(0));
+    let mut batch = writer();
+    batch.put("posts", post("arthur", 1)).unwrap();
+    let report = block_on(batch.execute(&dynamodb));
+    assert_eq!(report.written, 0);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].reason, WriteFailure::Unprocessed);
+
+    let mut batch = writer().key("users", "id", None);
+    batch.put("posts", post("arthur", 1)).unwrap();
+    batch.put("users", json!({ "id": "arthur" })).unwrap();
+    dynamodb.limit_batches(None);
+    let report = block_on(batch.execute(&dynamodb));
+    assert_eq!(report.written, 0);
+    assert_eq!(report.failed.len(), 2);
+    assert_eq!(
+        report.failed[0].reason,
+        WriteFailure::Rejected(String::from("Requested resource not found"))
+    );
+}
+
+#[test]
+fn batch_get_across_tables() {
+    let dynamodb = posts();
+    create_table(&dynamodb, "users", &[("id", "S")]);
+    let mut batch = writer().key("users", "id", None);
+    for at in 0..150 {
+        batch.put("posts", post("arthur", at)).unwrap();
+    }
+    batch
+        .put("users", json!({ "id": "arthur", "age": 42 }))
+        .unwrap();
+    assert!(block_on(batch.execute(&dynamodb)).is_complete());
+
+    // Throttle the reads, so every call leaves keys unprocessed
+    dynamodb.limit_batches(Some(30));
+    let mut batch = BatchGetter::new().backoff(fast());
+    let post_keys: Vec<_> = (140..160)
+        .chain(0..130)
+        .chain(Some(0))
+        .map(|at| json!({ "author": "arthur", "at": at }))
+        .collect();
+    let posts = batch.table::<_, Post>("posts", post_keys).unwrap();
+    let users = batch
+        .table::<_, serde_json::Value>(
+            "users",
+            vec![json!({ "id": "arthur" }), json!({ "id": "ford" })],
+        )
+        .unwrap();
+    let mut results = block_on(batch.execute(&dynamodb)).unwrap();
+
+    let posts = results.take(posts).unwrap();
+    assert_eq!(posts.len(), 151);
+    assert_eq!(posts[0].0, json!({ "author": "arthur", "at": 140 }));
+    assert_eq!(posts[0].1, Some(post("arthur", 140)));
+    assert_eq!(posts[10].1, None);
+    assert_eq!(posts[150].1, Some(post("arthur", 0)));
+    assert_eq!(posts.iter().filter(|(_, post)| post.is_some()).count(), 141);
+
+    let users = results.take(users).unwrap();
+    assert_eq!(
+        users,
+        vec![
+            (
+                json!({ "id": "arthur" }),
+                Some(json!({ "id": "arthur", "age": 42 }))
+            ),
+            (json!({ "id": "ford" }), None),
+        ]
+    );
+}
+
+#[test]
+fn batch_get_errors() {
+    let dynamodb = posts();
+    let mut batch = BatchGetter::new();
+    batch
+        .table::<_, Post>("posts", vec![json!({ "author": "arthur", "at": 1 })])
+        .unwrap();
+    assert_eq!(
+        batch
+            .table::<_, Post>("posts", vec![json!({ "author": "arthur" })])
+            .unwrap_err()
+            .to_string(),
+        "The provided key element does not match the schema"
+    );
+
+    dynamodb.limit_batches(Some(0));
+    let results = block_on(batch.backoff(fast()).execute(&dynamodb)).unwrap();
+    assert!(!results.is_complete());
+    assert_eq!(
+        results.unprocessed(),
+        &[UnprocessedKey {
+            table_name: String::from("posts"),
+            key: crate::to_item(json!({ "author": "arthur", "at": 1 })).unwrap(),
+        }]
+    );
+
+    let mut batch = BatchGetter::new();
+    batch
+        .table::<_, Post>("users", vec![json!({ "id": "arthur" })])
+        .unwrap();
+    match block_on(batch.execute(&dynamodb)) {
+        Err(RusotoError::Service(BatchGetItemError::ResourceNotFound(_))) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn batch_get_keeps_partial_results() {
+    let dynamodb = posts();
+    let mut batch = writer();
+    for at in 0..50 {
+        batch.put("posts", post("arthur", at)).unwrap();
+    }
+    assert!(block_on(batch.execute(&dynamodb)).is_complete());
+
+    dynamodb.limit_batches(Some(30));
+    let mut batch = BatchGetter::new().backoff(Backoff {
+        max_attempts: 1,
+        ..fast()
+    });
+    let keys = batch
+        .table::<_, Post>(
+            "posts",
+            (0..50).map(|at| json!({ "author": "arthur", "at": at })),
+        )
+        .unwrap();
+    let mut results = block_on(batch.execute(&dynamodb)).unwrap();
+    assert_eq!(results.unprocessed().len(), 20);
+    let posts = results.take(keys).unwrap();
+    assert_eq!(posts.len(), 30);
+    assert!(posts.iter().all(|(_, post)| post.is_some()));
+}
+
+#[test]
+fn batch_get_matches_numbers_by_value() {
+    let dynamodb = posts();
+    let mut item = crate::to_item(json!({ "author": "arthur" })).unwrap();
+    item.insert(
+        String::from("at"),
+        AttributeValue {
+            n: Some(String::from("1.50")),
+            ..AttributeValue::default()
+        },
+    );
+    block_on(dynamodb.put_item(PutItemInput {
+        table_name: String::from("posts"),
+        item,
+        ..PutItemInput::default()
+    }))
+    .unwrap();
+
+    let mut batch = BatchGetter::new();
+    let keys = batch
+        .table::<_, serde_json::Value>("posts", vec![json!({ "author": "arthur", "at": 1.5 })])
+        .unwrap();
+    let mut results = block_on(batch.execute(&dynamodb)).unwrap();
+    let items = results.take(keys).unwrap();
+    assert_eq!(items[0].1, Some(json!({ "author": "arthur", "at": 1.5 })));
+}
+
+#[test]
+fn batch_get_keys_of_another_getter() {
+    let dynamodb = posts();
+    let keys = |count: u32| (0..count).map(|at| json!({ "author": "arthur", "at": at }));
+
+    let mut small = BatchGetter::new();
+    small.table::<_, Post>("posts", keys(1)).unwrap();
+    let mut large = BatchGetter::new();
+    let first = large.table::<_, Post>("posts", keys(5)).unwrap();
+    let second = large.table::<_, Post>("posts", keys(5)).unwrap();
+    let third = large.table::<_, Post>("posts", keys(5)).unwrap();
+    let clone = large.clone();
+
+    let mut results = block_on(small.execute(&dynamodb)).unwrap();
+    assert_eq!(
+        results.take(first).unwrap_err().to_string(),
+        "The keys were added to a different BatchGetter"
+    );
+    let mut results = block_on(clone.execute(&dynamodb)).unwrap();
+    assert!(results.take(second).is_err());
+    let mut results = block_on(large.execute(&dynamodb)).unwrap();
+    assert_eq!(results.take(third).unwrap().len(), 5);
+}
+
+fn transact_writer() -> TransactWriter {
+    TransactWriter::new()
+        .key("posts", "author", Some("at"))
+        .key("users", "id", None)
+}
+
+#[test]
+fn transact_write_limits() {
+    let mut transaction = transact_writer();
+    for at in 0..MAX_TRANSACT_ITEMS as u32 {
+        transaction.put("posts", post("arthur", at), None).unwrap();
+    }
+    assert_eq!(
+        transaction
+            .put("posts", post("arthur", 100), None)
+            .unwrap_err()
+            .to_string(),
+        "1 validation error detected: Value at 'transactItems' failed to satisfy constraint: Member must have length less than or equal to 100"
+    );
+
+    let mut transaction = transact_writer();
+    transaction.put("posts", post("arthur", 1), None).unwrap();
+    let key = json!({ "author": "arthur", "at": 1 });
+    assert_eq!(
+        transaction
+            .delete("posts", &key, None)
+            .unwrap_err()
+            .to_string(),
+        "Transaction request cannot include multiple operations on one item"
+    );
+    transaction
+        .delete("users", json!({ "id": "arthur" }), None)
+        .unwrap();
+    assert!(transaction
+        .condition_check(
+            "posts",
+            json!({ "author": "arthur" }),
+            Expression::default()
+        )
+        .is_err());
+    assert!(transaction
+        .put("teams", json!({ "id": "arthur" }), None)
+        .is_err());
+    assert_eq!(transaction.len(), 2);
+}
+
+#[test]
+fn transact_write_merges_placeholders() {
+    let key = json!({ "author": "arthur", "at": 1 });
+    let update = crate::expression::diff(
+        &crate::to_item(post("arthur", 1)).unwrap(),
+        &crate::to_item(json!({ "author": "arthur", "at": 1, "likes": 2 })).unwrap(),
+    )
+    .unwrap()
+    .unwrap();
+    let condition = Condition::attribute_exists("author").build().unwrap();
+
+    let mut transaction = transact_writer();
+    transaction
+        .update("posts", &key, update.clone(), Some(condition.clone()))
+        .unwrap();
+    let input = transaction.input();
+    let action = input.transact_items[0].update.as_ref().unwrap();
+    let names = action.expression_attribute_names.as_ref().unwrap();
+    assert_eq!(names.len(), update.names.len() + condition.names.len());
+    assert_eq!(
+        action.expression_attribute_values,
+        update.expression_attribute_values()
+    );
+
+    let clash = Expression {
+        expression: String::from("attribute_exists(#u0)"),
+        names: vec![(String::from("#u0"), String::from("other"))]
+            .into_iter()
+            .collect(),
+        ..Expression::default()
+    };
+    assert!(transaction
+        .update(
+            "posts",
+            json!({ "author": "ford", "at": 1 }),
+            update,
+            Some(clash)
+        )
+        .is_err());
+}
+
+#[test]
+fn transact_write_and_get() {
+    let dynamodb = posts();
+    create_table(&dynamodb, "users", &[("id", "S")]);
+
+    let mut transaction = transact_writer();
+    transaction
+        .put("users", json!({ "id": "arthur", "posts": 0 }), None)
+        .unwrap();
+    transaction
+        .put(
+            "posts",
+            post("arthur", 1),
+            Some(Condition::attribute_not_exists("author").build().unwrap()),
+        )
+        .unwrap();
+    block_on(transaction.execute(&dynamodb)).unwrap();
+
+    // A failed condition cancels every action
+    let mut transaction = transact_writer();
+    transaction
+        .update(
+            "users",
+            json!({ "id": "arthur" }),
+            Expression {
+                expression: String::from("SET posts = :one"),
+                values: vec![(String::from(":one"), to_attribute_value(1).unwrap())]
+                    .into_iter()
+                    .collect(),
+                ..Expression::default()
+            },
+            None,
+        )
+        .unwrap();
+    transaction
+        .put(
+            "posts",
+            post("arthur", 1),
+            Some(Condition::attribute_not_exists("author").build().unwrap()),
+        )
+        .unwrap();
+    match block_on(transaction.execute(&dynamodb)) {
+        Err(RusotoError::Service(TransactWriteItemsError::TransactionCanceled(_))) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let mut transaction = TransactGetter::new();
+    transaction.get("users", json!({ "id": "arthur" })).unwrap();
+    transaction
+        .get("posts", json!({ "author": "arthur", "at": 1 }))
+        .unwrap();
+    transaction
+        .get("posts", json!({ "author": "arthur", "at": 2 }))
+        .unwrap();
+    assert!(transaction.get("users", json!({ "id": "arthur" })).is_err());
+
+    // -0.0 serializes as "-0", which is the same number as 0
+    let mut other = TransactGetter::new();
+    other
+        .get("posts", json!({ "author": "ford", "at": 0 }))
+        .unwrap();
+    assert!(other
+        .get("posts", json!({ "author": "ford", "at": -0.0 }))
+        .is_err());
+
+    let results = block_on(transaction.execute(&dynamodb)).unwrap();
+    let (user, first, second): (Option<serde_json::Value>, Option<Post>, Option<Post>) =
+        results.clone().items().unwrap();
+    assert_eq!(user, Some(json!({ "id": "arthur", "posts": 0 })));
+    assert_eq!(first, Some(post("arthur", 1)));
+    assert_eq!(second, None);
+
+    // Different types in one Vec
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(untagged)]
+    enum Record {
+        Post(Post),
+        User { id: String },
+    }
+    let records: Vec<Option<Record>> = results.clone().items().unwrap();
+    assert_eq!(
+        records,
+        vec![
+            Some(Record::User {
+                id: String::from("arthur")
+            }),
+            Some(Record::Post(post("arthur", 1))),
+            None,
+        ]
+    );
+    assert!(results.items::<(Option<Post>,)>().is_err());
+}
+
+fn post_query(limit: Option<i64>) -> QueryInput {
+    let key_condition = KeyCondition::partition_key("author", "arthur")
+        .build()
+        .unwrap();
+    QueryInput {
+        table_name: String::from("posts"),
+        key_condition_expression: Some(key_condition.expression.clone()),
+        expression_attribute_names: key_condition.expression_attribute_names(),
+        expression_attribute_values: key_condition.expression_attribute_values(),
+        limit,
+        return_consumed_capacity: Some(String::from("TOTAL")),
+        ..QueryInput::default()
+    }
+}
+
+fn write_posts(dynamodb: &MockDynamoDb, authors: &[&str], count: u32) {
+    let mut batch = writer();
+    for author in authors {
+        for at in 0..count {
+            batch.put("posts", post(author, at)).unwrap();
+        }
+    }
+    assert!(block_on(batch.execute(dynamodb)).is_complete());
+}
+
+#[test]
+fn query_items_across_pages() {
+    let dynamodb = posts();
+    write_posts(&dynamodb, &["arthur", "ford"], 25);
+
+    let mut items = dynamodb.query_items::<Post>(post_query(Some(10)));
+    let posts: Vec<Post> = block_on((&mut items).try_collect()).unwrap();
+    assert_eq!(
+        posts,
+        (0..25).map(|at| post("arthur", at)).collect::<Vec<_>>()
+    );
+    // Pages of 10, 10 and 5 small items, at half a unit each
+    assert_eq!(items.consumed_capacity().len(), 3);
+    assert_eq!(items.capacity_units(), 1.5);
+
+    // A maximum shrinks the last page, instead of reading more than it needs
+    let mut items = dynamodb
+        .query_items::<Post>(post_query(Some(10)))
+        .max_items(13);
+    let posts: Vec<Post> = block_on((&mut items).try_collect()).unwrap();
+    assert_eq!(posts.len(), 13);
+    assert_eq!(posts[12], post("arthur", 12));
+    assert_eq!(items.consumed_capacity().len(), 2);
+
+    let items = dynamodb.query_items::<Post>(post_query(None)).max_items(0);
+    assert!(block_on(items.collect::<Vec<_>>()).is_empty());
+}
+
+#[test]
+fn scan_items_in_parallel() {
+    let dynamodb = posts();
+    let authors = ["arthur", "ford", "trillian", "zaphod", "marvin"];
+    write_posts(&dynamodb, &authors, 10);
+    let input = ScanInput {
+        table_name: String::from("posts"),
+        limit: Some(4),
+        ..ScanInput::default()
+    };
+
+    let mut expected: Vec<_> = authors
+        .iter()
+        .flat_map(|author| (0..10).map(move |at| post(author, at)))
+        .collect();
+    expected.sort_by(|a, b| (&a.author, a.at).cmp(&(&b.author, b.at)));
+    let sorted = |mut posts: Vec<Post>| {
+        posts.sort_by(|a, b| (&a.author, a.at).cmp(&(&b.author, b.at)));
+        posts
+    };
+
+    let posts = block_on(dynamodb.scan_items::<Post>(input.clone()).try_collect());
+    assert_eq!(sorted(posts.unwrap()), expected);
+    let posts = block_on(
+        dynamodb
+            .parallel_scan_items::<Post>(input.clone(), 3)
+            .try_collect(),
+    );
+    assert_eq!(sorted(posts.unwrap()), expected);
+
+    // The maximum is shared between segments
+    let posts: Vec<Post> = block_on(
+        dynamodb
+            .parallel_scan_items::<Post>(input, 3)
+            .max_items(21)
+            .try_collect(),
+    )
+    .unwrap();
+    assert_eq!(posts.len(), 21);
+}
+
+#[test]
+fn items_errors() {
+    let dynamodb = posts();
+    write_posts(&dynamodb, &["arthur"], 3);
+
+    // Items that don't deserialize fail on their own
+    let results: Vec<_> = block_on(
+        dynamodb
+            .query_items::<(String, String)>(post_query(None))
+            .collect(),
+    );
+    assert_eq!(results.len(), 3);
+    assert!(matches!(results[0], Err(ItemsError::Item(_))));
+
+    // A failed call ends the stream
+    let mut input = post_query(None);
+    input.table_name = String::from("users");
+    let results: Vec<_> = block_on(dynamodb.query_items::<Post>(input).collect());
+    assert_eq!(results.len(), 1);
+    match &results[0] {
+        Err(ItemsError::Request(RusotoError::Service(QueryError::ResourceNotFound(_)))) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct User {
+    id: String,
+    name: String,
+    age: u8,
+}
+
+fn arthur() -> User {
+    User {
+        id: String::from("arthur"),
+        name: String::from("Arthur Dent"),
+        age: 42,
+    }
+}
+
+#[test]
+fn table_reads_and_writes() {
+    let dynamodb = MockDynamoDb::new();
+    create_table(&dynamodb, "users", &[("id", "S")]);
+    let users = Table::<User, _>::new(dynamodb.clone(), "users", KeySchema::new("id"));
+    let key = json!({ "id": "arthur" });
+
+    block_on(async {
+        assert_eq!(users.get(&key).await.unwrap(), None);
+        users.put_if_absent(&arthur()).await.unwrap();
+        assert_eq!(users.get(&key).await.unwrap(), Some(arthur()));
+        match users.put_if_absent(&arthur()).await {
+            Err(TableError::ConditionalCheckFailed(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let updated = users.update(&key, json!({ "age": 43 })).await.unwrap();
+        assert_eq!(updated.age, 43);
+        assert_eq!(updated.name, "Arthur Dent");
+        match users
+            .update(json!({ "id": "ford" }), json!({ "age": 1 }))
+            .await
+        {
+            Err(TableError::ConditionalCheckFailed(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        users.put(&arthur()).await.unwrap();
+        assert_eq!(users.delete(&key).await.unwrap(), Some(arthur()));
+        assert_eq!(users.delete(&key).await.unwrap(), None);
+    });
+    assert!(dynamodb.items("users").unwrap().is_empty());
+}
+
+#[test]
+fn table_queries() {
+    let dynamodb = posts();
+    write_posts(&dynamodb, &["arthur", "ford"], 5);
+    let posts = Table::<Post, _>::new(dynamodb, "posts", KeySchema::new("author").sort_key("at"));
+    let key_condition =
+        KeyCondition::partition_key("author", "ford").sort_key("at", SortKeyCondition::ge(3));
+    let found = block_on(posts.query(key_condition)).unwrap();
+    assert_eq!(found, vec![post("ford", 3), post("ford", 4)]);
+}
+
+#[test]
+fn table_errors() {
+    let dynamodb = MockDynamoDb::new();
+    let users = Table::<User, _>::new(dynamodb, "users", KeySchema::new("id"));
+    block_on(async {
+        match users.get(json!({ "id": "arthur" })).await {
+            Err(TableError::ResourceNotFound(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match users.get(json!({ "id": "arthur", "age": 1 })).await {
+            Err(TableError::Item(err)) => assert_eq!(
+                err.to_string(),
+                "The provided key element does not match the schema"
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+        match users
+            .update(json!({ "id": "arthur" }), json!({ "id": "ford" }))
+            .await
+        {
+            Err(err @ TableError::Item(_)) => assert_eq!(
+                err.to_string(),
+                "One or more parameter values were invalid: Cannot update attribute id. This attribute is part of the key"
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+    });
+}
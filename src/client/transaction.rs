@@ -0,0 +1,470 @@
+use super::{comparable_key, placeholders, ComparableKey};
+use crate::error::ErrorImpl;
+use crate::expression::Expression;
+use crate::{from_item, Item, Result};
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::{
+    ConditionCheck, Delete, DynamoDb, Get, Put, TransactGetItem, TransactGetItemsError,
+    TransactGetItemsInput, TransactGetItemsOutput, TransactWriteItem, TransactWriteItemsError,
+    TransactWriteItemsInput, TransactWriteItemsOutput, Update,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// The most actions DynamoDB accepts in one TransactWriteItems or TransactGetItems call.
+pub const MAX_TRANSACT_ITEMS: usize = 100;
+
+/// Builds a TransactWriteItems call from typed items and keys.
+///
+/// DynamoDB rejects a transaction with more than 100 actions, or with two actions on the same
+/// item. Both are checked as actions are added, instead of when the call is sent. Finding
+/// duplicates needs each table's key, so tables are declared with
+/// [`key`](TransactWriter::key) before writing to them.
+///
+/// Conditions and updates are [`Expression`]s. An update and its condition share one set of
+/// placeholder maps, which works as long as they were built with different prefixes, as the
+/// [`expression`](crate::expression) module's builders are.
+///
+/// ```
+/// # use rusoto_dynamodb::DynamoDb;
+/// use serde_dynamo::client::TransactWriter;
+/// use serde_dynamo::expression::Condition;
+/// # use serde_derive::Serialize;
+/// #
+/// # #[derive(Serialize)]
+/// # struct User { id: String, team: String }
+/// # #[derive(Serialize)]
+/// # struct Key<'a> { id: &'a str }
+/// #
+/// # async fn join(client: &dyn DynamoDb, user: User) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let mut transaction = TransactWriter::new()
+///     .key("users", "id", None)
+///     .key("teams", "id", None);
+/// transaction.condition_check(
+///     "teams",
+///     Key { id: &user.team },
+///     Condition::attribute_exists("id").build()?,
+/// )?;
+/// transaction.put(
+///     "users",
+///     &user,
+///     Some(Condition::attribute_not_exists("id").build()?),
+/// )?;
+/// transaction.execute(client).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TransactWriter {
+    keys: HashMap<String, Vec<String>>,
+    actions: Vec<TransactWriteItem>,
+    /// The table and comparable key of every action
+    written: HashSet<(String, ComparableKey)>,
+    client_request_token: Option<String>,
+}
+
+impl TransactWriter {
+    /// A transaction without any actions.
+    pub fn new() -> Self {
+        TransactWriter::default()
+    }
+
+    /// Declare the key attributes of a table.
+    pub fn key(mut self, table_name: &str, partition_key: &str, sort_key: Option<&str>) -> Self {
+        let key = std::iter::once(partition_key)
+            .chain(sort_key)
+            .map(String::from)
+            .collect();
+        self.keys.insert(table_name.to_string(), key);
+        self
+    }
+
+    /// Send `token` as the call's `ClientRequestToken`, so that retrying it is idempotent.
+    pub fn client_request_token(mut self, token: impl Into<String>) -> Self {
+        self.client_request_token = Some(token.into());
+        self
+    }
+
+    /// Add a put of `item`, if `condition` holds.
+    pub fn put<T: Serialize>(
+        &mut self,
+        table_name: &str,
+        item: T,
+        condition: Option<Expression>,
+    ) -> Result<()> {
+        let item = crate::to_item(item)?;
+        let key = self.key_of(table_name, &item, false)?;
+        let (names, values) = placeholders(&[condition.as_ref()])?;
+        self.push(
+            table_name,
+            key,
+            TransactWriteItem {
+                put: Some(Put {
+                    table_name: table_name.to_string(),
+                    item,
+                    condition_expression: condition.map(|c| c.expression),
+                    expression_attribute_names: names,
+                    expression_attribute_values: values,
+                    ..Put::default()
+                }),
+                ..TransactWriteItem::default()
+            },
+        )
+    }
+
+    /// Add an update of the item with the given key, if `condition` holds.
+    pub fn update<K: Serialize>(
+        &mut self,
+        table_name: &str,
+        key: K,
+        update: Expression,
+        condition: Option<Expression>,
+    ) -> Result<()> {
+        let key = crate::to_item(key)?;
+        let comparable = self.key_of(table_name, &key, true)?;
+        let (names, values) = placeholders(&[Some(&update), condition.as_ref()])?;
+        self.push(
+            table_name,
+            comparable,
+            TransactWriteItem {
+                update: Some(Update {
+                    table_name: table_name.to_string(),
+                    key,
+                    update_expression: update.expression,
+                    condition_expression: condition.map(|c| c.expression),
+                    expression_attribute_names: names,
+                    expression_attribute_values: values,
+                    ..Update::default()
+                }),
+                ..TransactWriteItem::default()
+            },
+        )
+    }
+
+    /// Add a delete of the item with the given key, if `condition` holds.
+    pub fn delete<K: Serialize>(
+        &mut self,
+        table_name: &str,
+        key: K,
+        condition: Option<Expression>,
+    ) -> Result<()> {
+        let key = crate::to_item(key)?;
+        let comparable = self.key_of(table_name, &key, true)?;
+        let (names, values) = placeholders(&[condition.as_ref()])?;
+        self.push(
+            table_name,
+            comparable,
+            TransactWriteItem {
+                delete: Some(Delete {
+                    table_name: table_name.to_string(),
+                    key,
+                    condition_expression: condition.map(|c| c.expression),
+                    expression_attribute_names: names,
+                    expression_attribute_values: values,
+                    ..Delete::default()
+                }),
+                ..TransactWriteItem::default()
+            },
+        )
+    }
+
+    /// Add a check that `condition` holds for the item with the given key, without writing it.
+    pub fn condition_check<K: Serialize>(
+        &mut self,
+        table_name: &str,
+        key: K,
+        condition: Expression,
+    ) -> Result<()> {
+        let key = crate::to_item(key)?;
+        let comparable = self.key_of(table_name, &key, true)?;
+        let (names, values) = placeholders(&[Some(&condition)])?;
+        self.push(
+            table_name,
+            comparable,
+            TransactWriteItem {
+                condition_check: Some(ConditionCheck {
+                    table_name: table_name.to_string(),
+                    key,
+                    condition_expression: condition.expression,
+                    expression_attribute_names: names,
+                    expression_attribute_values: values,
+                    ..ConditionCheck::default()
+                }),
+                ..TransactWriteItem::default()
+            },
+        )
+    }
+
+    /// How many actions have been added.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Whether no actions have been added.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// The TransactWriteItems call the actions are sent in.
+    pub fn input(&self) -> TransactWriteItemsInput {
+        TransactWriteItemsInput {
+            transact_items: self.actions.clone(),
+            client_request_token: self.client_request_token.clone(),
+            ..TransactWriteItemsInput::default()
+        }
+    }
+
+    /// Send the transaction.
+    ///
+    /// Nothing is retried: if any condition fails, DynamoDB cancels the whole transaction and
+    /// this returns its `TransactionCanceled` error.
+    pub async fn execute<D>(
+        self,
+        client: &D,
+    ) -> Result<TransactWriteItemsOutput, RusotoError<TransactWriteItemsError>>
+    where
+        D: DynamoDb + ?Sized,
+    {
+        client.transact_write_items(self.input()).await
+    }
+
+    /// The comparable form of `item`'s key. Keys of updates, deletes and checks have to have
+    /// exactly the key attributes.
+    fn key_of(&self, table_name: &str, item: &Item, exact: bool) -> Result<ComparableKey> {
+        let key = self.keys.get(table_name).ok_or_else(|| {
+            ErrorImpl::Message(format!("No key declared for table {}", table_name)).into()
+        })?;
+        if let Some(name) = key.iter().find(|name| !item.contains_key(*name)) {
+            return Err(ErrorImpl::Validation(format!(
+                "One or more parameter values were invalid: Missing the key {} in the item",
+                name
+            ))
+            .into());
+        }
+        if exact && item.len() != key.len() {
+            return Err(ErrorImpl::Validation(String::from(
+                "The provided key element does not match the schema",
+            ))
+            .into());
+        }
+        Ok(comparable_key(item, key))
+    }
+
+    fn push(
+        &mut self,
+        table_name: &str,
+        key: ComparableKey,
+        action: TransactWriteItem,
+    ) -> Result<()> {
+        check_capacity(self.actions.len())?;
+        if !self.written.insert((table_name.to_string(), key)) {
+            return Err(duplicate());
+        }
+        self.actions.push(action);
+        Ok(())
+    }
+}
+
+/// Builds a TransactGetItems call from typed keys, and decodes its items by position.
+///
+/// Like [`TransactWriter`], it refuses more than 100 gets, or two gets of the same item.
+///
+/// ```
+/// # use rusoto_dynamodb::DynamoDb;
+/// use serde_dynamo::client::TransactGetter;
+/// # use serde_derive::{Deserialize, Serialize};
+/// #
+/// # #[derive(Serialize)]
+/// # struct Key<'a> { id: &'a str }
+/// # #[derive(Deserialize)]
+/// # struct User { id: String, team: String }
+/// # #[derive(Deserialize)]
+/// # struct Team { id: String, name: String }
+/// #
+/// # async fn read(client: &dyn DynamoDb) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let mut transaction = TransactGetter::new();
+/// transaction.get("users", Key { id: "arthur" })?;
+/// transaction.get("teams", Key { id: "heart-of-gold" })?;
+///
+/// let results = transaction.execute(client).await?;
+/// let (user, team): (Option<User>, Option<Team>) = results.items()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TransactGetter {
+    gets: Vec<TransactGetItem>,
+    /// The table, key attribute names and key of every get
+    read: HashSet<(String, Vec<String>, ComparableKey)>,
+}
+
+impl TransactGetter {
+    /// A transaction without any gets.
+    pub fn new() -> Self {
+        TransactGetter::default()
+    }
+
+    /// Add a get of the item with the given key.
+    ///
+    /// The items are returned in the order their gets were added.
+    pub fn get<K: Serialize>(&mut self, table_name: &str, key: K) -> Result<()> {
+        let key = crate::to_item(key)?;
+        check_capacity(self.gets.len())?;
+        let mut names: Vec<_> = key.keys().cloned().collect();
+        names.sort();
+        let comparable = comparable_key(&key, &names);
+        if !self
+            .read
+            .insert((table_name.to_string(), names, comparable))
+        {
+            return Err(duplicate());
+        }
+        self.gets.push(TransactGetItem {
+            get: Get {
+                table_name: table_name.to_string(),
+                key,
+                ..Get::default()
+            },
+        });
+        Ok(())
+    }
+
+    /// How many gets have been added.
+    pub fn len(&self) -> usize {
+        self.gets.len()
+    }
+
+    /// Whether no gets have been added.
+    pub fn is_empty(&self) -> bool {
+        self.gets.is_empty()
+    }
+
+    /// The TransactGetItems call the gets are sent in.
+    pub fn input(&self) -> TransactGetItemsInput {
+        TransactGetItemsInput {
+            transact_items: self.gets.clone(),
+            ..TransactGetItemsInput::default()
+        }
+    }
+
+    /// Send the transaction.
+    pub async fn execute<D>(
+        self,
+        client: &D,
+    ) -> Result<TransactGetResults, RusotoError<TransactGetItemsError>>
+    where
+        D: DynamoDb + ?Sized,
+    {
+        let output = client.transact_get_items(self.input()).await?;
+        Ok(TransactGetResults::from(output))
+    }
+}
+
+/// The items a TransactGetItems call read, in the order they were requested.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactGetResults {
+    items: Vec<Option<Item>>,
+}
+
+impl TransactGetResults {
+    /// Decode the items by position, into a tuple of `Option`s or a `Vec` of them.
+    ///
+    /// Each element of a tuple can be a different type. A `Vec` can hold items of different
+    /// types too, with an enum that deserializes from each of them, such as an untagged one.
+    ///
+    /// Fails if a tuple doesn't have one element per item, or an item can't be deserialized as
+    /// its element.
+    pub fn items<T: FromTransactGet>(self) -> Result<T> {
+        T::from_transact_get(self.items)
+    }
+
+    /// The items as they were read.
+    pub fn into_inner(self) -> Vec<Option<Item>> {
+        self.items
+    }
+}
+
+impl From<TransactGetItemsOutput> for TransactGetResults {
+    fn from(output: TransactGetItemsOutput) -> Self {
+        TransactGetResults {
+            items: output
+                .responses
+                .unwrap_or_default()
+                .into_iter()
+                .map(|response| response.item)
+                .collect(),
+        }
+    }
+}
+
+/// Types the items of a TransactGetItems call can be decoded into, by
+/// [`TransactGetResults::items`].
+///
+/// It is implemented for `Vec<Option<T>>`, and for tuples of up to 12 `Option`s.
+pub trait FromTransactGet: Sized {
+    /// Decode `items`, in the order they were requested.
+    fn from_transact_get(items: Vec<Option<Item>>) -> Result<Self>;
+}
+
+impl<T: DeserializeOwned> FromTransactGet for Vec<Option<T>> {
+    fn from_transact_get(items: Vec<Option<Item>>) -> Result<Self> {
+        items
+            .into_iter()
+            .map(|item| item.map(from_item).transpose())
+            .collect()
+    }
+}
+
+macro_rules! impl_from_transact_get {
+    ($len:literal => $($name:ident)+) => {
+        impl<$($name: DeserializeOwned),+> FromTransactGet for ($(Option<$name>,)+) {
+            fn from_transact_get(items: Vec<Option<Item>>) -> Result<Self> {
+                if items.len() != $len {
+                    return Err(ErrorImpl::Message(format!(
+                        "Expected {} items, but the transaction read {}",
+                        $len,
+                        items.len()
+                    ))
+                    .into());
+                }
+                let mut items = items.into_iter();
+                Ok(($(
+                    items.next().flatten().map(from_item::<$name>).transpose()?,
+                )+))
+            }
+        }
+    };
+}
+
+impl_from_transact_get!(1 => A);
+impl_from_transact_get!(2 => A B);
+impl_from_transact_get!(3 => A B C);
+impl_from_transact_get!(4 => A B C D);
+impl_from_transact_get!(5 => A B C D E);
+impl_from_transact_get!(6 => A B C D E F);
+impl_from_transact_get!(7 => A B C D E F G);
+impl_from_transact_get!(8 => A B C D E F G H);
+impl_from_transact_get!(9 => A B C D E F G H I);
+impl_from_transact_get!(10 => A B C D E F G H I J);
+impl_from_transact_get!(11 => A B C D E F G H I J K);
+impl_from_transact_get!(12 => A B C D E F G H I J K L);
+
+fn check_capacity(len: usize) -> Result<()> {
+    if len >= MAX_TRANSACT_ITEMS {
+        return Err(ErrorImpl::Validation(String::from(
+            "1 validation error detected: Value at 'transactItems' failed to satisfy constraint: Member must have length less than or equal to 100",
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+fn duplicate() -> crate::Error {
+    ErrorImpl::Validation(String::from(
+        "Transaction request cannot include multiple operations on one item",
+    ))
+    .into()
+}
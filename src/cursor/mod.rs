@@ -0,0 +1,147 @@
+//! Opaque pagination cursors made from a `LastEvaluatedKey`.
+//!
+//! APIs that page through Query or Scan results have to hand the next page's starting key to
+//! their clients somehow. A [`Cursor`] turns the key into a short, URL-safe string, and turns it
+//! back into an [`Item`] for `ExclusiveStartKey` when the client returns it.
+//!
+//! A cursor is base64 of the key's [DynamoDB JSON], so anyone can read it. With a secret, the
+//! cursor is also signed with HMAC-SHA256, and decoding rejects cursors that were changed or
+//! made up, so clients can't start a page at a key of their choosing.
+//!
+//! ```
+//! use serde_dynamo::cursor::Cursor;
+//! # use serde_derive::{Deserialize, Serialize};
+//! #
+//! # #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! # struct PostKey { author: String, at: u32 }
+//! #
+//! # fn test() -> serde_dynamo::Result<()> {
+//!
+//! let cursors = Cursor::signed(b"a secret only the server knows");
+//!
+//! let key = PostKey { author: String::from("arthur"), at: 42 };
+//! let cursor = cursors.encode_key(&key)?;
+//!
+//! // Later, when the client asks for the next page
+//! let key: PostKey = cursors.decode_key(&cursor)?;
+//! assert_eq!(key.at, 42);
+//!
+//! // Cursors signed with another secret are rejected
+//! assert!(Cursor::signed(b"a guess").decode(&cursor).is_err());
+//! # Ok(())
+//! # }
+//! # test().unwrap();
+//! ```
+//!
+//! [DynamoDB JSON]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Programming.LowLevelAPI.html
+
+use crate::error::ErrorImpl;
+use crate::{from_item, to_item, Item, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Encodes keys as cursors, and decodes them back, signing them if it has a secret.
+///
+/// A signed cursor is the encoded key and its signature, separated by a `.`. A cursor encoded
+/// with one secret only decodes with the same secret, and unsigned cursors only decode without
+/// one.
+#[derive(Clone, Default)]
+pub struct Cursor {
+    secret: Option<Vec<u8>>,
+}
+
+impl fmt::Debug for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Keep the secret out of logs
+        f.debug_struct("Cursor")
+            .field("signed", &self.secret.is_some())
+            .finish()
+    }
+}
+
+impl Cursor {
+    /// Cursors without a signature.
+    pub fn unsigned() -> Self {
+        Cursor::default()
+    }
+
+    /// Cursors signed with `secret`.
+    pub fn signed(secret: impl AsRef<[u8]>) -> Self {
+        Cursor {
+            secret: Some(secret.as_ref().to_vec()),
+        }
+    }
+
+    /// Encode a key, such as a `LastEvaluatedKey`, as a cursor.
+    ///
+    /// The same key always encodes to the same cursor.
+    pub fn encode(&self, key: &Item) -> Result<String> {
+        // Sorted, so the encoding doesn't depend on the map's order
+        let sorted: BTreeMap<_, _> = key.iter().collect();
+        let json = serde_json::to_vec(&sorted).map_err(|err| -> crate::Error {
+            ErrorImpl::Message(format!("Failed to encode cursor: {}", err)).into()
+        })?;
+        let mut cursor = URL_SAFE_NO_PAD.encode(&json);
+        if let Some(mac) = self.mac(cursor.as_bytes()) {
+            cursor.push('.');
+            cursor.push_str(&URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()));
+        }
+        Ok(cursor)
+    }
+
+    /// Decode a cursor back into the key it was encoded from, for `ExclusiveStartKey`.
+    ///
+    /// Fails if the cursor isn't one, or if its signature doesn't match.
+    pub fn decode(&self, cursor: &str) -> Result<Item> {
+        let payload = match (self.mac(b""), cursor.split_once('.')) {
+            (None, None) => cursor,
+            (None, Some(_)) => return Err(invalid("unexpected signature")),
+            (Some(_), None) => return Err(invalid("missing signature")),
+            (Some(_), Some((payload, signature))) => {
+                let signature = URL_SAFE_NO_PAD
+                    .decode(signature)
+                    .map_err(|_| invalid("malformed signature"))?;
+                let mac = self.mac(payload.as_bytes()).expect("cursor has a secret");
+                mac.verify_slice(&signature)
+                    .map_err(|_| invalid("signature does not match"))?;
+                payload
+            }
+        };
+        let json = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| invalid("malformed key"))?;
+        serde_json::from_slice(&json).map_err(|_| invalid("malformed key"))
+    }
+
+    /// Encode a key struct as a cursor.
+    pub fn encode_key<K: Serialize>(&self, key: &K) -> Result<String> {
+        self.encode(&to_item(key)?)
+    }
+
+    /// Decode a cursor into a key struct.
+    pub fn decode_key<K: DeserializeOwned>(&self, cursor: &str) -> Result<K> {
+        from_item(self.decode(cursor)?)
+    }
+
+    fn mac(&self, payload: &[u8]) -> Option<HmacSha256> {
+        let secret = self.secret.as_ref()?;
+        let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC takes keys of any size");
+        mac.update(payload);
+        Some(mac)
+    }
+}
+
+fn invalid(reason: &str) -> crate::Error {
+    ErrorImpl::InvalidCursor(reason.to_string()).into()
+}
@@ -0,0 +1,100 @@
+use super::*;
+use crate::AttributeValue;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PostKey {
+    author: String,
+    at: u32,
+}
+
+fn key() -> PostKey {
+    PostKey {
+        author: String::from("arthur"),
+        at: 42,
+    }
+}
+
+#[test]
+fn round_trip() {
+    let item = to_item(key()).unwrap();
+    for cursors in &[Cursor::unsigned(), Cursor::signed("secret")] {
+        let cursor = cursors.encode(&item).unwrap();
+        assert!(cursor
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)));
+        assert_eq!(cursors.decode(&cursor).unwrap(), item);
+        assert_eq!(cursors.encode(&item.clone()).unwrap(), cursor);
+        assert_eq!(cursors.decode_key::<PostKey>(&cursor).unwrap(), key());
+    }
+
+    // Binary keys survive as well
+    let mut item = Item::new();
+    item.insert(
+        String::from("id"),
+        AttributeValue {
+            b: Some(vec![0, 159, 255].into()),
+            ..AttributeValue::default()
+        },
+    );
+    let cursors = Cursor::unsigned();
+    assert_eq!(
+        cursors.decode(&cursors.encode(&item).unwrap()).unwrap(),
+        item
+    );
+}
+
+#[test]
+fn unsigned_format() {
+    let cursor = Cursor::unsigned().encode_key(&key()).unwrap();
+    let json = URL_SAFE_NO_PAD.decode(&cursor).unwrap();
+    assert_eq!(json, br#"{"at":{"N":"42"},"author":{"S":"arthur"}}"#);
+}
+
+#[test]
+fn rejects_tampering() {
+    let signed = Cursor::signed("secret");
+    let cursor = signed.encode_key(&key()).unwrap();
+    let (_, signature) = cursor.split_once('.').unwrap();
+
+    let forged = Cursor::unsigned().encode_key(&PostKey {
+        author: String::from("ford"),
+        at: 1,
+    });
+    let forged = format!("{}.{}", forged.unwrap(), signature);
+    let message = |cursors: &Cursor, cursor: &str| cursors.decode(cursor).unwrap_err().to_string();
+    assert_eq!(
+        message(&signed, &forged),
+        "Invalid cursor: signature does not match"
+    );
+    assert_eq!(
+        message(&Cursor::signed("other"), &cursor),
+        "Invalid cursor: signature does not match"
+    );
+    assert_eq!(
+        message(&Cursor::unsigned(), &cursor),
+        "Invalid cursor: unexpected signature"
+    );
+    let unsigned = Cursor::unsigned().encode_key(&key()).unwrap();
+    assert_eq!(
+        message(&signed, &unsigned),
+        "Invalid cursor: missing signature"
+    );
+    assert_eq!(
+        message(&Cursor::unsigned(), "not a cursor!"),
+        "Invalid cursor: malformed key"
+    );
+    assert_eq!(
+        message(&signed, &format!("{}.!", unsigned)),
+        "Invalid cursor: malformed signature"
+    );
+    assert!(signed.decode_key::<PostKey>(&cursor).is_ok());
+}
+
+#[test]
+fn debug_hides_secret() {
+    assert_eq!(
+        format!("{:?}", Cursor::signed("secret")),
+        "Cursor { signed: true }"
+    );
+}
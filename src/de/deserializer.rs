@@ -34,7 +34,7 @@ macro_rules! deserialize_number {
     };
 }
 
-impl<'de, 'a> de::Deserializer<'de> for Deserializer {
+impl<'de> de::Deserializer<'de> for Deserializer {
     type Error = Error;
 
     // Look at the input data to decide what Serde data model type to
@@ -44,8 +44,8 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        if self.input.n.is_some() {
-            DeserializerNumber::from_string(self.input.n.unwrap()).deserialize_any(visitor)
+        if let Some(n) = self.input.n {
+            DeserializerNumber::from_string(n).deserialize_any(visitor)
         } else if self.input.s.is_some() {
             self.deserialize_string(visitor)
         } else if self.input.bool.is_some() {
@@ -55,7 +55,7 @@ impl DeserializerMapKey {
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for DeserializerMapKey {
+impl<'de> de::Deserializer<'de> for DeserializerMapKey {
     type Error = Error;
 
     // Look at the input data to decide what Serde data model type to
@@ -0,0 +1,159 @@
+//! Typed records of the DynamoDB Streams API, as [`rusoto_dynamodbstreams`] reads them.
+//!
+//! Workers that poll a stream with `GetRecords`, rather than run in Lambda, receive records
+//! whose `AttributeValue` is `rusoto_dynamodbstreams`' own type, which [`from_item`] doesn't
+//! take. [`StreamImages`] deserializes their keys and images directly, and a whole record
+//! converts into a typed [`stream::StreamRecord`]:
+//!
+//! ```
+//! use rusoto_dynamodbstreams::GetRecordsOutput;
+//! use serde_dynamo::dynamodbstreams::StreamImages;
+//! use serde_dynamo::stream::{Change, StreamRecord};
+//! use std::convert::TryFrom;
+//! # use serde_derive::Deserialize;
+//! #
+//! # #[derive(Deserialize)]
+//! # struct User { id: String, name: String }
+//! # #[derive(Deserialize)]
+//! # struct UserKey { id: String }
+//!
+//! fn process(output: GetRecordsOutput) -> serde_dynamo::Result<()> {
+//!     for record in output.records.unwrap_or_default() {
+//!         let key: UserKey = record.keys()?;
+//!         let record = StreamRecord::<User, UserKey>::try_from(record)?;
+//!         if let Change::Insert(Some(user)) = record.change {
+//!             println!("Welcome, {} ({})", user.name, key.id);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::error::ErrorImpl;
+use crate::stream::{self, RawRecord, StreamViewType, UserIdentity};
+use crate::{from_item, AttributeValue, Item, Result};
+use rusoto_dynamodbstreams::{AttributeValue as StreamsAttributeValue, Record, StreamRecord};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::{Duration, UNIX_EPOCH};
+
+pub use crate::stream::StreamImages;
+
+#[cfg(test)]
+mod tests;
+
+/// An item the way [`rusoto_dynamodbstreams`] represents it.
+pub type StreamsItem = HashMap<String, StreamsAttributeValue>;
+
+impl StreamImages for StreamRecord {
+    fn keys<K: DeserializeOwned>(&self) -> Result<K> {
+        image(self.keys.as_ref(), "Keys")
+    }
+
+    fn new_image<T: DeserializeOwned>(&self) -> Result<T> {
+        image(self.new_image.as_ref(), "NewImage")
+    }
+
+    fn old_image<T: DeserializeOwned>(&self) -> Result<T> {
+        image(self.old_image.as_ref(), "OldImage")
+    }
+}
+
+impl StreamImages for Record {
+    fn keys<K: DeserializeOwned>(&self) -> Result<K> {
+        image(self.dynamodb.as_ref().and_then(|r| r.keys.as_ref()), "Keys")
+    }
+
+    fn new_image<T: DeserializeOwned>(&self) -> Result<T> {
+        image(
+            self.dynamodb.as_ref().and_then(|r| r.new_image.as_ref()),
+            "NewImage",
+        )
+    }
+
+    fn old_image<T: DeserializeOwned>(&self) -> Result<T> {
+        image(
+            self.dynamodb.as_ref().and_then(|r| r.old_image.as_ref()),
+            "OldImage",
+        )
+    }
+}
+
+impl<T, K> TryFrom<Record> for stream::StreamRecord<T, K>
+where
+    T: DeserializeOwned,
+    K: DeserializeOwned,
+{
+    type Error = crate::Error;
+
+    /// Deserialize the record's keys and images.
+    ///
+    /// Fails if the record lacks an image its change and its stream's view type call for, such
+    /// as the `NewImage` of an `INSERT` to a `NEW_IMAGE` stream.
+    fn try_from(record: Record) -> Result<Self> {
+        let change = record.dynamodb.unwrap_or_default();
+        let view_type = match change.stream_view_type {
+            Some(view_type) => Some(StreamViewType::parse(&view_type).ok_or_else(
+                || -> crate::Error {
+                    ErrorImpl::InvalidStreamRecord(format!("unknown view type {}", view_type))
+                        .into()
+                },
+            )?),
+            None => None,
+        };
+        RawRecord {
+            event_id: record.event_id,
+            event_name: record.event_name.as_deref().unwrap_or_default(),
+            table_name: None,
+            sequence_number: change.sequence_number,
+            approximate_creation_time: change
+                .approximate_creation_date_time
+                .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                .map(|seconds| UNIX_EPOCH + Duration::from_secs_f64(seconds)),
+            user_identity: record.user_identity.map(|identity| UserIdentity {
+                type_: identity.type_.unwrap_or_default(),
+                principal_id: identity.principal_id.unwrap_or_default(),
+            }),
+            view_type,
+            keys: change.keys.map(from_streams_item).unwrap_or_default(),
+            new_image: change.new_image.map(from_streams_item),
+            old_image: change.old_image.map(from_streams_item),
+        }
+        .into_typed()
+    }
+}
+
+/// Deserialize one of a record's images, if it has it.
+fn image<T: DeserializeOwned>(item: Option<&StreamsItem>, name: &'static str) -> Result<T> {
+    match item {
+        Some(item) if !item.is_empty() => from_item(from_streams_item(item.clone())),
+        _ => Err(ErrorImpl::MissingImage(name).into()),
+    }
+}
+
+/// Convert an item from a stream record to rusoto_dynamodb's, without losing anything.
+pub fn from_streams_item(item: StreamsItem) -> Item {
+    item.into_iter()
+        .map(|(name, value)| (name, from_streams_value(value)))
+        .collect()
+}
+
+/// Convert an attribute value from a stream record to rusoto_dynamodb's, without losing
+/// anything.
+pub fn from_streams_value(value: StreamsAttributeValue) -> AttributeValue {
+    AttributeValue {
+        b: value.b,
+        bool: value.bool,
+        bs: value.bs,
+        l: value
+            .l
+            .map(|l| l.into_iter().map(from_streams_value).collect()),
+        m: value.m.map(from_streams_item),
+        n: value.n,
+        ns: value.ns,
+        null: value.null,
+        s: value.s,
+        ss: value.ss,
+    }
+}
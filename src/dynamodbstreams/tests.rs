@@ -0,0 +1,206 @@
+use super::*;
+use crate::stream::Change;
+use maplit::hashmap;
+use rusoto_dynamodbstreams::Identity;
+use serde_derive::Deserialize;
+use std::time::SystemTime;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct User {
+    id: String,
+    age: u8,
+    #[serde(with = "serde_bytes")]
+    avatar: Vec<u8>,
+    tags: Vec<String>,
+    address: Address,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Address {
+    lines: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct UserKey {
+    id: String,
+}
+
+fn s(s: &str) -> StreamsAttributeValue {
+    StreamsAttributeValue {
+        s: Some(s.to_string()),
+        ..StreamsAttributeValue::default()
+    }
+}
+
+fn keys() -> StreamsItem {
+    hashmap! { String::from("id") => s("arthur") }
+}
+
+fn image(age: &str) -> StreamsItem {
+    hashmap! {
+        String::from("id") => s("arthur"),
+        String::from("age") => StreamsAttributeValue {
+            n: Some(age.to_string()),
+            ..StreamsAttributeValue::default()
+        },
+        String::from("avatar") => StreamsAttributeValue {
+            b: Some(vec![0, 1, 255].into()),
+            ..StreamsAttributeValue::default()
+        },
+        String::from("tags") => StreamsAttributeValue {
+            ss: Some(vec![String::from("human")]),
+            ..StreamsAttributeValue::default()
+        },
+        String::from("address") => StreamsAttributeValue {
+            m: Some(hashmap! {
+                String::from("lines") => StreamsAttributeValue {
+                    l: Some(vec![s("155 Country Lane")]),
+                    ..StreamsAttributeValue::default()
+                },
+            }),
+            ..StreamsAttributeValue::default()
+        },
+    }
+}
+
+fn user(age: u8) -> User {
+    User {
+        id: String::from("arthur"),
+        age,
+        avatar: vec![0, 1, 255],
+        tags: vec![String::from("human")],
+        address: Address {
+            lines: vec![String::from("155 Country Lane")],
+        },
+    }
+}
+
+fn record(event_name: &str, view_type: &str, change: StreamRecord) -> Record {
+    Record {
+        aws_region: Some(String::from("us-east-1")),
+        dynamodb: Some(StreamRecord {
+            approximate_creation_date_time: Some(1479499740.5),
+            keys: Some(keys()),
+            sequence_number: Some(String::from("111")),
+            stream_view_type: Some(view_type.to_string()),
+            ..change
+        }),
+        event_id: Some(String::from("1")),
+        event_name: Some(event_name.to_string()),
+        event_source: Some(String::from("aws:dynamodb")),
+        event_version: Some(String::from("1.1")),
+        user_identity: None,
+    }
+}
+
+#[test]
+fn images() {
+    let record = record(
+        "MODIFY",
+        "NEW_AND_OLD_IMAGES",
+        StreamRecord {
+            new_image: Some(image("43")),
+            old_image: Some(image("42")),
+            ..StreamRecord::default()
+        },
+    );
+
+    assert_eq!(record.new_image::<User>().unwrap(), user(43));
+    assert_eq!(record.old_image::<User>().unwrap(), user(42));
+    assert_eq!(record.keys::<UserKey>().unwrap().id, "arthur");
+    assert_eq!(
+        record
+            .dynamodb
+            .as_ref()
+            .unwrap()
+            .new_image::<User>()
+            .unwrap(),
+        user(43)
+    );
+}
+
+#[test]
+fn missing_images() {
+    let record = record("REMOVE", "KEYS_ONLY", StreamRecord::default());
+    let err = record.old_image::<User>().unwrap_err();
+    assert_eq!(err.to_string(), "The stream record has no OldImage");
+
+    let err = Record::default().keys::<UserKey>().unwrap_err();
+    assert_eq!(err.to_string(), "The stream record has no Keys");
+}
+
+#[test]
+fn typed_records() {
+    let modify = record(
+        "MODIFY",
+        "NEW_AND_OLD_IMAGES",
+        StreamRecord {
+            new_image: Some(image("43")),
+            old_image: Some(image("42")),
+            ..StreamRecord::default()
+        },
+    );
+    let typed = stream::StreamRecord::<User, UserKey>::try_from(modify).unwrap();
+    assert_eq!(
+        typed.change,
+        Change::Modify {
+            old: Some(user(42)),
+            new: Some(user(43)),
+        }
+    );
+    assert_eq!(typed.keys.id, "arthur");
+    assert_eq!(typed.view_type, Some(StreamViewType::NewAndOldImages));
+    assert_eq!(typed.sequence_number.as_deref(), Some("111"));
+    assert_eq!(
+        typed.approximate_creation_time,
+        Some(UNIX_EPOCH + Duration::from_millis(1479499740500))
+    );
+
+    let insert = record("INSERT", "NEW_IMAGE", StreamRecord::default());
+    let err = stream::StreamRecord::<User, UserKey>::try_from(insert).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid stream record: it has no NewImage, though its view type NEW_IMAGE includes it"
+    );
+
+    let unknown = record("INSERT", "EVERYTHING", StreamRecord::default());
+    let err = stream::StreamRecord::<User, UserKey>::try_from(unknown).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid stream record: unknown view type EVERYTHING"
+    );
+}
+
+#[test]
+fn ttl_deletes() {
+    let mut expired = record("REMOVE", "KEYS_ONLY", StreamRecord::default());
+    expired.user_identity = Some(Identity {
+        principal_id: Some(String::from("dynamodb.amazonaws.com")),
+        type_: Some(String::from("Service")),
+    });
+    let typed = stream::StreamRecord::<User, UserKey>::try_from(expired).unwrap();
+    assert_eq!(typed.change, Change::Remove(None));
+    assert!(typed.is_ttl_delete());
+    assert!(typed.approximate_creation_time.unwrap() < SystemTime::now());
+}
+
+#[test]
+fn converts_without_losing_anything() {
+    let value = from_streams_value(StreamsAttributeValue {
+        bs: Some(vec![vec![1].into(), vec![2, 3].into()]),
+        ..StreamsAttributeValue::default()
+    });
+    assert_eq!(value.bs.unwrap().len(), 2);
+
+    let value = from_streams_value(StreamsAttributeValue {
+        ns: Some(vec![String::from("1.5")]),
+        ..StreamsAttributeValue::default()
+    });
+    assert_eq!(value.ns, Some(vec![String::from("1.5")]));
+
+    let value = from_streams_value(StreamsAttributeValue {
+        null: Some(true),
+        ..StreamsAttributeValue::default()
+    });
+    assert_eq!(value.null, Some(true));
+}
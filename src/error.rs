@@ -60,8 +60,24 @@ pub enum ErrorImpl {
     FailedToParseInt(String, std::num::ParseIntError),
     /// Failed to parse as a float
     FailedToParseFloat(String, std::num::ParseFloatError),
+    /// An expression is longer than DynamoDB allows
+    ExpressionTooLong(usize),
+    /// An expression breaks one of DynamoDB's rules
+    InvalidExpression(String),
+    /// A request DynamoDB would reject, with the message DynamoDB would give
+    Validation(String),
+    /// A pagination cursor that can't be decoded, or whose signature doesn't match
+    #[cfg(feature = "cursor")]
+    InvalidCursor(String),
+    /// A stream record without the image that was asked for
+    #[cfg(any(feature = "lambda", feature = "streams"))]
+    MissingImage(&'static str),
+    /// A stream record that doesn't make sense for its event name or view type
+    #[cfg(any(feature = "kinesis", feature = "lambda", feature = "streams"))]
+    InvalidStreamRecord(String),
 }
 
+#[allow(clippy::from_over_into)]
 impl Into<Error> for ErrorImpl {
     fn into(self) -> Error {
         Error(self)
@@ -71,7 +87,7 @@ impl Into<Error> for ErrorImpl {
 impl Display for ErrorImpl {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ErrorImpl::Message(s) => f.write_str(&s),
+            ErrorImpl::Message(s) => f.write_str(s),
             ErrorImpl::NotMaplike => f.write_str("Not a map-like object"),
             ErrorImpl::ExpectedString => f.write_str("Expected string"),
             ErrorImpl::ExpectedMap => f.write_str("Expected map"),
@@ -90,6 +106,20 @@ impl Display for ErrorImpl {
             ErrorImpl::FailedToParseFloat(s, err) => {
                 write!(f, "Failed to parse '{0}' as a float: {1}", s, err)
             }
+            ErrorImpl::ExpressionTooLong(len) => write!(
+                f,
+                "Expression is {0} bytes long, but DynamoDB allows at most {1}",
+                len,
+                crate::expression::MAX_EXPRESSION_LENGTH
+            ),
+            ErrorImpl::InvalidExpression(s) => write!(f, "Invalid expression: {0}", s),
+            ErrorImpl::Validation(s) => f.write_str(s),
+            #[cfg(feature = "cursor")]
+            ErrorImpl::InvalidCursor(s) => write!(f, "Invalid cursor: {0}", s),
+            #[cfg(any(feature = "lambda", feature = "streams"))]
+            ErrorImpl::MissingImage(s) => write!(f, "The stream record has no {0}", s),
+            #[cfg(any(feature = "kinesis", feature = "lambda", feature = "streams"))]
+            ErrorImpl::InvalidStreamRecord(s) => write!(f, "Invalid stream record: {0}", s),
         }
     }
 }
@@ -0,0 +1,550 @@
+use super::eval::{type_of, Context};
+use super::parser::{self, RawPath, SetValue, UpdateAction, UpdateOperand};
+use super::{numbers_equal, project, AttributeType, AttributeValue, Item, Path, PathElement};
+use crate::error::ErrorImpl;
+use crate::number::Number;
+use crate::Result;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// A parsed [UpdateExpression], which can be applied to items without a round trip to DynamoDB.
+///
+/// ```
+/// use maplit::hashmap;
+/// use serde_dynamo::expression::{ParsedUpdate, ReturnValues};
+/// use serde_dynamo::{to_attribute_value, to_item};
+/// # use serde_derive::Serialize;
+/// #
+/// # fn update() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[derive(Serialize)]
+/// # struct User { id: String, logins: u32 }
+///
+/// let update = ParsedUpdate::parse("SET logins = logins + :one, last_login = :now")?;
+/// let values = hashmap! {
+///     ":one".to_string() => to_attribute_value(1)?,
+///     ":now".to_string() => to_attribute_value("1985-04-21")?,
+/// };
+///
+/// let user = to_item(User { id: "fSsgVtal8TpP".to_string(), logins: 41 })?;
+/// let outcome = update.apply(&user, &Default::default(), &values)?;
+/// assert_eq!(outcome.new["logins"], to_attribute_value(42)?);
+///
+/// // The attributes UpdateItem would have returned with `ReturnValues: UPDATED_OLD`
+/// let returned = outcome.attributes(ReturnValues::UpdatedOld).unwrap();
+/// assert_eq!(returned, hashmap! { "logins".to_string() => to_attribute_value(41)? });
+/// # Ok(())
+/// # }
+/// # update().unwrap();
+/// ```
+///
+/// As in DynamoDB, every action reads the item as it was before the update, numbers are added
+/// exactly, and actions on overlapping paths are rejected. Errors carry the messages DynamoDB
+/// would return. Whether an action touches the table's key is not checked, since the key schema
+/// isn't known here.
+///
+/// [UpdateExpression]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Expressions.UpdateExpressions.html
+#[derive(Debug, Clone)]
+pub struct ParsedUpdate(Vec<UpdateAction>);
+
+/// The result of applying an UpdateExpression to an item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateOutcome {
+    /// The item before the update
+    pub old: Item,
+    /// The item after the update
+    pub new: Item,
+    /// The paths the update acted on, in the order they appear in the expression
+    pub updated: Vec<Path>,
+}
+
+/// Which attributes an UpdateItem call returns, as in its `ReturnValues` parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ReturnValues {
+    /// `NONE`
+    #[default]
+    None,
+    /// `ALL_OLD`
+    AllOld,
+    /// `UPDATED_OLD`
+    UpdatedOld,
+    /// `ALL_NEW`
+    AllNew,
+    /// `UPDATED_NEW`
+    UpdatedNew,
+}
+
+impl Display for ReturnValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReturnValues::None => "NONE",
+            ReturnValues::AllOld => "ALL_OLD",
+            ReturnValues::UpdatedOld => "UPDATED_OLD",
+            ReturnValues::AllNew => "ALL_NEW",
+            ReturnValues::UpdatedNew => "UPDATED_NEW",
+        })
+    }
+}
+
+impl FromStr for ReturnValues {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "NONE" => Ok(ReturnValues::None),
+            "ALL_OLD" => Ok(ReturnValues::AllOld),
+            "UPDATED_OLD" => Ok(ReturnValues::UpdatedOld),
+            "ALL_NEW" => Ok(ReturnValues::AllNew),
+            "UPDATED_NEW" => Ok(ReturnValues::UpdatedNew),
+            _ => Err(validation(format!(
+                "1 validation error detected: Value '{}' at 'returnValues' failed to satisfy constraint: Member must satisfy enum value set: [ALL_NEW, UPDATED_OLD, ALL_OLD, NONE, UPDATED_NEW]",
+                s
+            ))),
+        }
+    }
+}
+
+impl UpdateOutcome {
+    /// The `Attributes` UpdateItem would return, or `None` if it would return none.
+    ///
+    /// The `UPDATED_*` variants return only the paths the update acted on, nested the same way
+    /// they are in the item.
+    pub fn attributes(&self, return_values: ReturnValues) -> Option<Item> {
+        let attributes = match return_values {
+            ReturnValues::None => return None,
+            ReturnValues::AllOld => self.old.clone(),
+            ReturnValues::AllNew => self.new.clone(),
+            ReturnValues::UpdatedOld => project(&self.old, &self.updated),
+            ReturnValues::UpdatedNew => project(&self.new, &self.updated),
+        };
+        if attributes.is_empty() {
+            None
+        } else {
+            Some(attributes)
+        }
+    }
+}
+
+/// Parse an UpdateExpression and apply it to `item`.
+///
+/// See [`ParsedUpdate`] for the rules, and to reuse a parsed expression across many items.
+pub fn apply(
+    expression: &str,
+    item: &Item,
+    names: &HashMap<String, String>,
+    values: &HashMap<String, AttributeValue>,
+) -> Result<UpdateOutcome> {
+    ParsedUpdate::parse(expression)?.apply(item, names, values)
+}
+
+fn validation(message: impl Into<String>) -> crate::Error {
+    ErrorImpl::Validation(message.into()).into()
+}
+
+fn incorrect_data_type() -> crate::Error {
+    validation("An operand in the update expression has an incorrect data type")
+}
+
+fn invalid_path() -> crate::Error {
+    validation("The document path provided in the update expression is invalid for update")
+}
+
+/// The name DynamoDB uses for a data type in its error messages.
+fn type_name(value: &AttributeValue) -> &'static str {
+    match type_of(value) {
+        Some(AttributeType::S) => "STRING",
+        Some(AttributeType::SS) => "STRING SET",
+        Some(AttributeType::N) => "NUMBER",
+        Some(AttributeType::NS) => "NUMBER SET",
+        Some(AttributeType::B) => "BINARY",
+        Some(AttributeType::BS) => "BINARY SET",
+        Some(AttributeType::Bool) => "BOOLEAN",
+        Some(AttributeType::Null) => "NULL",
+        Some(AttributeType::L) => "LIST",
+        Some(AttributeType::M) => "MAP",
+        None => "UNKNOWN",
+    }
+}
+
+/// A path the way DynamoDB prints it in error messages, e.g. `[address, lines, [1]]`.
+fn path_list(path: &Path) -> String {
+    let elements: Vec<String> = path
+        .elements()
+        .iter()
+        .map(|element| match element {
+            PathElement::Attribute(name) => name.clone(),
+            PathElement::Index(index) => format!("[{}]", index),
+        })
+        .collect();
+    format!("[{}]", elements.join(", "))
+}
+
+/// Order paths so that, among elements of the same list, higher indexes come first.
+fn reverse_path_order(left: &Path, right: &Path) -> Ordering {
+    for (l, r) in left.elements().iter().zip(right.elements()) {
+        let ordering = match (l, r) {
+            (PathElement::Attribute(l), PathElement::Attribute(r)) => l.cmp(r),
+            (PathElement::Index(l), PathElement::Index(r)) => r.cmp(l),
+            (PathElement::Attribute(_), PathElement::Index(_)) => Ordering::Less,
+            (PathElement::Index(_), PathElement::Attribute(_)) => Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    left.elements().len().cmp(&right.elements().len())
+}
+
+fn number(value: &AttributeValue) -> Result<Number> {
+    let n = value.n.as_ref().ok_or_else(incorrect_data_type)?;
+    Number::parse_checked(n).map_err(|err| validation(err.to_string()))
+}
+
+fn number_value(n: Number) -> Result<AttributeValue> {
+    n.check().map_err(|err| validation(err.to_string()))?;
+    Ok(AttributeValue {
+        n: Some(n.to_string()),
+        ..AttributeValue::default()
+    })
+}
+
+impl ParsedUpdate {
+    /// Parse an UpdateExpression.
+    pub fn parse(expression: &str) -> Result<Self> {
+        super::check_length(expression)?;
+        parser::parse_update(expression).map(ParsedUpdate)
+    }
+
+    /// Apply the update to `item`, given the expression's names and values.
+    ///
+    /// An empty `item` stands for one that doesn't exist yet, which UpdateItem creates.
+    pub fn apply(
+        &self,
+        item: &Item,
+        names: &HashMap<String, String>,
+        values: &HashMap<String, AttributeValue>,
+    ) -> Result<UpdateOutcome> {
+        let context = Context {
+            kind: "UpdateExpression",
+            names,
+            values,
+        };
+
+        let mut paths = Vec::with_capacity(self.0.len());
+        for action in &self.0 {
+            let path = match action {
+                UpdateAction::Set(path, value) => {
+                    check_set_value(&context, value)?;
+                    path
+                }
+                UpdateAction::Remove(path) => path,
+                UpdateAction::Add(path, placeholder) | UpdateAction::Delete(path, placeholder) => {
+                    check_operand(&context, action, context.value(placeholder)?)?;
+                    path
+                }
+            };
+            paths.push(context.path(path)?);
+        }
+        check_overlaps(&context, &paths)?;
+
+        // The new value of each path, or `None` to remove it
+        let mut changes = Vec::with_capacity(self.0.len());
+        for (action, path) in self.0.iter().zip(&paths) {
+            let old = path.get(item);
+            let change = match action {
+                UpdateAction::Set(_, value) => Some(set_value(&context, value, item)?),
+                UpdateAction::Remove(_) => None,
+                UpdateAction::Add(_, placeholder) => {
+                    let value = context.value(placeholder)?;
+                    match old {
+                        Some(old) => Some(add(old, value)?),
+                        None => Some(value.clone()),
+                    }
+                }
+                UpdateAction::Delete(_, placeholder) => match old {
+                    Some(old) => delete(old, context.value(placeholder)?)?,
+                    None => continue,
+                },
+            };
+            changes.push((path, change));
+        }
+
+        let mut new = item.clone();
+        let mut removed = Vec::new();
+        for (path, change) in changes {
+            match change {
+                Some(value) => write(&mut new, path, value)?,
+                None => removed.push(path),
+            }
+        }
+        // Removing from the end of a list first keeps the indexes of earlier elements valid
+        removed.sort_by(|left, right| reverse_path_order(left, right));
+        for path in removed {
+            remove(&mut new, path)?;
+        }
+
+        Ok(UpdateOutcome {
+            old: item.clone(),
+            new,
+            updated: paths,
+        })
+    }
+}
+
+fn check_overlaps(context: &Context<'_>, paths: &[Path]) -> Result<()> {
+    for (idx, left) in paths.iter().enumerate() {
+        for right in &paths[idx + 1..] {
+            if left == right {
+                return Err(context.invalid(format!(
+                    "Two document paths conflict with each other; must remove or rewrite one of these paths; path one: {}, path two: {}",
+                    path_list(left),
+                    path_list(right)
+                )));
+            } else if left.overlaps(right) {
+                return Err(context.invalid(format!(
+                    "Two document paths overlap with each other; must remove or rewrite one of these paths; path one: {}, path two: {}",
+                    path_list(left),
+                    path_list(right)
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check the placeholders of a `SET` action up front, including ones in unused defaults.
+fn check_set_value(context: &Context<'_>, value: &SetValue) -> Result<()> {
+    fn check(context: &Context<'_>, operand: &UpdateOperand) -> Result<()> {
+        match operand {
+            UpdateOperand::Path(path) => context.path(path).map(drop),
+            UpdateOperand::Value(placeholder) => context.value(placeholder).map(drop),
+            UpdateOperand::IfNotExists(path, default) => {
+                context.path(path)?;
+                check(context, default)
+            }
+            UpdateOperand::ListAppend(left, right) => {
+                check(context, left)?;
+                check(context, right)
+            }
+        }
+    }
+
+    match value {
+        SetValue::Operand(operand) => check(context, operand),
+        SetValue::Plus(left, right) | SetValue::Minus(left, right) => {
+            check(context, left)?;
+            check(context, right)
+        }
+    }
+}
+
+/// `ADD` takes a number or a set, and `DELETE` takes a set.
+fn check_operand(
+    context: &Context<'_>,
+    action: &UpdateAction,
+    value: &AttributeValue,
+) -> Result<()> {
+    let (operator, allowed): (_, &[AttributeType]) = match action {
+        UpdateAction::Add(..) => (
+            "ADD",
+            &[
+                AttributeType::N,
+                AttributeType::SS,
+                AttributeType::NS,
+                AttributeType::BS,
+            ],
+        ),
+        _ => (
+            "DELETE",
+            &[AttributeType::SS, AttributeType::NS, AttributeType::BS],
+        ),
+    };
+    match type_of(value) {
+        Some(t) if allowed.contains(&t) => Ok(()),
+        _ => Err(context.invalid(format!(
+            "Incorrect operand type for operator or function; operator: {}, operand type: {}",
+            operator,
+            type_name(value)
+        ))),
+    }
+}
+
+fn set_value(context: &Context<'_>, value: &SetValue, item: &Item) -> Result<AttributeValue> {
+    match value {
+        SetValue::Operand(operand) => update_operand(context, operand, item),
+        SetValue::Plus(left, right) => {
+            let left = number(&update_operand(context, left, item)?)?;
+            let right = number(&update_operand(context, right, item)?)?;
+            number_value(left.add(&right))
+        }
+        SetValue::Minus(left, right) => {
+            let left = number(&update_operand(context, left, item)?)?;
+            let right = number(&update_operand(context, right, item)?)?;
+            number_value(left.sub(&right))
+        }
+    }
+}
+
+fn update_operand(
+    context: &Context<'_>,
+    operand: &UpdateOperand,
+    item: &Item,
+) -> Result<AttributeValue> {
+    match operand {
+        UpdateOperand::Path(path) => resolve(context, path, item)?.cloned().ok_or_else(|| {
+            validation(
+                "The provided expression refers to an attribute that does not exist in the item",
+            )
+        }),
+        UpdateOperand::Value(placeholder) => context.value(placeholder).cloned(),
+        UpdateOperand::IfNotExists(path, default) => match resolve(context, path, item)? {
+            Some(value) => Ok(value.clone()),
+            None => update_operand(context, default, item),
+        },
+        UpdateOperand::ListAppend(left, right) => {
+            let left = update_operand(context, left, item)?;
+            let right = update_operand(context, right, item)?;
+            match (left.l, right.l) {
+                (Some(mut left), Some(right)) => {
+                    left.extend(right);
+                    Ok(AttributeValue {
+                        l: Some(left),
+                        ..AttributeValue::default()
+                    })
+                }
+                _ => Err(incorrect_data_type()),
+            }
+        }
+    }
+}
+
+fn resolve<'i>(
+    context: &Context<'_>,
+    path: &RawPath,
+    item: &'i Item,
+) -> Result<Option<&'i AttributeValue>> {
+    Ok(context.path(path)?.get(item))
+}
+
+/// `ADD`: sum two numbers, or take the union of two sets of the same type.
+fn add(old: &AttributeValue, value: &AttributeValue) -> Result<AttributeValue> {
+    if let (Some(_), Some(_)) = (&old.n, &value.n) {
+        number_value(number(old)?.add(&number(value)?))
+    } else if let (Some(old), Some(value)) = (&old.ss, &value.ss) {
+        let mut union = old.clone();
+        union.extend(value.iter().filter(|s| !old.contains(s)).cloned());
+        Ok(AttributeValue {
+            ss: Some(union),
+            ..AttributeValue::default()
+        })
+    } else if let (Some(old), Some(value)) = (&old.ns, &value.ns) {
+        let mut union = old.clone();
+        for n in value {
+            if !union.iter().any(|member| numbers_equal(member, n)) {
+                union.push(n.clone());
+            }
+        }
+        Ok(AttributeValue {
+            ns: Some(union),
+            ..AttributeValue::default()
+        })
+    } else if let (Some(old), Some(value)) = (&old.bs, &value.bs) {
+        let mut union = old.clone();
+        union.extend(value.iter().filter(|b| !old.contains(b)).cloned());
+        Ok(AttributeValue {
+            bs: Some(union),
+            ..AttributeValue::default()
+        })
+    } else {
+        Err(incorrect_data_type())
+    }
+}
+
+/// `DELETE`: remove members from a set, or `None` if none are left.
+fn delete(old: &AttributeValue, value: &AttributeValue) -> Result<Option<AttributeValue>> {
+    let remaining = if let (Some(old), Some(value)) = (&old.ss, &value.ss) {
+        let remaining: Vec<_> = old.iter().filter(|s| !value.contains(s)).cloned().collect();
+        AttributeValue {
+            ss: Some(remaining).filter(|set| !set.is_empty()),
+            ..AttributeValue::default()
+        }
+    } else if let (Some(old), Some(value)) = (&old.ns, &value.ns) {
+        let remaining: Vec<_> = old
+            .iter()
+            .filter(|member| !value.iter().any(|n| numbers_equal(member, n)))
+            .cloned()
+            .collect();
+        AttributeValue {
+            ns: Some(remaining).filter(|set| !set.is_empty()),
+            ..AttributeValue::default()
+        }
+    } else if let (Some(old), Some(value)) = (&old.bs, &value.bs) {
+        let remaining: Vec<_> = old.iter().filter(|b| !value.contains(b)).cloned().collect();
+        AttributeValue {
+            bs: Some(remaining).filter(|set| !set.is_empty()),
+            ..AttributeValue::default()
+        }
+    } else {
+        return Err(incorrect_data_type());
+    };
+    Ok(type_of(&remaining).map(|_| remaining))
+}
+
+/// Store `value` at `path`, whose parent must already exist.
+///
+/// Setting an index past the end of a list appends to it, as DynamoDB does.
+fn write(item: &mut Item, path: &Path, value: AttributeValue) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) => parent.get_mut(item).ok_or_else(invalid_path)?,
+        None => {
+            if let Some(PathElement::Attribute(name)) = path.elements().first() {
+                item.insert(name.clone(), value);
+            }
+            return Ok(());
+        }
+    };
+    match path.elements().last() {
+        Some(PathElement::Attribute(name)) => {
+            let map = parent.m.as_mut().ok_or_else(invalid_path)?;
+            map.insert(name.clone(), value);
+        }
+        Some(PathElement::Index(index)) => {
+            let list = parent.l.as_mut().ok_or_else(invalid_path)?;
+            if *index < list.len() {
+                list[*index] = value;
+            } else {
+                list.push(value);
+            }
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// Remove whatever is at `path`, if anything.
+fn remove(item: &mut Item, path: &Path) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) => parent.get_mut(item).ok_or_else(invalid_path)?,
+        None => {
+            if let Some(PathElement::Attribute(name)) = path.elements().first() {
+                item.remove(name);
+            }
+            return Ok(());
+        }
+    };
+    match path.elements().last() {
+        Some(PathElement::Attribute(name)) => {
+            let map = parent.m.as_mut().ok_or_else(invalid_path)?;
+            map.remove(name);
+        }
+        Some(PathElement::Index(index)) => {
+            let list = parent.l.as_mut().ok_or_else(invalid_path)?;
+            if *index < list.len() {
+                list.remove(*index);
+            }
+        }
+        None => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use super::{values_equal, AttributeValue, Item, Path};
+use crate::{to_item, Result};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// An attribute that differs between two versions of an item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// Where the attribute is, e.g. `address.lines[1]`
+    pub path: Path,
+    /// Its value before, or `None` if it was added
+    pub old: Option<AttributeValue>,
+    /// Its value after, or `None` if it was removed
+    pub new: Option<AttributeValue>,
+}
+
+/// The attributes that differ between `old` and `new`, such as the old and new images of a
+/// stream record, with their values before and after.
+///
+/// Maps are compared key by key, and lists of the same length element by element, so a change
+/// deep inside an attribute is reported at its own document path. A list that grew or shrank is
+/// reported as a whole. Numbers are compared by value and sets without regard to order, the way
+/// DynamoDB compares them, so values that only differ in formatting aren't changes.
+///
+/// Changes are ordered by path.
+///
+/// ```
+/// use serde_dynamo::expression::changed_fields;
+/// # use serde_dynamo::to_item;
+/// # use serde_json::json;
+/// #
+/// # fn test() -> serde_dynamo::Result<()> {
+/// let old = to_item(json!({ "id": "arthur", "age": 42, "address": { "city": "Cottington" } }))?;
+/// let new = to_item(json!({ "id": "arthur", "age": 42.0, "address": { "city": "London" } }))?;
+///
+/// let changes = changed_fields(&old, &new);
+/// assert_eq!(changes.len(), 1);
+/// assert_eq!(changes[0].path.to_string(), "address.city");
+/// # Ok(())
+/// # }
+/// # test().unwrap()
+/// ```
+pub fn changed_fields(old: &Item, new: &Item) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    changed_in_item(&mut changes, None, old, new);
+    changes
+}
+
+/// The attributes that differ between two values, serialized with [`to_item`] and compared with
+/// [`changed_fields`].
+///
+/// Fails if either value doesn't serialize to an item.
+pub fn changed_fields_of<T: Serialize + ?Sized>(old: &T, new: &T) -> Result<Vec<FieldChange>> {
+    Ok(changed_fields(&to_item(old)?, &to_item(new)?))
+}
+
+fn changed_in_item(changes: &mut Vec<FieldChange>, path: Option<&Path>, old: &Item, new: &Item) {
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for key in keys {
+        let path = match path {
+            Some(parent) => parent.clone().key(key.as_str()),
+            None => Path::attribute(key.as_str()),
+        };
+        match (old.get(key), new.get(key)) {
+            (Some(old), Some(new)) => changed_in_value(changes, path, old, new),
+            (old, new) => changes.push(FieldChange {
+                path,
+                old: old.cloned(),
+                new: new.cloned(),
+            }),
+        }
+    }
+}
+
+fn changed_in_value(
+    changes: &mut Vec<FieldChange>,
+    path: Path,
+    old: &AttributeValue,
+    new: &AttributeValue,
+) {
+    if values_equal(old, new) {
+        return;
+    }
+    if let (Some(old), Some(new)) = (&old.m, &new.m) {
+        return changed_in_item(changes, Some(&path), old, new);
+    }
+    if let (Some(old_l), Some(new_l)) = (&old.l, &new.l) {
+        if old_l.len() == new_l.len() {
+            for (index, (old, new)) in old_l.iter().zip(new_l).enumerate() {
+                changed_in_value(changes, path.clone().index(index), old, new);
+            }
+            return;
+        }
+    }
+    changes.push(FieldChange {
+        path,
+        old: Some(old.clone()),
+        new: Some(new.clone()),
+    });
+}
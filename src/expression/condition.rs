@@ -0,0 +1,470 @@
+use super::{AttributeValue, Expression, Path, Placeholders};
+use crate::error::ErrorImpl;
+use crate::{to_attribute_value, to_item, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+/// The most values DynamoDB allows on the right-hand side of `IN`.
+const MAX_IN_VALUES: usize = 100;
+
+/// A typed [ConditionExpression].
+///
+/// Conditions are built from [`Operand`]s and the functions on this type, combined with
+/// [`and`](Condition::and), [`or`](Condition::or), and `!`, and finally turned into an
+/// [`Expression`] with [`build`](Condition::build). Values are serialized with
+/// [`to_attribute_value`], so anything that can be written with [`to_item`] can be compared
+/// against.
+///
+/// ```
+/// # use serde_dynamo::expression::{Condition, Operand};
+/// # use rusoto_dynamodb::PutItemInput;
+/// #
+/// # fn put() -> Result<(), Box<dyn std::error::Error>> {
+/// // Only overwrite the item if nobody else has changed it since we read version 3
+/// let condition = Condition::attribute_not_exists("id")
+///     .or(Operand::path("version").eq(3))
+///     .build()?;
+///
+/// assert_eq!(condition.expression, "attribute_not_exists(#c0) OR #c1 = :c0");
+///
+/// let input = PutItemInput {
+///     table_name: "users".to_string(),
+///     condition_expression: Some(condition.expression.clone()),
+///     expression_attribute_names: condition.expression_attribute_names(),
+///     expression_attribute_values: condition.expression_attribute_values(),
+///     ..PutItemInput::default()
+/// };
+/// # Ok(())
+/// # }
+/// # put().unwrap()
+/// ```
+///
+/// The placeholders of a built condition start with `#c` and `:c`, so they never collide with
+/// those of a [`diff`](super::diff) update sent in the same request.
+///
+/// [ConditionExpression]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Expressions.OperatorsAndFunctions.html
+#[derive(Debug, Clone)]
+pub struct Condition(Node);
+
+#[derive(Debug, Clone)]
+enum Node {
+    Compare(Operand, Comparator, Operand),
+    Between(Operand, Operand, Operand),
+    In(Operand, Vec<Operand>),
+    AttributeExists(Path),
+    AttributeNotExists(Path),
+    AttributeType(Path, AttributeType),
+    BeginsWith(Path, Operand),
+    Contains(Path, Operand),
+    And(Vec<Node>),
+    Or(Vec<Node>),
+    Not(Box<Node>),
+    /// An error that happened while building the condition, reported by `build`
+    Invalid(crate::Error),
+}
+
+/// One side of a comparison: an attribute, the size of an attribute, or a value.
+#[derive(Debug, Clone)]
+pub struct Operand(OperandImpl);
+
+#[derive(Debug, Clone)]
+enum OperandImpl {
+    Path(Path),
+    Size(Path),
+    Value(Box<Result<AttributeValue>>),
+}
+
+/// A comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparator {
+    /// `=`
+    Eq,
+    /// `<>`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+/// A DynamoDB data type, as used by the `attribute_type` function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttributeType {
+    /// String
+    S,
+    /// String set
+    SS,
+    /// Number
+    N,
+    /// Number set
+    NS,
+    /// Binary
+    B,
+    /// Binary set
+    BS,
+    /// Boolean
+    Bool,
+    /// Null
+    Null,
+    /// List
+    L,
+    /// Map
+    M,
+}
+
+impl Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Comparator::Eq => "=",
+            Comparator::Ne => "<>",
+            Comparator::Lt => "<",
+            Comparator::Le => "<=",
+            Comparator::Gt => ">",
+            Comparator::Ge => ">=",
+        })
+    }
+}
+
+impl Display for AttributeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AttributeType::S => "S",
+            AttributeType::SS => "SS",
+            AttributeType::N => "N",
+            AttributeType::NS => "NS",
+            AttributeType::B => "B",
+            AttributeType::BS => "BS",
+            AttributeType::Bool => "BOOL",
+            AttributeType::Null => "NULL",
+            AttributeType::L => "L",
+            AttributeType::M => "M",
+        })
+    }
+}
+
+impl Operand {
+    /// The value of an attribute.
+    pub fn path(path: impl Into<Path>) -> Self {
+        Operand(OperandImpl::Path(path.into()))
+    }
+
+    /// The `size` of an attribute.
+    pub fn size(path: impl Into<Path>) -> Self {
+        Operand(OperandImpl::Size(path.into()))
+    }
+
+    /// A value, serialized with [`to_attribute_value`].
+    pub fn value<T>(value: T) -> Self
+    where
+        T: Serialize,
+    {
+        Operand(OperandImpl::Value(Box::new(to_attribute_value(value))))
+    }
+
+    /// Compare this operand to another one.
+    pub fn compare(self, comparator: Comparator, other: Operand) -> Condition {
+        Condition(Node::Compare(self, comparator, other))
+    }
+
+    /// `self = value`
+    pub fn eq<T: Serialize>(self, value: T) -> Condition {
+        self.compare(Comparator::Eq, Operand::value(value))
+    }
+
+    /// `self <> value`
+    pub fn ne<T: Serialize>(self, value: T) -> Condition {
+        self.compare(Comparator::Ne, Operand::value(value))
+    }
+
+    /// `self < value`
+    pub fn lt<T: Serialize>(self, value: T) -> Condition {
+        self.compare(Comparator::Lt, Operand::value(value))
+    }
+
+    /// `self <= value`
+    pub fn le<T: Serialize>(self, value: T) -> Condition {
+        self.compare(Comparator::Le, Operand::value(value))
+    }
+
+    /// `self > value`
+    pub fn gt<T: Serialize>(self, value: T) -> Condition {
+        self.compare(Comparator::Gt, Operand::value(value))
+    }
+
+    /// `self >= value`
+    pub fn ge<T: Serialize>(self, value: T) -> Condition {
+        self.compare(Comparator::Ge, Operand::value(value))
+    }
+
+    /// `self BETWEEN low AND high`
+    pub fn between<L: Serialize, H: Serialize>(self, low: L, high: H) -> Condition {
+        Condition(Node::Between(
+            self,
+            Operand::value(low),
+            Operand::value(high),
+        ))
+    }
+
+    /// `self IN (values...)`
+    ///
+    /// DynamoDB allows between 1 and 100 values.
+    pub fn is_in<I, T>(self, values: I) -> Condition
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+    {
+        Condition(Node::In(
+            self,
+            values.into_iter().map(Operand::value).collect(),
+        ))
+    }
+}
+
+impl Condition {
+    /// `attribute_exists(path)`
+    pub fn attribute_exists(path: impl Into<Path>) -> Self {
+        Condition(Node::AttributeExists(path.into()))
+    }
+
+    /// `attribute_not_exists(path)`
+    pub fn attribute_not_exists(path: impl Into<Path>) -> Self {
+        Condition(Node::AttributeNotExists(path.into()))
+    }
+
+    /// `attribute_type(path, type)`
+    pub fn attribute_type(path: impl Into<Path>, attribute_type: AttributeType) -> Self {
+        Condition(Node::AttributeType(path.into(), attribute_type))
+    }
+
+    /// `begins_with(path, prefix)`
+    pub fn begins_with<T: Serialize>(path: impl Into<Path>, prefix: T) -> Self {
+        Condition(Node::BeginsWith(path.into(), Operand::value(prefix)))
+    }
+
+    /// `contains(path, operand)`
+    ///
+    /// True if the attribute is a string containing `operand` as a substring, or a set or list
+    /// containing `operand` as an element.
+    pub fn contains<T: Serialize>(path: impl Into<Path>, operand: T) -> Self {
+        Condition(Node::Contains(path.into(), Operand::value(operand)))
+    }
+
+    /// A condition that holds when the stored item currently has every attribute of `partial`,
+    /// with the same value.
+    ///
+    /// `partial` is serialized with [`to_item`]. Attributes it doesn't serialize, for example
+    /// `None`s skipped with `skip_serializing_if`, aren't checked. Attributes it serializes as
+    /// null match attributes that are null or missing, mirroring how `from_item` reads them.
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// # use serde_dynamo::expression::Condition;
+    /// #
+    /// # fn guard() -> Result<(), Box<dyn std::error::Error>> {
+    /// #[derive(Serialize)]
+    /// struct Guard<'a> {
+    ///     status: &'a str,
+    ///     version: u32,
+    /// }
+    ///
+    /// let condition = Condition::matches(&Guard { status: "active", version: 3 }).build()?;
+    /// assert_eq!(condition.expression, "#c0 = :c0 AND #c1 = :c1");
+    /// # Ok(())
+    /// # }
+    /// # guard().unwrap()
+    /// ```
+    pub fn matches<T: Serialize>(partial: &T) -> Self {
+        let item = match to_item(partial) {
+            Ok(item) => item,
+            Err(err) => return Condition(Node::Invalid(err)),
+        };
+        if item.is_empty() {
+            return Condition(Node::Invalid(
+                ErrorImpl::InvalidExpression(String::from(
+                    "A partial item must have at least one attribute",
+                ))
+                .into(),
+            ));
+        }
+
+        // Sort the attributes so the generated expression is deterministic
+        let conditions = item
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(name, value)| {
+                if value.null == Some(true) {
+                    Condition::attribute_not_exists(name.as_str())
+                        .or(Condition::attribute_type(
+                            name.as_str(),
+                            AttributeType::Null,
+                        ))
+                        .0
+                } else {
+                    Node::Compare(
+                        Operand::path(name),
+                        Comparator::Eq,
+                        Operand(OperandImpl::Value(Box::new(Ok(value)))),
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if conditions.len() == 1 {
+            Condition(conditions.into_iter().next().unwrap())
+        } else {
+            Condition(Node::And(conditions))
+        }
+    }
+
+    /// `self AND other`
+    pub fn and(self, other: Condition) -> Self {
+        Condition(match self.0 {
+            Node::And(mut nodes) => {
+                nodes.push(other.0);
+                Node::And(nodes)
+            }
+            node => Node::And(vec![node, other.0]),
+        })
+    }
+
+    /// `self OR other`
+    pub fn or(self, other: Condition) -> Self {
+        Condition(match self.0 {
+            Node::Or(mut nodes) => {
+                nodes.push(other.0);
+                Node::Or(nodes)
+            }
+            node => Node::Or(vec![node, other.0]),
+        })
+    }
+
+    /// Render the condition into an expression with `#c`/`:c` placeholders.
+    ///
+    /// Fails if any value couldn't be serialized, or if the condition breaks one of DynamoDB's
+    /// rules that can be checked without a table.
+    pub fn build(self) -> Result<Expression> {
+        self.build_with(Placeholders::new("c"))
+    }
+
+    pub(crate) fn build_with(self, mut placeholders: Placeholders) -> Result<Expression> {
+        let mut expression = String::new();
+        render(&self.0, &mut placeholders, &mut expression)?;
+        super::check_length(&expression)?;
+        Ok(placeholders.finish(expression))
+    }
+}
+
+impl std::ops::Not for Condition {
+    type Output = Condition;
+
+    /// `NOT self`
+    fn not(self) -> Self::Output {
+        Condition(Node::Not(Box::new(self.0)))
+    }
+}
+
+fn render_operand(operand: &Operand, placeholders: &mut Placeholders) -> Result<String> {
+    Ok(match &operand.0 {
+        OperandImpl::Path(path) => placeholders.path(path),
+        OperandImpl::Size(path) => format!("size({})", placeholders.path(path)),
+        OperandImpl::Value(value) => placeholders.value((**value).clone()?),
+    })
+}
+
+/// Render a child of `AND` or `OR`, with parentheses if it is itself a combination.
+fn render_child(node: &Node, placeholders: &mut Placeholders, out: &mut String) -> Result<()> {
+    match node {
+        Node::And(_) | Node::Or(_) => {
+            out.push('(');
+            render(node, placeholders, out)?;
+            out.push(')');
+            Ok(())
+        }
+        _ => render(node, placeholders, out),
+    }
+}
+
+fn render(node: &Node, placeholders: &mut Placeholders, out: &mut String) -> Result<()> {
+    match node {
+        Node::Compare(left, comparator, right) => {
+            let left = render_operand(left, placeholders)?;
+            let right = render_operand(right, placeholders)?;
+            out.push_str(&format!("{} {} {}", left, comparator, right));
+        }
+        Node::Between(operand, low, high) => {
+            let operand = render_operand(operand, placeholders)?;
+            let low = render_operand(low, placeholders)?;
+            let high = render_operand(high, placeholders)?;
+            out.push_str(&format!("{} BETWEEN {} AND {}", operand, low, high));
+        }
+        Node::In(operand, values) => {
+            if values.is_empty() || values.len() > MAX_IN_VALUES {
+                return Err(ErrorImpl::InvalidExpression(format!(
+                    "IN takes between 1 and {} values, but {} were given",
+                    MAX_IN_VALUES,
+                    values.len()
+                ))
+                .into());
+            }
+            let operand = render_operand(operand, placeholders)?;
+            let values = values
+                .iter()
+                .map(|value| render_operand(value, placeholders))
+                .collect::<Result<Vec<_>>>()?;
+            out.push_str(&format!("{} IN ({})", operand, values.join(", ")));
+        }
+        Node::AttributeExists(path) => {
+            out.push_str(&format!("attribute_exists({})", placeholders.path(path)));
+        }
+        Node::AttributeNotExists(path) => {
+            out.push_str(&format!(
+                "attribute_not_exists({})",
+                placeholders.path(path)
+            ));
+        }
+        Node::AttributeType(path, attribute_type) => {
+            let path = placeholders.path(path);
+            let attribute_type = placeholders.value(AttributeValue {
+                s: Some(attribute_type.to_string()),
+                ..AttributeValue::default()
+            });
+            out.push_str(&format!("attribute_type({}, {})", path, attribute_type));
+        }
+        Node::BeginsWith(path, prefix) => {
+            let path = placeholders.path(path);
+            let prefix = render_operand(prefix, placeholders)?;
+            out.push_str(&format!("begins_with({}, {})", path, prefix));
+        }
+        Node::Contains(path, operand) => {
+            let path = placeholders.path(path);
+            let operand = render_operand(operand, placeholders)?;
+            out.push_str(&format!("contains({}, {})", path, operand));
+        }
+        Node::And(nodes) | Node::Or(nodes) => {
+            let separator = if let Node::And(_) = node {
+                " AND "
+            } else {
+                " OR "
+            };
+            for (idx, child) in nodes.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(separator);
+                }
+                render_child(child, placeholders, out)?;
+            }
+        }
+        Node::Not(child) => {
+            out.push_str("NOT (");
+            render(child, placeholders, out)?;
+            out.push(')');
+        }
+        Node::Invalid(err) => return Err(err.clone()),
+    }
+    Ok(())
+}
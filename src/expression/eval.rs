@@ -0,0 +1,473 @@
+use super::parser::{self, Operand, RawPath, RawPathElement};
+use super::{
+    numbers_equal, values_equal, AttributeType, AttributeValue, Comparator, Item, Path, PathElement,
+};
+use crate::number::Number;
+use crate::Result;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// A parsed [ConditionExpression] or FilterExpression, which can be evaluated against items
+/// without a round trip to DynamoDB.
+///
+/// ```
+/// use maplit::hashmap;
+/// use serde_dynamo::{expression::ParsedCondition, to_attribute_value, to_item};
+/// # use serde_derive::Serialize;
+/// #
+/// # fn filter() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[derive(Serialize)]
+/// # struct User { name: String, age: u8 }
+///
+/// let filter = ParsedCondition::parse("begins_with(#name, :prefix) AND age >= :age")?;
+/// let names = hashmap! { "#name".to_string() => "name".to_string() };
+/// let values = hashmap! {
+///     ":prefix".to_string() => to_attribute_value("Arthur")?,
+///     ":age".to_string() => to_attribute_value(18)?,
+/// };
+///
+/// let user = to_item(User { name: "Arthur Dent".to_string(), age: 42 })?;
+/// assert!(filter.evaluate(&user, &names, &values)?);
+/// # Ok(())
+/// # }
+/// # filter().unwrap();
+/// ```
+///
+/// Comparisons follow DynamoDB's rules. Numbers are compared by value, and strings and binary by
+/// their bytes. An ordering comparison between values of different types, or with an attribute
+/// that doesn't exist, is false. `a <> b` is always the negation of `a = b`, so it is true in
+/// those cases.
+///
+/// Expressions are checked for the mistakes DynamoDB reports up front (unknown functions,
+/// undefined placeholders, and operands of the wrong type) before anything is evaluated, so a
+/// mistake is reported even in a branch that would be short-circuited. DynamoDB's list of
+/// reserved words is not checked.
+///
+/// [ConditionExpression]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Expressions.OperatorsAndFunctions.html
+#[derive(Debug, Clone)]
+pub struct ParsedCondition {
+    kind: &'static str,
+    condition: parser::Condition,
+}
+
+impl ParsedCondition {
+    /// Parse a condition expression.
+    pub fn parse(expression: &str) -> Result<Self> {
+        ParsedCondition::parse_as("ConditionExpression", expression)
+    }
+
+    /// Parse a condition that DynamoDB calls `kind` in its error messages, e.g.
+    /// `FilterExpression`.
+    pub(crate) fn parse_as(kind: &'static str, expression: &str) -> Result<Self> {
+        super::check_length(expression)?;
+        let condition = parser::parse_condition(kind, expression)?;
+        Ok(ParsedCondition { kind, condition })
+    }
+
+    #[cfg(feature = "mock")]
+    pub(crate) fn syntax(&self) -> &parser::Condition {
+        &self.condition
+    }
+
+    /// Whether `item` satisfies the condition, given the expression's names and values.
+    pub fn evaluate(
+        &self,
+        item: &Item,
+        names: &HashMap<String, String>,
+        values: &HashMap<String, AttributeValue>,
+    ) -> Result<bool> {
+        let context = Context {
+            kind: self.kind,
+            names,
+            values,
+        };
+        context.check(&self.condition)?;
+        context.evaluate(&self.condition, item)
+    }
+}
+
+/// Parse a condition expression and evaluate it against `item`.
+///
+/// See [`ParsedCondition`] for the rules, and to reuse a parsed expression across many items.
+pub fn evaluate(
+    expression: &str,
+    item: &Item,
+    names: &HashMap<String, String>,
+    values: &HashMap<String, AttributeValue>,
+) -> Result<bool> {
+    ParsedCondition::parse(expression)?.evaluate(item, names, values)
+}
+
+/// The data type of an attribute value, or `None` if no field is set.
+pub(crate) fn type_of(value: &AttributeValue) -> Option<AttributeType> {
+    Some(if value.s.is_some() {
+        AttributeType::S
+    } else if value.n.is_some() {
+        AttributeType::N
+    } else if value.b.is_some() {
+        AttributeType::B
+    } else if value.bool.is_some() {
+        AttributeType::Bool
+    } else if value.null.is_some() {
+        AttributeType::Null
+    } else if value.ss.is_some() {
+        AttributeType::SS
+    } else if value.ns.is_some() {
+        AttributeType::NS
+    } else if value.bs.is_some() {
+        AttributeType::BS
+    } else if value.l.is_some() {
+        AttributeType::L
+    } else if value.m.is_some() {
+        AttributeType::M
+    } else {
+        return None;
+    })
+}
+
+/// How two scalar values are ordered, or `None` if they can't be ordered.
+///
+/// Only two strings, two numbers, or two binary values have an ordering.
+pub(crate) fn compare_values(left: &AttributeValue, right: &AttributeValue) -> Option<Ordering> {
+    if let (Some(l), Some(r)) = (&left.s, &right.s) {
+        Some(l.as_bytes().cmp(r.as_bytes()))
+    } else if let (Some(l), Some(r)) = (&left.n, &right.n) {
+        Some(Number::parse(l).ok()?.cmp(&Number::parse(r).ok()?))
+    } else if let (Some(l), Some(r)) = (&left.b, &right.b) {
+        Some(l.as_ref().cmp(r.as_ref()))
+    } else {
+        None
+    }
+}
+
+fn parse_type(name: &str) -> Option<AttributeType> {
+    Some(match name {
+        "S" => AttributeType::S,
+        "SS" => AttributeType::SS,
+        "N" => AttributeType::N,
+        "NS" => AttributeType::NS,
+        "B" => AttributeType::B,
+        "BS" => AttributeType::BS,
+        "BOOL" => AttributeType::Bool,
+        "NULL" => AttributeType::Null,
+        "L" => AttributeType::L,
+        "M" => AttributeType::M,
+        _ => return None,
+    })
+}
+
+/// The names and values an expression's placeholders refer to.
+pub(crate) struct Context<'a> {
+    /// The kind of expression, for error messages
+    pub(crate) kind: &'static str,
+    pub(crate) names: &'a HashMap<String, String>,
+    pub(crate) values: &'a HashMap<String, AttributeValue>,
+}
+
+impl<'a> Context<'a> {
+    pub(crate) fn invalid(&self, message: impl Display) -> crate::Error {
+        parser::syntax_error(self.kind, message)
+    }
+
+    pub(crate) fn path(&self, path: &RawPath) -> Result<Path> {
+        let mut elements = Vec::with_capacity(path.0.len());
+        for element in &path.0 {
+            elements.push(match element {
+                RawPathElement::Name(name) => PathElement::Attribute(name.clone()),
+                RawPathElement::Placeholder(placeholder) => {
+                    let name = self.names.get(placeholder).ok_or_else(|| {
+                        self.invalid(format!(
+                            "An expression attribute name used in the document path is not defined; attribute name: {}",
+                            placeholder
+                        ))
+                    })?;
+                    PathElement::Attribute(name.clone())
+                }
+                RawPathElement::Index(index) => PathElement::Index(*index),
+            });
+        }
+        Ok(Path(elements))
+    }
+
+    pub(crate) fn value(&self, placeholder: &str) -> Result<&'a AttributeValue> {
+        self.values.get(placeholder).ok_or_else(|| {
+            self.invalid(format!(
+                "An expression attribute value used in expression is not defined; attribute value: {}",
+                placeholder
+            ))
+        })
+    }
+
+    /// The value of an operand, or `None` if it refers to a missing attribute.
+    fn operand<'i>(
+        &self,
+        operand: &Operand,
+        item: &'i Item,
+    ) -> Result<Option<Cow<'i, AttributeValue>>>
+    where
+        'a: 'i,
+    {
+        Ok(match operand {
+            Operand::Path(path) => self.path(path)?.get(item).map(Cow::Borrowed),
+            Operand::Value(placeholder) => Some(Cow::Borrowed(self.value(placeholder)?)),
+            Operand::Size(path) => self.path(path)?.get(item).and_then(size).map(|size| {
+                Cow::Owned(AttributeValue {
+                    n: Some(size.to_string()),
+                    ..AttributeValue::default()
+                })
+            }),
+        })
+    }
+
+    /// Check everything DynamoDB validates before looking at an item.
+    fn check(&self, condition: &parser::Condition) -> Result<()> {
+        use parser::Condition::*;
+
+        match condition {
+            Compare(left, _, right) => {
+                self.check_operand(left)?;
+                self.check_operand(right)
+            }
+            Between(operand, low, high) => {
+                self.check_operand(operand)?;
+                self.check_operand(low)?;
+                self.check_operand(high)?;
+                if let (Operand::Value(low), Operand::Value(high)) = (low, high) {
+                    let (low, high) = (self.value(low)?, self.value(high)?);
+                    if compare_values(low, high) == Some(Ordering::Greater) {
+                        return Err(self.invalid(
+                            "The BETWEEN operator requires upper bound to be greater than or equal to lower bound",
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            In(operand, list) => {
+                self.check_operand(operand)?;
+                if list.len() > 100 {
+                    return Err(self.invalid(format!(
+                        "The IN operator is provided with too many operands; number of operands: {}",
+                        list.len()
+                    )));
+                }
+                list.iter()
+                    .try_for_each(|operand| self.check_operand(operand))
+            }
+            Function(name, args) => self.check_function(name, args),
+            And(conditions) | Or(conditions) => conditions
+                .iter()
+                .try_for_each(|condition| self.check(condition)),
+            Not(condition) => self.check(condition),
+        }
+    }
+
+    fn check_operand(&self, operand: &Operand) -> Result<()> {
+        match operand {
+            Operand::Path(path) | Operand::Size(path) => self.path(path).map(drop),
+            Operand::Value(placeholder) => self.value(placeholder).map(drop),
+        }
+    }
+
+    fn check_function(&self, name: &str, args: &[Operand]) -> Result<()> {
+        let arity = match name {
+            "attribute_exists" | "attribute_not_exists" => 1,
+            "attribute_type" | "begins_with" | "contains" => 2,
+            _ => return Err(self.invalid(format!("Invalid function name; function: {}", name))),
+        };
+        if args.len() != arity {
+            return Err(self.invalid(format!(
+                "Incorrect number of operands for operator or function; operator or function: {}, number of operands: {}",
+                name,
+                args.len()
+            )));
+        }
+        if !matches!(args[0], Operand::Path(_)) {
+            return Err(self.invalid(format!(
+                "Operator or function requires a document path; operator or function: {}",
+                name
+            )));
+        }
+        args.iter().try_for_each(|arg| self.check_operand(arg))?;
+
+        let operand_type = |arg: &Operand| match arg {
+            Operand::Value(placeholder) => self.value(placeholder).map(type_of),
+            _ => Ok(None),
+        };
+        match name {
+            "attribute_type" => {
+                let value = match &args[1] {
+                    Operand::Value(placeholder) => self.value(placeholder)?,
+                    _ => {
+                        return Err(self.invalid(
+                            "Incorrect operand type for operator or function; operator or function: attribute_type",
+                        ))
+                    }
+                };
+                match &value.s {
+                    Some(s) if parse_type(s).is_some() => Ok(()),
+                    Some(s) => Err(self.invalid(format!(
+                        "Invalid attribute type name found; type: {}, valid types: {{ B,NULL,SS,BOOL,L,BS,N,NS,S,M }}",
+                        s
+                    ))),
+                    None => Err(self.invalid(
+                        "Incorrect operand type for operator or function; operator or function: attribute_type",
+                    )),
+                }
+            }
+            "begins_with" => match operand_type(&args[1])? {
+                None | Some(AttributeType::S) | Some(AttributeType::B) => Ok(()),
+                Some(other) => Err(self.invalid(format!(
+                    "Incorrect operand type for operator or function; operator or function: begins_with, operand type: {}",
+                    other
+                ))),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn evaluate(&self, condition: &parser::Condition, item: &Item) -> Result<bool> {
+        use parser::Condition::*;
+
+        Ok(match condition {
+            Compare(left, comparator, right) => {
+                let left = self.operand(left, item)?;
+                let right = self.operand(right, item)?;
+                match (left, right) {
+                    (Some(left), Some(right)) => compare(&left, *comparator, &right),
+                    _ => *comparator == Comparator::Ne,
+                }
+            }
+            Between(operand, low, high) => {
+                let operand = self.operand(operand, item)?;
+                let low = self.operand(low, item)?;
+                let high = self.operand(high, item)?;
+                match (operand, low, high) {
+                    (Some(operand), Some(low), Some(high)) => {
+                        compare(&operand, Comparator::Ge, &low)
+                            && compare(&operand, Comparator::Le, &high)
+                    }
+                    _ => false,
+                }
+            }
+            In(operand, list) => match self.operand(operand, item)? {
+                Some(operand) => {
+                    for candidate in list {
+                        if let Some(candidate) = self.operand(candidate, item)? {
+                            if values_equal(&operand, &candidate) {
+                                return Ok(true);
+                            }
+                        }
+                    }
+                    false
+                }
+                None => false,
+            },
+            Function(name, args) => self.function(name, args, item)?,
+            And(conditions) => {
+                for condition in conditions {
+                    if !self.evaluate(condition, item)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            Or(conditions) => {
+                for condition in conditions {
+                    if self.evaluate(condition, item)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            Not(condition) => !self.evaluate(condition, item)?,
+        })
+    }
+
+    fn function(&self, name: &str, args: &[Operand], item: &Item) -> Result<bool> {
+        let attribute = self.operand(&args[0], item)?;
+        if name == "attribute_exists" {
+            return Ok(attribute.is_some());
+        } else if name == "attribute_not_exists" {
+            return Ok(attribute.is_none());
+        }
+
+        let (attribute, operand) = match (attribute, self.operand(&args[1], item)?) {
+            (Some(attribute), Some(operand)) => (attribute, operand),
+            _ => return Ok(false),
+        };
+        Ok(match name {
+            "attribute_type" => type_of(&attribute) == operand.s.as_deref().and_then(parse_type),
+            "begins_with" => {
+                if let (Some(s), Some(prefix)) = (&attribute.s, &operand.s) {
+                    s.starts_with(prefix.as_str())
+                } else if let (Some(b), Some(prefix)) = (&attribute.b, &operand.b) {
+                    b.starts_with(prefix)
+                } else {
+                    false
+                }
+            }
+            "contains" => contains(&attribute, &operand),
+            _ => unreachable!("functions are checked before evaluation"),
+        })
+    }
+}
+
+fn compare(left: &AttributeValue, comparator: Comparator, right: &AttributeValue) -> bool {
+    match comparator {
+        Comparator::Eq => values_equal(left, right),
+        Comparator::Ne => !values_equal(left, right),
+        Comparator::Lt => compare_values(left, right) == Some(Ordering::Less),
+        Comparator::Le => matches!(
+            compare_values(left, right),
+            Some(Ordering::Less) | Some(Ordering::Equal)
+        ),
+        Comparator::Gt => compare_values(left, right) == Some(Ordering::Greater),
+        Comparator::Ge => matches!(
+            compare_values(left, right),
+            Some(Ordering::Greater) | Some(Ordering::Equal)
+        ),
+    }
+}
+
+/// The `contains` function: a substring of a string, or a member of a set or list.
+fn contains(attribute: &AttributeValue, operand: &AttributeValue) -> bool {
+    if let (Some(s), Some(substring)) = (&attribute.s, &operand.s) {
+        s.contains(substring.as_str())
+    } else if let (Some(b), Some(needle)) = (&attribute.b, &operand.b) {
+        needle.is_empty()
+            || b.windows(needle.len())
+                .any(|window| window == needle.as_ref())
+    } else if let (Some(ss), Some(s)) = (&attribute.ss, &operand.s) {
+        ss.contains(s)
+    } else if let (Some(ns), Some(n)) = (&attribute.ns, &operand.n) {
+        ns.iter().any(|member| numbers_equal(member, n))
+    } else if let (Some(bs), Some(b)) = (&attribute.bs, &operand.b) {
+        bs.contains(b)
+    } else if let Some(l) = &attribute.l {
+        l.iter().any(|element| values_equal(element, operand))
+    } else {
+        false
+    }
+}
+
+/// The `size` function: the length in bytes of a string or binary value, or the number of
+/// elements in a set, list, or map.
+fn size(value: &AttributeValue) -> Option<usize> {
+    if let Some(s) = &value.s {
+        Some(s.len())
+    } else if let Some(b) = &value.b {
+        Some(b.len())
+    } else if let Some(ss) = &value.ss {
+        Some(ss.len())
+    } else if let Some(ns) = &value.ns {
+        Some(ns.len())
+    } else if let Some(bs) = &value.bs {
+        Some(bs.len())
+    } else if let Some(l) = &value.l {
+        Some(l.len())
+    } else {
+        value.m.as_ref().map(|m| m.len())
+    }
+}
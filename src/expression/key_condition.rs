@@ -0,0 +1,196 @@
+use super::{AttributeValue, Comparator, Expression, Placeholders};
+use crate::error::ErrorImpl;
+use crate::{to_attribute_value, Result};
+use rusoto_dynamodb::QueryInput;
+use serde::Serialize;
+
+/// A typed [KeyConditionExpression] for a [query] call.
+///
+/// A key condition always tests the partition key for equality, and may additionally test the
+/// sort key with one of the comparisons in [`SortKeyCondition`]. Nothing else is allowed by
+/// DynamoDB, and nothing else can be built with this type.
+///
+/// ```
+/// use serde_dynamo::expression::{KeyCondition, SortKeyCondition};
+/// # use rusoto_dynamodb::{DynamoDb, DynamoDbClient, QueryInput};
+/// #
+/// # async fn query(client: &DynamoDbClient) -> Result<(), Box<dyn std::error::Error>> {
+/// # let user_type = "user";
+/// # let yesterday = "1985-04-21";
+///
+/// let mut input = QueryInput {
+///     table_name: "users".to_string(),
+///     index_name: Some("by_type_and_last_login".to_string()),
+///     ..QueryInput::default()
+/// };
+///
+/// // Fill in the key condition, along with its names and values
+/// KeyCondition::partition_key("user_type", user_type)
+///     .sort_key("last_login", SortKeyCondition::gt(yesterday))
+///     .apply_to(&mut input)?;
+///
+/// client.query(input).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The placeholders of a built key condition start with `#k` and `:k`, so a filter built with
+/// [`Condition`](super::Condition) can share the same request.
+///
+/// [KeyConditionExpression]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Query.html#Query.KeyConditionExpressions
+/// [query]: https://docs.rs/rusoto_dynamodb/0.45.0/rusoto_dynamodb/trait.DynamoDb.html#tymethod.query
+#[derive(Debug, Clone)]
+pub struct KeyCondition {
+    partition_key: String,
+    partition_value: Result<AttributeValue>,
+    sort_key: Option<(String, SortKeyCondition)>,
+}
+
+/// The comparisons DynamoDB allows on a sort key.
+#[derive(Debug, Clone)]
+pub struct SortKeyCondition(SortKeyConditionImpl);
+
+#[derive(Debug, Clone)]
+enum SortKeyConditionImpl {
+    /// Any comparison but `<>`, which DynamoDB doesn't allow on keys
+    Compare(Comparator, Result<AttributeValue>),
+    Between(Box<(Result<AttributeValue>, Result<AttributeValue>)>),
+    BeginsWith(Result<AttributeValue>),
+}
+
+impl SortKeyCondition {
+    fn compare<T: Serialize>(comparator: Comparator, value: T) -> Self {
+        SortKeyCondition(SortKeyConditionImpl::Compare(
+            comparator,
+            to_attribute_value(value),
+        ))
+    }
+
+    /// `sort_key = value`
+    pub fn eq<T: Serialize>(value: T) -> Self {
+        SortKeyCondition::compare(Comparator::Eq, value)
+    }
+
+    /// `sort_key < value`
+    pub fn lt<T: Serialize>(value: T) -> Self {
+        SortKeyCondition::compare(Comparator::Lt, value)
+    }
+
+    /// `sort_key <= value`
+    pub fn le<T: Serialize>(value: T) -> Self {
+        SortKeyCondition::compare(Comparator::Le, value)
+    }
+
+    /// `sort_key > value`
+    pub fn gt<T: Serialize>(value: T) -> Self {
+        SortKeyCondition::compare(Comparator::Gt, value)
+    }
+
+    /// `sort_key >= value`
+    pub fn ge<T: Serialize>(value: T) -> Self {
+        SortKeyCondition::compare(Comparator::Ge, value)
+    }
+
+    /// `sort_key BETWEEN low AND high`
+    pub fn between<L: Serialize, H: Serialize>(low: L, high: H) -> Self {
+        SortKeyCondition(SortKeyConditionImpl::Between(Box::new((
+            to_attribute_value(low),
+            to_attribute_value(high),
+        ))))
+    }
+
+    /// `begins_with(sort_key, prefix)`
+    ///
+    /// Only string and binary sort keys can be matched by prefix.
+    pub fn begins_with<T: Serialize>(prefix: T) -> Self {
+        SortKeyCondition(SortKeyConditionImpl::BeginsWith(to_attribute_value(prefix)))
+    }
+}
+
+impl KeyCondition {
+    /// Match items whose partition key equals `value`.
+    pub fn partition_key<T: Serialize>(name: impl Into<String>, value: T) -> Self {
+        KeyCondition {
+            partition_key: name.into(),
+            partition_value: to_attribute_value(value),
+            sort_key: None,
+        }
+    }
+
+    /// Additionally match the sort key.
+    pub fn sort_key(mut self, name: impl Into<String>, condition: SortKeyCondition) -> Self {
+        self.sort_key = Some((name.into(), condition));
+        self
+    }
+
+    /// Render the key condition into an expression with `#k`/`:k` placeholders.
+    ///
+    /// Fails if a value couldn't be serialized, or if it can't be a key: keys must be strings,
+    /// numbers, or binary.
+    pub fn build(self) -> Result<Expression> {
+        let mut placeholders = Placeholders::new("k");
+
+        let name = placeholders.name(&self.partition_key);
+        let value = placeholders.value(key_value(self.partition_value)?);
+        let mut expression = format!("{} = {}", name, value);
+
+        if let Some((sort_key, condition)) = self.sort_key {
+            let name = placeholders.name(&sort_key);
+            let condition = match condition.0 {
+                SortKeyConditionImpl::Compare(comparator, value) => {
+                    let value = placeholders.value(key_value(value)?);
+                    format!("{} {} {}", name, comparator, value)
+                }
+                SortKeyConditionImpl::Between(bounds) => {
+                    let (low, high) = *bounds;
+                    let low = placeholders.value(key_value(low)?);
+                    let high = placeholders.value(key_value(high)?);
+                    format!("{} BETWEEN {} AND {}", name, low, high)
+                }
+                SortKeyConditionImpl::BeginsWith(prefix) => {
+                    let prefix = key_value(prefix)?;
+                    if prefix.n.is_some() {
+                        return Err(ErrorImpl::InvalidExpression(String::from(
+                            "begins_with can't be used with a number sort key",
+                        ))
+                        .into());
+                    }
+                    let prefix = placeholders.value(prefix);
+                    format!("begins_with({}, {})", name, prefix)
+                }
+            };
+            expression = format!("{} AND {}", expression, condition);
+        }
+
+        Ok(placeholders.finish(expression))
+    }
+
+    /// Build the key condition and store it in `input`, adding its names and values to any
+    /// that are already there.
+    pub fn apply_to(self, input: &mut QueryInput) -> Result<()> {
+        let expression = self.build()?;
+        input.key_condition_expression = Some(expression.expression);
+        input
+            .expression_attribute_names
+            .get_or_insert_with(Default::default)
+            .extend(expression.names);
+        input
+            .expression_attribute_values
+            .get_or_insert_with(Default::default)
+            .extend(expression.values);
+        Ok(())
+    }
+}
+
+/// Check that a value can be compared against a key attribute.
+fn key_value(value: Result<AttributeValue>) -> Result<AttributeValue> {
+    let value = value?;
+    if value.s.is_some() || value.n.is_some() || value.b.is_some() {
+        Ok(value)
+    } else {
+        Err(ErrorImpl::InvalidExpression(String::from(
+            "Key attributes must be strings, numbers, or binary",
+        ))
+        .into())
+    }
+}
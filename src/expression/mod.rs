@@ -0,0 +1,432 @@
+//! Building DynamoDB expressions from strongly-typed data.
+//!
+//! DynamoDB's [UpdateExpression]s, [ConditionExpression]s, and friends are strings that refer
+//! to attribute names and values through placeholders (`#name` and `:value`). The types in this
+//! module build those strings along with the [ExpressionAttributeNames] and
+//! [ExpressionAttributeValues] maps that go with them.
+//!
+//! Condition and filter expressions can also be [evaluated](evaluate) against an [`Item`] locally,
+//! and UpdateExpressions [applied](apply) to one, which makes them testable without a table.
+//! Two versions of an item can be compared attribute by attribute with [`changed_fields`].
+//!
+//! [UpdateExpression]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Expressions.UpdateExpressions.html
+//! [ConditionExpression]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Expressions.ConditionExpressions.html
+//! [ExpressionAttributeNames]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Expressions.ExpressionAttributeNames.html
+//! [ExpressionAttributeValues]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Expressions.ExpressionAttributeValues.html
+
+use super::{AttributeValue, Item};
+use crate::error::ErrorImpl;
+use crate::number::Number;
+use crate::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display};
+
+mod apply;
+mod changes;
+mod condition;
+mod eval;
+mod key_condition;
+pub(crate) mod parser;
+mod update;
+
+#[cfg(test)]
+mod tests;
+
+pub use apply::{apply, ParsedUpdate, ReturnValues, UpdateOutcome};
+pub use changes::{changed_fields, changed_fields_of, FieldChange};
+pub use condition::{AttributeType, Comparator, Condition, Operand};
+pub use eval::{evaluate, ParsedCondition};
+pub use key_condition::{KeyCondition, SortKeyCondition};
+pub use update::diff;
+
+#[cfg(feature = "mock")]
+pub(crate) use eval::Context;
+#[cfg(feature = "mock")]
+pub(crate) use parser::{parse_projection, placeholders};
+
+/// The maximum length, in bytes, of any expression string DynamoDB accepts.
+pub const MAX_EXPRESSION_LENGTH: usize = 4096;
+
+/// An expression string together with the names and values its placeholders refer to.
+///
+/// The maps are keyed by placeholder, exactly as DynamoDB expects them in
+/// `ExpressionAttributeNames` and `ExpressionAttributeValues`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Expression {
+    /// The expression itself, e.g. `SET #u0 = :u0`
+    pub expression: String,
+    /// Placeholders for attribute names, e.g. `#u0` => `name`
+    pub names: HashMap<String, String>,
+    /// Placeholders for attribute values, e.g. `:u0` => `{"S": "Arthur Dent"}`
+    pub values: HashMap<String, AttributeValue>,
+}
+
+impl Expression {
+    /// The names map, or `None` if it is empty.
+    ///
+    /// DynamoDB rejects empty `ExpressionAttributeNames`, so this is the form rusoto's inputs want.
+    pub fn expression_attribute_names(&self) -> Option<HashMap<String, String>> {
+        if self.names.is_empty() {
+            None
+        } else {
+            Some(self.names.clone())
+        }
+    }
+
+    /// The values map, or `None` if it is empty.
+    ///
+    /// DynamoDB rejects empty `ExpressionAttributeValues`, so this is the form rusoto's inputs
+    /// want.
+    pub fn expression_attribute_values(&self) -> Option<HashMap<String, AttributeValue>> {
+        if self.values.is_empty() {
+            None
+        } else {
+            Some(self.values.clone())
+        }
+    }
+
+    /// Evaluate this expression as a condition against `item`.
+    ///
+    /// This is a shortcut for [`evaluate`] with the expression's own names and values.
+    pub fn evaluate(&self, item: &Item) -> Result<bool> {
+        evaluate(&self.expression, item, &self.names, &self.values)
+    }
+
+    /// Apply this expression as an UpdateExpression to `item`.
+    ///
+    /// This is a shortcut for [`apply`] with the expression's own names and values.
+    pub fn apply(&self, item: &Item) -> Result<UpdateOutcome> {
+        apply(&self.expression, item, &self.names, &self.values)
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+/// A path to an attribute, possibly nested inside maps and lists, e.g. `address.lines[1]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Path(Vec<PathElement>);
+
+/// A single step of a [`Path`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathElement {
+    /// A named attribute of an item, or a key of a map
+    Attribute(String),
+    /// An index into a list
+    Index(usize),
+}
+
+impl Path {
+    /// A path to a top-level attribute.
+    ///
+    /// The name is used as-is; dots and brackets in it are not interpreted.
+    pub fn attribute(name: impl Into<String>) -> Self {
+        Path(vec![PathElement::Attribute(name.into())])
+    }
+
+    /// Extend the path to a key of the map it refers to.
+    pub fn key(mut self, name: impl Into<String>) -> Self {
+        self.0.push(PathElement::Attribute(name.into()));
+        self
+    }
+
+    /// Extend the path to an element of the list it refers to.
+    pub fn index(mut self, index: usize) -> Self {
+        self.0.push(PathElement::Index(index));
+        self
+    }
+
+    /// The elements of this path, outermost first.
+    pub fn elements(&self) -> &[PathElement] {
+        &self.0
+    }
+
+    /// The value this path refers to in `item`, if there is one.
+    pub fn get<'a>(&self, item: &'a Item) -> Option<&'a AttributeValue> {
+        let (first, rest) = self.0.split_first()?;
+        let mut value = match first {
+            PathElement::Attribute(name) => item.get(name)?,
+            PathElement::Index(_) => return None,
+        };
+        for element in rest {
+            value = match element {
+                PathElement::Attribute(name) => value.m.as_ref()?.get(name)?,
+                PathElement::Index(index) => value.l.as_ref()?.get(*index)?,
+            };
+        }
+        Some(value)
+    }
+
+    /// The value this path refers to in `item`, mutably.
+    pub(crate) fn get_mut<'a>(&self, item: &'a mut Item) -> Option<&'a mut AttributeValue> {
+        let (first, rest) = self.0.split_first()?;
+        let mut value = match first {
+            PathElement::Attribute(name) => item.get_mut(name)?,
+            PathElement::Index(_) => return None,
+        };
+        for element in rest {
+            value = match element {
+                PathElement::Attribute(name) => value.m.as_mut()?.get_mut(name)?,
+                PathElement::Index(index) => value.l.as_mut()?.get_mut(*index)?,
+            };
+        }
+        Some(value)
+    }
+
+    /// The path to the map or list this path is inside of, or `None` for a top-level attribute.
+    pub(crate) fn parent(&self) -> Option<Path> {
+        if self.0.len() > 1 {
+            Some(Path(self.0[..self.0.len() - 1].to_vec()))
+        } else {
+            None
+        }
+    }
+
+    /// Whether the path refers to a top-level attribute.
+    pub fn is_top_level(&self) -> bool {
+        self.0.len() == 1
+    }
+
+    /// Whether one path is equal to, or nested inside, the other.
+    ///
+    /// DynamoDB rejects expressions that act on overlapping paths.
+    pub fn overlaps(&self, other: &Path) -> bool {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .all(|(left, right)| left == right)
+    }
+}
+
+impl From<&str> for Path {
+    fn from(name: &str) -> Self {
+        Path::attribute(name)
+    }
+}
+
+impl From<String> for Path {
+    fn from(name: String) -> Self {
+        Path::attribute(name)
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, element) in self.0.iter().enumerate() {
+            match element {
+                PathElement::Attribute(name) if idx == 0 => f.write_str(name)?,
+                PathElement::Attribute(name) => write!(f, ".{}", name)?,
+                PathElement::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Copy the values at `paths` out of `item`, keeping their nesting.
+///
+/// This is how DynamoDB projects attributes: elements picked out of a list end up in a shorter
+/// list, in their original order, and paths that don't exist are skipped.
+pub(crate) fn project(item: &Item, paths: &[Path]) -> Item {
+    #[derive(Default)]
+    struct Node {
+        value: Option<AttributeValue>,
+        keys: BTreeMap<String, Node>,
+        indexes: BTreeMap<usize, Node>,
+    }
+
+    impl Node {
+        fn into_value(self) -> AttributeValue {
+            if let Some(value) = self.value {
+                value
+            } else if !self.indexes.is_empty() {
+                AttributeValue {
+                    l: Some(self.indexes.into_values().map(Node::into_value).collect()),
+                    ..AttributeValue::default()
+                }
+            } else {
+                AttributeValue {
+                    m: Some(
+                        self.keys
+                            .into_iter()
+                            .map(|(key, node)| (key, node.into_value()))
+                            .collect(),
+                    ),
+                    ..AttributeValue::default()
+                }
+            }
+        }
+    }
+
+    let mut root = Node::default();
+    for path in paths {
+        let value = match path.get(item) {
+            Some(value) => value,
+            None => continue,
+        };
+        let mut node = &mut root;
+        for element in path.elements() {
+            if node.value.is_some() {
+                break;
+            }
+            node = match element {
+                PathElement::Attribute(name) => node.keys.entry(name.clone()).or_default(),
+                PathElement::Index(index) => node.indexes.entry(*index).or_default(),
+            };
+        }
+        if node.value.is_none() {
+            *node = Node {
+                value: Some(value.clone()),
+                ..Node::default()
+            };
+        }
+    }
+
+    root.keys
+        .into_iter()
+        .map(|(key, node)| (key, node.into_value()))
+        .collect()
+}
+
+/// Fail if an expression is longer than DynamoDB allows.
+pub(crate) fn check_length(expression: &str) -> Result<()> {
+    if expression.len() > MAX_EXPRESSION_LENGTH {
+        Err(ErrorImpl::ExpressionTooLong(expression.len()).into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Hands out placeholders for attribute names and values, reusing a placeholder whenever the
+/// same name or value is seen again.
+#[derive(Debug)]
+pub(crate) struct Placeholders {
+    prefix: &'static str,
+    names: Vec<String>,
+    values: Vec<AttributeValue>,
+}
+
+impl Placeholders {
+    /// Placeholders are generated as `#{prefix}{n}` and `:{prefix}{n}`, so expressions built with
+    /// different prefixes can share a single request.
+    pub(crate) fn new(prefix: &'static str) -> Self {
+        Placeholders {
+            prefix,
+            names: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    pub(crate) fn name(&mut self, name: &str) -> String {
+        let idx = match self.names.iter().position(|n| n == name) {
+            Some(idx) => idx,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() - 1
+            }
+        };
+        format!("#{}{}", self.prefix, idx)
+    }
+
+    pub(crate) fn value(&mut self, value: AttributeValue) -> String {
+        let idx = match self.values.iter().position(|v| *v == value) {
+            Some(idx) => idx,
+            None => {
+                self.values.push(value);
+                self.values.len() - 1
+            }
+        };
+        format!(":{}{}", self.prefix, idx)
+    }
+
+    pub(crate) fn path(&mut self, path: &Path) -> String {
+        let mut s = String::new();
+        for element in path.elements() {
+            match element {
+                PathElement::Attribute(name) => {
+                    if !s.is_empty() {
+                        s.push('.');
+                    }
+                    s.push_str(&self.name(name));
+                }
+                PathElement::Index(index) => {
+                    s.push_str(&format!("[{}]", index));
+                }
+            }
+        }
+        s
+    }
+
+    pub(crate) fn finish(self, expression: String) -> Expression {
+        let prefix = self.prefix;
+        Expression {
+            expression,
+            names: self
+                .names
+                .into_iter()
+                .enumerate()
+                .map(|(idx, name)| (format!("#{}{}", prefix, idx), name))
+                .collect(),
+            values: self
+                .values
+                .into_iter()
+                .enumerate()
+                .map(|(idx, value)| (format!(":{}{}", prefix, idx), value))
+                .collect(),
+        }
+    }
+}
+
+/// Whether two attribute values are the same as far as DynamoDB is concerned.
+///
+/// Numbers are compared by value, so `1` and `1.0` are equal, and sets are compared without
+/// regard to order.
+pub(crate) fn values_equal(left: &AttributeValue, right: &AttributeValue) -> bool {
+    if let (Some(l), Some(r)) = (&left.s, &right.s) {
+        l == r
+    } else if let (Some(l), Some(r)) = (&left.n, &right.n) {
+        numbers_equal(l, r)
+    } else if let (Some(l), Some(r)) = (&left.b, &right.b) {
+        l == r
+    } else if let (Some(l), Some(r)) = (&left.bool, &right.bool) {
+        l == r
+    } else if let (Some(l), Some(r)) = (&left.null, &right.null) {
+        l == r
+    } else if let (Some(l), Some(r)) = (&left.ss, &right.ss) {
+        l.iter().collect::<HashSet<_>>() == r.iter().collect::<HashSet<_>>()
+    } else if let (Some(l), Some(r)) = (&left.ns, &right.ns) {
+        number_set(l) == number_set(r)
+    } else if let (Some(l), Some(r)) = (&left.bs, &right.bs) {
+        l.iter().collect::<HashSet<_>>() == r.iter().collect::<HashSet<_>>()
+    } else if let (Some(l), Some(r)) = (&left.l, &right.l) {
+        l.len() == r.len() && l.iter().zip(r.iter()).all(|(l, r)| values_equal(l, r))
+    } else if let (Some(l), Some(r)) = (&left.m, &right.m) {
+        items_equal(l, r)
+    } else {
+        false
+    }
+}
+
+/// Whether two items are the same as far as DynamoDB is concerned.
+pub(crate) fn items_equal(left: &Item, right: &Item) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .all(|(key, l)| right.get(key).is_some_and(|r| values_equal(l, r)))
+}
+
+fn numbers_equal(left: &str, right: &str) -> bool {
+    match (Number::parse(left), Number::parse(right)) {
+        (Ok(l), Ok(r)) => l == r,
+        _ => left == right,
+    }
+}
+
+fn number_set(ns: &[String]) -> HashSet<Result<Number, &str>> {
+    ns.iter()
+        .map(|n| Number::parse(n).map_err(|_| n.as_str()))
+        .collect()
+}
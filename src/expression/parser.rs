@@ -0,0 +1,560 @@
+//! A parser for DynamoDB's expression grammar.
+//!
+//! The parser only produces a syntax tree. Placeholders are left unresolved, so the same tree can
+//! be evaluated against different names and values.
+
+use super::Comparator;
+use crate::error::ErrorImpl;
+use crate::Result;
+use std::fmt::{self, Display};
+
+/// A document path as written in an expression, with `#placeholders` not yet substituted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RawPath(pub(crate) Vec<RawPathElement>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RawPathElement {
+    /// A name written directly in the expression
+    Name(String),
+    /// A `#placeholder`, including the `#`
+    Placeholder(String),
+    /// A list index
+    Index(usize),
+}
+
+/// An operand of a condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Operand {
+    Path(RawPath),
+    /// A `:placeholder`, including the `:`
+    Value(String),
+    Size(RawPath),
+}
+
+/// A condition, as used in ConditionExpressions, FilterExpressions, and KeyConditionExpressions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Condition {
+    Compare(Operand, Comparator, Operand),
+    Between(Operand, Operand, Operand),
+    In(Operand, Vec<Operand>),
+    Function(String, Vec<Operand>),
+    /// Two or more conditions joined by `AND`, kept flat so long chains don't nest
+    And(Vec<Condition>),
+    /// Two or more conditions joined by `OR`
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+/// An operand on the right-hand side of a `SET` action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum UpdateOperand {
+    Path(RawPath),
+    /// A `:placeholder`, including the `:`
+    Value(String),
+    IfNotExists(RawPath, Box<UpdateOperand>),
+    ListAppend(Box<UpdateOperand>, Box<UpdateOperand>),
+}
+
+/// The right-hand side of a `SET` action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SetValue {
+    Operand(UpdateOperand),
+    Plus(UpdateOperand, UpdateOperand),
+    Minus(UpdateOperand, UpdateOperand),
+}
+
+/// A single action of an UpdateExpression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum UpdateAction {
+    Set(RawPath, SetValue),
+    Remove(RawPath),
+    /// `ADD path :value`
+    Add(RawPath, String),
+    /// `DELETE path :subset`
+    Delete(RawPath, String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Token {
+    /// A bare word: an attribute name, keyword, or function name
+    Word(String),
+    NamePlaceholder(String),
+    ValuePlaceholder(String),
+    Integer(usize),
+    Symbol(&'static str),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(s) | Token::NamePlaceholder(s) | Token::ValuePlaceholder(s) => {
+                f.write_str(s)
+            }
+            Token::Integer(n) => write!(f, "{}", n),
+            Token::Symbol(s) => f.write_str(s),
+        }
+    }
+}
+
+const SYMBOLS: &[&str] = &[
+    "<>", "<=", ">=", "=", "<", ">", "(", ")", "[", "]", ",", ".", "+", "-",
+];
+
+pub(crate) fn syntax_error(kind: &str, message: impl Display) -> crate::Error {
+    ErrorImpl::Validation(format!("Invalid {}: {}", kind, message)).into()
+}
+
+fn tokenize(kind: &str, input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+            continue;
+        }
+
+        let word_len = |s: &str| {
+            s.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(s.len())
+        };
+
+        if c == '#' || c == ':' {
+            let len = 1 + word_len(&rest[1..]);
+            if len == 1 {
+                return Err(syntax_error(
+                    kind,
+                    format!("Syntax error; token: \"{}\", near: \"{}\"", c, rest),
+                ));
+            }
+            let placeholder = rest[..len].to_string();
+            tokens.push(if c == '#' {
+                Token::NamePlaceholder(placeholder)
+            } else {
+                Token::ValuePlaceholder(placeholder)
+            });
+            rest = &rest[len..];
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let n = rest[..len].parse().map_err(|_| {
+                syntax_error(kind, format!("List index is too large: {}", &rest[..len]))
+            })?;
+            tokens.push(Token::Integer(n));
+            rest = &rest[len..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = word_len(rest);
+            tokens.push(Token::Word(rest[..len].to_string()));
+            rest = &rest[len..];
+        } else {
+            return Err(syntax_error(
+                kind,
+                format!("Syntax error; token: \"{}\", near: \"{}\"", c, rest),
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+/// How deeply parentheses, `NOT`s, and update functions can nest before an expression is refused,
+/// so that deeply nested input fails instead of overflowing the stack.
+const MAX_NESTING_DEPTH: usize = 100;
+
+/// A recursive-descent parser over a token stream.
+pub(crate) struct Parser {
+    kind: &'static str,
+    tokens: Vec<Token>,
+    pos: usize,
+    /// How many nested constructs are being parsed
+    depth: usize,
+}
+
+impl Parser {
+    /// `kind` names the expression in error messages, e.g. `ConditionExpression`.
+    pub(crate) fn new(kind: &'static str, input: &str) -> Result<Self> {
+        let tokens = tokenize(kind, input)?;
+        if tokens.is_empty() {
+            return Err(syntax_error(kind, "The expression can not be empty;"));
+        }
+        Ok(Parser {
+            kind,
+            tokens,
+            pos: 0,
+            depth: 0,
+        })
+    }
+
+    pub(crate) fn error(&self, message: impl Display) -> crate::Error {
+        syntax_error(self.kind, message)
+    }
+
+    fn unexpected(&self) -> crate::Error {
+        match self.peek() {
+            Some(token) => self.error(format!("Syntax error; token: \"{}\"", token)),
+            None => self.error("Syntax error; token: \"<EOF>\""),
+        }
+    }
+
+    /// Parse a nested construct with `parse`, failing if the expression nests too deeply.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(ErrorImpl::InvalidExpression(format!(
+                "{} is nested more than {} levels deep",
+                self.kind, MAX_NESTING_DEPTH
+            ))
+            .into());
+        }
+        self.depth += 1;
+        let parsed = parse(self);
+        self.depth -= 1;
+        parsed
+    }
+
+    pub(crate) fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    pub(crate) fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    pub(crate) fn expect_end(&self) -> Result<()> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    pub(crate) fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if let Some(Token::Symbol(s)) = self.peek() {
+            if *s == symbol {
+                self.pos += 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub(crate) fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// Consume a keyword, which DynamoDB matches case-insensitively.
+    pub(crate) fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if let Some(Token::Word(word)) = self.peek() {
+            if word.eq_ignore_ascii_case(keyword) {
+                self.pos += 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether the next tokens are a function call, i.e. a word followed by `(`.
+    pub(crate) fn peek_function(&self) -> Option<&str> {
+        match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Word(name)), Some(Token::Symbol("("))) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn path(&mut self) -> Result<RawPath> {
+        let mut elements = vec![self.path_name()?];
+        loop {
+            if self.eat_symbol(".") {
+                elements.push(self.path_name()?);
+            } else if self.eat_symbol("[") {
+                match self.peek() {
+                    Some(Token::Integer(n)) => {
+                        elements.push(RawPathElement::Index(*n));
+                        self.pos += 1;
+                    }
+                    _ => return Err(self.unexpected()),
+                }
+                self.expect_symbol("]")?;
+            } else {
+                return Ok(RawPath(elements));
+            }
+        }
+    }
+
+    fn path_name(&mut self) -> Result<RawPathElement> {
+        match self.peek().cloned() {
+            Some(Token::Word(word)) => {
+                self.pos += 1;
+                Ok(RawPathElement::Name(word))
+            }
+            Some(Token::NamePlaceholder(placeholder)) => {
+                self.pos += 1;
+                Ok(RawPathElement::Placeholder(placeholder))
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    pub(crate) fn value_placeholder(&mut self) -> Result<String> {
+        match self.peek().cloned() {
+            Some(Token::ValuePlaceholder(placeholder)) => {
+                self.pos += 1;
+                Ok(placeholder)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn function_args<T>(&mut self, mut arg: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        self.expect_symbol("(")?;
+        let mut args = vec![arg(self)?];
+        while self.eat_symbol(",") {
+            args.push(arg(self)?);
+        }
+        self.expect_symbol(")")?;
+        Ok(args)
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        if let Some(name) = self.peek_function() {
+            if name == "size" {
+                self.pos += 1;
+                let mut args = self.function_args(Self::path)?;
+                if args.len() != 1 {
+                    return Err(self.error(format!(
+                        "Incorrect number of operands for operator or function; operator or function: size, number of operands: {}",
+                        args.len()
+                    )));
+                }
+                return Ok(Operand::Size(args.remove(0)));
+            }
+            return Err(self.error(format!(
+                "The function is not allowed to be used this way in an expression; function: {}",
+                name
+            )));
+        }
+        match self.peek() {
+            Some(Token::ValuePlaceholder(_)) => Ok(Operand::Value(self.value_placeholder()?)),
+            _ => Ok(Operand::Path(self.path()?)),
+        }
+    }
+
+    fn comparator(&mut self) -> Option<Comparator> {
+        let comparator = match self.peek() {
+            Some(Token::Symbol("=")) => Comparator::Eq,
+            Some(Token::Symbol("<>")) => Comparator::Ne,
+            Some(Token::Symbol("<")) => Comparator::Lt,
+            Some(Token::Symbol("<=")) => Comparator::Le,
+            Some(Token::Symbol(">")) => Comparator::Gt,
+            Some(Token::Symbol(">=")) => Comparator::Ge,
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(comparator)
+    }
+
+    /// Parse a full condition, up to the end of the input.
+    pub(crate) fn condition_expression(mut self) -> Result<Condition> {
+        let condition = self.condition()?;
+        self.expect_end()?;
+        Ok(condition)
+    }
+
+    fn condition(&mut self) -> Result<Condition> {
+        let mut conditions = vec![self.and_condition()?];
+        while self.eat_keyword("OR") {
+            conditions.push(self.and_condition()?);
+        }
+        Ok(match conditions.len() {
+            1 => conditions.remove(0),
+            _ => Condition::Or(conditions),
+        })
+    }
+
+    fn and_condition(&mut self) -> Result<Condition> {
+        let mut conditions = vec![self.not_condition()?];
+        while self.eat_keyword("AND") {
+            conditions.push(self.not_condition()?);
+        }
+        Ok(match conditions.len() {
+            1 => conditions.remove(0),
+            _ => Condition::And(conditions),
+        })
+    }
+
+    fn not_condition(&mut self) -> Result<Condition> {
+        if self.eat_keyword("NOT") {
+            let condition = self.nested(Self::not_condition)?;
+            Ok(Condition::Not(Box::new(condition)))
+        } else {
+            self.primary_condition()
+        }
+    }
+
+    fn primary_condition(&mut self) -> Result<Condition> {
+        if self.eat_symbol("(") {
+            let condition = self.nested(Self::condition)?;
+            self.expect_symbol(")")?;
+            return Ok(condition);
+        }
+
+        if let Some(name) = self.peek_function() {
+            if name != "size" {
+                let name = name.to_string();
+                self.pos += 1;
+                let args = self.function_args(Self::operand)?;
+                return Ok(Condition::Function(name, args));
+            }
+        }
+
+        let left = self.operand()?;
+        if let Some(comparator) = self.comparator() {
+            let right = self.operand()?;
+            Ok(Condition::Compare(left, comparator, right))
+        } else if self.eat_keyword("BETWEEN") {
+            let low = self.operand()?;
+            if !self.eat_keyword("AND") {
+                return Err(self.unexpected());
+            }
+            let high = self.operand()?;
+            Ok(Condition::Between(left, low, high))
+        } else if self.eat_keyword("IN") {
+            let values = self.function_args(Self::operand)?;
+            Ok(Condition::In(left, values))
+        } else {
+            Err(self.unexpected())
+        }
+    }
+}
+
+const UPDATE_CLAUSES: &[&str] = &["SET", "REMOVE", "ADD", "DELETE"];
+
+impl Parser {
+    /// Parse a full UpdateExpression, up to the end of the input.
+    pub(crate) fn update_expression(mut self) -> Result<Vec<UpdateAction>> {
+        let mut seen = Vec::new();
+        let mut actions = Vec::new();
+        while !self.at_end() {
+            let clause = match UPDATE_CLAUSES
+                .iter()
+                .find(|clause| self.eat_keyword(clause))
+            {
+                Some(clause) => *clause,
+                None => return Err(self.unexpected()),
+            };
+            if seen.contains(&clause) {
+                return Err(self.error(format!(
+                    "The \"{}\" section can only be used once in an update expression;",
+                    clause
+                )));
+            }
+            seen.push(clause);
+
+            loop {
+                let path = self.path()?;
+                actions.push(match clause {
+                    "SET" => {
+                        self.expect_symbol("=")?;
+                        UpdateAction::Set(path, self.set_value()?)
+                    }
+                    "REMOVE" => UpdateAction::Remove(path),
+                    "ADD" => UpdateAction::Add(path, self.value_placeholder()?),
+                    _ => UpdateAction::Delete(path, self.value_placeholder()?),
+                });
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        Ok(actions)
+    }
+
+    fn set_value(&mut self) -> Result<SetValue> {
+        let left = self.update_operand()?;
+        if self.eat_symbol("+") {
+            Ok(SetValue::Plus(left, self.update_operand()?))
+        } else if self.eat_symbol("-") {
+            Ok(SetValue::Minus(left, self.update_operand()?))
+        } else {
+            Ok(SetValue::Operand(left))
+        }
+    }
+
+    fn update_operand(&mut self) -> Result<UpdateOperand> {
+        if let Some(name) = self.peek_function() {
+            let name = name.to_string();
+            self.pos += 1;
+            self.expect_symbol("(")?;
+            let operand = match name.as_str() {
+                "if_not_exists" => {
+                    let path = self.path()?;
+                    self.expect_symbol(",")?;
+                    let operand = self.nested(Self::update_operand)?;
+                    UpdateOperand::IfNotExists(path, Box::new(operand))
+                }
+                "list_append" => {
+                    let left = self.nested(Self::update_operand)?;
+                    self.expect_symbol(",")?;
+                    let right = self.nested(Self::update_operand)?;
+                    UpdateOperand::ListAppend(Box::new(left), Box::new(right))
+                }
+                _ => return Err(self.error(format!("Invalid function name; function: {}", name))),
+            };
+            self.expect_symbol(")")?;
+            return Ok(operand);
+        }
+        match self.peek() {
+            Some(Token::ValuePlaceholder(_)) => Ok(UpdateOperand::Value(self.value_placeholder()?)),
+            _ => Ok(UpdateOperand::Path(self.path()?)),
+        }
+    }
+}
+
+/// Parse an UpdateExpression into its actions.
+pub(crate) fn parse_update(input: &str) -> Result<Vec<UpdateAction>> {
+    Parser::new("UpdateExpression", input)?.update_expression()
+}
+
+/// Parse a ProjectionExpression into the paths it lists.
+#[cfg(feature = "mock")]
+pub(crate) fn parse_projection(input: &str) -> Result<Vec<RawPath>> {
+    let mut parser = Parser::new("ProjectionExpression", input)?;
+    let mut paths = vec![parser.path()?];
+    while parser.eat_symbol(",") {
+        paths.push(parser.path()?);
+    }
+    parser.expect_end()?;
+    Ok(paths)
+}
+
+/// The `#name` and `:value` placeholders an expression uses.
+#[cfg(feature = "mock")]
+pub(crate) fn placeholders(kind: &str, input: &str) -> Result<(Vec<String>, Vec<String>)> {
+    let mut names = Vec::new();
+    let mut values = Vec::new();
+    for token in tokenize(kind, input)? {
+        match token {
+            Token::NamePlaceholder(name) => names.push(name),
+            Token::ValuePlaceholder(value) => values.push(value),
+            _ => {}
+        }
+    }
+    Ok((names, values))
+}
+
+/// Parse a condition expression into its syntax tree.
+pub(crate) fn parse_condition(kind: &'static str, input: &str) -> Result<Condition> {
+    Parser::new(kind, input)?.condition_expression()
+}
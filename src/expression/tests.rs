@@ -0,0 +1,941 @@
+use super::*;
+use crate::to_attribute_value;
+use maplit::hashmap;
+use serde_json::json;
+
+fn item(value: serde_json::Value) -> Item {
+    crate::to_item(value).unwrap()
+}
+
+/// Replace the placeholders in an expression with the names they stand for, so assertions can be
+/// written against something readable. Values are left as placeholders.
+fn with_names(expression: &Expression) -> String {
+    let mut names: Vec<_> = expression.names.iter().collect();
+    // Replace `#u10` before `#u1`
+    names.sort_by_key(|(placeholder, _)| std::cmp::Reverse(placeholder.len()));
+    let mut s = expression.expression.clone();
+    for (placeholder, name) in names {
+        s = s.replace(placeholder.as_str(), name);
+    }
+    s
+}
+
+fn string_set(values: &[&str]) -> AttributeValue {
+    AttributeValue {
+        ss: Some(values.iter().map(|s| s.to_string()).collect()),
+        ..AttributeValue::default()
+    }
+}
+
+#[test]
+fn path_display() {
+    let path = Path::attribute("address").key("lines").index(1);
+    assert_eq!(path.to_string(), "address.lines[1]");
+    assert!(!path.is_top_level());
+    assert!(path.overlaps(&Path::attribute("address")));
+    assert!(!path.overlaps(&Path::attribute("address").key("lines").index(2)));
+}
+
+#[test]
+fn placeholders_are_deduplicated() {
+    let mut placeholders = Placeholders::new("x");
+    assert_eq!(placeholders.name("a"), "#x0");
+    assert_eq!(placeholders.name("b"), "#x1");
+    assert_eq!(placeholders.name("a"), "#x0");
+    assert_eq!(placeholders.value(to_attribute_value(1).unwrap()), ":x0");
+    assert_eq!(placeholders.value(to_attribute_value(1).unwrap()), ":x0");
+    assert_eq!(
+        placeholders.path(&Path::attribute("b").index(3).key("a")),
+        "#x1[3].#x0"
+    );
+
+    let expression = placeholders.finish(String::from("#x0 = :x0"));
+    assert_eq!(
+        expression.names,
+        hashmap! {
+            String::from("#x0") => String::from("a"),
+            String::from("#x1") => String::from("b"),
+        }
+    );
+    assert_eq!(
+        expression.values,
+        hashmap! { String::from(":x0") => to_attribute_value(1).unwrap() }
+    );
+}
+
+#[test]
+fn diff_identical() {
+    let old = item(json!({ "id": "one", "n": 1, "tags": ["a", "b"] }));
+    assert_eq!(diff(&old, &old.clone()).unwrap(), None);
+}
+
+#[test]
+fn diff_numbers_by_value() {
+    let old = hashmap! { String::from("n") => AttributeValue { n: Some(String::from("1")), ..AttributeValue::default() } };
+    let new = hashmap! { String::from("n") => AttributeValue { n: Some(String::from("1.00")), ..AttributeValue::default() } };
+    assert_eq!(diff(&old, &new).unwrap(), None);
+}
+
+#[test]
+fn diff_numbers_with_extreme_exponents() {
+    for extreme in &["1.5e-9223372036854775808", "10e9223372036854775807"] {
+        let old = hashmap! { String::from("n") => AttributeValue { n: Some(extreme.to_string()), ..AttributeValue::default() } };
+        let new = hashmap! { String::from("n") => AttributeValue { n: Some(String::from("1")), ..AttributeValue::default() } };
+        let update = diff(&old, &new).unwrap().unwrap();
+        assert_eq!(with_names(&update), "SET n = :u0");
+    }
+}
+
+#[test]
+fn diff_top_level() {
+    let old = item(json!({ "id": "one", "changed": 1, "removed": true }));
+    let new = item(json!({ "id": "one", "changed": 2, "added": "yes" }));
+
+    let update = diff(&old, &new).unwrap().unwrap();
+    assert_eq!(
+        with_names(&update),
+        "SET added = :u0, changed = :u1 REMOVE removed"
+    );
+    assert_eq!(update.values[":u0"], to_attribute_value("yes").unwrap());
+    assert_eq!(update.values[":u1"], to_attribute_value(2).unwrap());
+    assert_eq!(update.expression_attribute_names().unwrap().len(), 3);
+}
+
+#[test]
+fn diff_nested_map() {
+    let old = item(json!({ "address": { "city": "Cottington", "zip": "12345", "extra": 1 } }));
+    let new = item(json!({ "address": { "city": "Islington", "zip": "12345" } }));
+
+    let update = diff(&old, &new).unwrap().unwrap();
+    assert_eq!(
+        with_names(&update),
+        "SET address.city = :u0 REMOVE address.extra"
+    );
+}
+
+#[test]
+fn diff_nested_map_rewritten_when_cheaper() {
+    let old = item(json!({ "address": { "a": 1, "b": 2, "c": 3 } }));
+    let new = item(json!({ "address": { "d": 4 } }));
+
+    let update = diff(&old, &new).unwrap().unwrap();
+    assert_eq!(with_names(&update), "SET address = :u0");
+    assert_eq!(
+        update.values[":u0"],
+        to_attribute_value(json!({ "d": 4 })).unwrap()
+    );
+}
+
+#[test]
+fn diff_list_elements() {
+    let old = item(json!({ "list": [1, { "a": 1 }, 3, 4] }));
+    let new = item(json!({ "list": [1, { "a": 2 }, 3] }));
+
+    let update = diff(&old, &new).unwrap().unwrap();
+    assert_eq!(with_names(&update), "SET list[1].a = :u0 REMOVE list[3]");
+}
+
+#[test]
+fn diff_list_append() {
+    let old = item(json!({ "list": [1, 2] }));
+    let new = item(json!({ "list": [1, 2, 3, 4] }));
+
+    let update = diff(&old, &new).unwrap().unwrap();
+    assert_eq!(with_names(&update), "SET list = list_append(list, :u0)");
+    assert_eq!(
+        update.values[":u0"],
+        to_attribute_value(json!([3, 4])).unwrap()
+    );
+
+    // Appending and changing an element at the same time would overlap
+    let new = item(json!({ "list": [0, 2, 3] }));
+    let update = diff(&old, &new).unwrap().unwrap();
+    assert_eq!(with_names(&update), "SET list = :u0");
+}
+
+#[test]
+fn diff_sets() {
+    let old = hashmap! { String::from("tags") => string_set(&["a", "b"]) };
+
+    let added = hashmap! { String::from("tags") => string_set(&["b", "a", "c"]) };
+    let update = diff(&old, &added).unwrap().unwrap();
+    assert_eq!(with_names(&update), "ADD tags :u0");
+    assert_eq!(update.values[":u0"], string_set(&["c"]));
+
+    let removed = hashmap! { String::from("tags") => string_set(&["b"]) };
+    let update = diff(&old, &removed).unwrap().unwrap();
+    assert_eq!(with_names(&update), "DELETE tags :u0");
+    assert_eq!(update.values[":u0"], string_set(&["a"]));
+
+    // ADD and DELETE can't act on the same attribute in one expression
+    let both = hashmap! { String::from("tags") => string_set(&["b", "c"]) };
+    let update = diff(&old, &both).unwrap().unwrap();
+    assert_eq!(with_names(&update), "SET tags = :u0");
+
+    // ADD and DELETE only work on top-level attributes
+    let old = hashmap! { String::from("m") => AttributeValue { m: Some(old), ..AttributeValue::default() } };
+    let new = hashmap! { String::from("m") => AttributeValue { m: Some(added), ..AttributeValue::default() } };
+    let update = diff(&old, &new).unwrap().unwrap();
+    assert_eq!(with_names(&update), "SET m.tags = :u0");
+}
+
+#[test]
+fn diff_number_sets_by_value() {
+    let ns = |values: &[&str]| AttributeValue {
+        ns: Some(values.iter().map(|s| s.to_string()).collect()),
+        ..AttributeValue::default()
+    };
+    let old = hashmap! { String::from("ns") => ns(&["1", "2.0"]) };
+    let new = hashmap! { String::from("ns") => ns(&["2", "1.0"]) };
+    assert_eq!(diff(&old, &new).unwrap(), None);
+}
+
+#[test]
+fn diff_falls_back_to_top_level_when_too_long() {
+    let keys: Vec<String> = (0..400).map(|idx| format!("key{}", idx)).collect();
+    let old: serde_json::Map<_, _> = keys.iter().map(|k| (k.clone(), json!(1))).collect();
+    let new: serde_json::Map<_, _> = keys.iter().map(|k| (k.clone(), json!(2))).collect();
+    let old = item(json!({ "m": old, "other": 1 }));
+    let new = item(json!({ "m": new, "other": 1 }));
+
+    let update = diff(&old, &new).unwrap().unwrap();
+    assert_eq!(with_names(&update), "SET m = :u0");
+}
+
+#[test]
+fn diff_too_long() {
+    let keys: Vec<String> = (0..600).map(|idx| format!("key{}", idx)).collect();
+    let old: Item = keys
+        .iter()
+        .map(|k| (k.clone(), to_attribute_value(1).unwrap()))
+        .collect();
+    let new: Item = keys
+        .iter()
+        .map(|k| (k.clone(), to_attribute_value(2).unwrap()))
+        .collect();
+
+    let err = diff(&old, &new).unwrap_err();
+    assert!(err.to_string().starts_with("Expression is "));
+}
+
+#[test]
+fn condition_functions() {
+    let condition = Condition::attribute_exists("id")
+        .and(Condition::attribute_not_exists(
+            Path::attribute("a").key("b"),
+        ))
+        .and(Condition::attribute_type("tags", AttributeType::SS))
+        .and(Condition::begins_with("name", "Art"))
+        .and(Condition::contains("tags", "admin"))
+        .build()
+        .unwrap();
+    assert_eq!(
+        with_names(&condition),
+        "attribute_exists(id) AND attribute_not_exists(a.b) AND attribute_type(tags, :c0) \
+         AND begins_with(name, :c1) AND contains(tags, :c2)"
+    );
+    assert_eq!(condition.values[":c0"], to_attribute_value("SS").unwrap());
+    assert_eq!(condition.values[":c1"], to_attribute_value("Art").unwrap());
+    assert_eq!(
+        condition.values[":c2"],
+        to_attribute_value("admin").unwrap()
+    );
+}
+
+#[test]
+fn condition_comparisons() {
+    let condition = Operand::path("a")
+        .eq(1)
+        .and(Operand::path("b").ne("x"))
+        .and(Operand::path("c").lt(2))
+        .and(Operand::path("d").le(2))
+        .and(Operand::size("e").gt(1))
+        .and(Operand::path("f").ge(1))
+        .and(Operand::path("g").compare(Comparator::Eq, Operand::path("h")))
+        .build()
+        .unwrap();
+    assert_eq!(
+        with_names(&condition),
+        "a = :c0 AND b <> :c1 AND c < :c2 AND d <= :c2 AND size(e) > :c0 AND f >= :c0 AND g = h"
+    );
+    assert_eq!(condition.values.len(), 3);
+}
+
+#[test]
+fn condition_between_and_in() {
+    let condition = Operand::path("age")
+        .between(18, 65)
+        .or(Operand::path("status").is_in(vec!["active", "pending"]))
+        .build()
+        .unwrap();
+    assert_eq!(
+        with_names(&condition),
+        "age BETWEEN :c0 AND :c1 OR status IN (:c2, :c3)"
+    );
+
+    let empty: Vec<&str> = Vec::new();
+    assert!(Operand::path("status").is_in(empty).build().is_err());
+    assert!(Operand::path("n").is_in(0..101).build().is_err());
+    assert!(Operand::path("n").is_in(0..100).build().is_ok());
+}
+
+#[test]
+fn condition_grouping() {
+    let condition = (Operand::path("a").eq(1).or(Operand::path("b").eq(2)))
+        .and(!Condition::attribute_exists("c"))
+        .build()
+        .unwrap();
+    assert_eq!(
+        with_names(&condition),
+        "(a = :c0 OR b = :c1) AND NOT (attribute_exists(c))"
+    );
+
+    let condition = !(Operand::path("a").eq(1).and(Operand::path("b").eq(1)));
+    assert_eq!(
+        with_names(&condition.build().unwrap()),
+        "NOT (a = :c0 AND b = :c0)"
+    );
+}
+
+#[test]
+fn condition_matches() {
+    #[derive(serde_derive::Serialize)]
+    struct Partial {
+        status: &'static str,
+        version: u32,
+        deleted_at: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ignored: Option<String>,
+    }
+
+    let condition = Condition::matches(&Partial {
+        status: "active",
+        version: 3,
+        deleted_at: None,
+        ignored: None,
+    })
+    .build()
+    .unwrap();
+    assert_eq!(
+        with_names(&condition),
+        "(attribute_not_exists(deleted_at) OR attribute_type(deleted_at, :c0)) \
+         AND status = :c1 AND version = :c2"
+    );
+
+    assert!(Condition::matches(&1).build().is_err());
+    assert!(Condition::matches(&serde_json::json!({})).build().is_err());
+}
+
+#[test]
+fn condition_serialization_error() {
+    // A list isn't a map-like value, which is only reported by `build`
+    let condition = Operand::path("a").eq(1).and(Condition::matches(&vec![1]));
+    assert!(condition.build().is_err());
+}
+
+#[test]
+fn key_condition_partition_only() {
+    let key = KeyCondition::partition_key("id", "fSsgVtal8TpP")
+        .build()
+        .unwrap();
+    assert_eq!(with_names(&key), "id = :k0");
+    assert_eq!(
+        key.values[":k0"],
+        to_attribute_value("fSsgVtal8TpP").unwrap()
+    );
+}
+
+#[test]
+fn key_condition_sort_key() {
+    let build = |condition| {
+        with_names(
+            &KeyCondition::partition_key("pk", "a")
+                .sort_key("sk", condition)
+                .build()
+                .unwrap(),
+        )
+    };
+
+    assert_eq!(build(SortKeyCondition::eq(1)), "pk = :k0 AND sk = :k1");
+    assert_eq!(build(SortKeyCondition::lt(1)), "pk = :k0 AND sk < :k1");
+    assert_eq!(build(SortKeyCondition::le(1)), "pk = :k0 AND sk <= :k1");
+    assert_eq!(build(SortKeyCondition::gt(1)), "pk = :k0 AND sk > :k1");
+    assert_eq!(build(SortKeyCondition::ge(1)), "pk = :k0 AND sk >= :k1");
+    assert_eq!(
+        build(SortKeyCondition::between(1, 2)),
+        "pk = :k0 AND sk BETWEEN :k1 AND :k2"
+    );
+    assert_eq!(
+        build(SortKeyCondition::begins_with("2021-")),
+        "pk = :k0 AND begins_with(sk, :k1)"
+    );
+    // Equal values share a placeholder
+    assert_eq!(build(SortKeyCondition::eq("a")), "pk = :k0 AND sk = :k0");
+}
+
+#[test]
+fn key_condition_invalid_values() {
+    assert!(KeyCondition::partition_key("pk", true).build().is_err());
+    assert!(KeyCondition::partition_key("pk", vec![1]).build().is_err());
+    assert!(KeyCondition::partition_key("pk", "a")
+        .sort_key("sk", SortKeyCondition::begins_with(1))
+        .build()
+        .is_err());
+    assert!(KeyCondition::partition_key("pk", "a")
+        .sort_key("sk", SortKeyCondition::between(1, Some(())))
+        .build()
+        .is_err());
+}
+
+#[test]
+fn key_condition_apply_to() {
+    let filter = Operand::path("status").eq("active").build().unwrap();
+    let mut input = rusoto_dynamodb::QueryInput {
+        table_name: String::from("users"),
+        filter_expression: Some(filter.expression.clone()),
+        expression_attribute_names: filter.expression_attribute_names(),
+        expression_attribute_values: filter.expression_attribute_values(),
+        ..rusoto_dynamodb::QueryInput::default()
+    };
+
+    KeyCondition::partition_key("user_type", "user")
+        .sort_key("last_login", SortKeyCondition::gt("1985-04-21"))
+        .apply_to(&mut input)
+        .unwrap();
+
+    assert_eq!(
+        input.key_condition_expression.as_deref(),
+        Some("#k0 = :k0 AND #k1 > :k1")
+    );
+    assert_eq!(input.expression_attribute_names.unwrap().len(), 3);
+    assert_eq!(input.expression_attribute_values.unwrap().len(), 3);
+}
+
+fn eval(expression: &str, item: &Item) -> Result<bool> {
+    let names = hashmap! {
+        String::from("#name") => String::from("name"),
+        String::from("#tags") => String::from("tags"),
+    };
+    let values = hashmap! {
+        String::from(":zero") => to_attribute_value(0).unwrap(),
+        String::from(":ten") => to_attribute_value(10).unwrap(),
+        String::from(":ten_point_zero") => to_attribute_value(10.0).unwrap(),
+        String::from(":nine") => to_attribute_value(9).unwrap(),
+        String::from(":arthur") => to_attribute_value("Arthur").unwrap(),
+        String::from(":art") => to_attribute_value("Art").unwrap(),
+        String::from(":a") => to_attribute_value("a").unwrap(),
+        String::from(":s") => to_attribute_value("S").unwrap(),
+        String::from(":n") => to_attribute_value("N").unwrap(),
+        String::from(":m") => to_attribute_value("M").unwrap(),
+        String::from(":bogus") => to_attribute_value("bogus").unwrap(),
+        String::from(":one") => to_attribute_value(1).unwrap(),
+        String::from(":tag") => to_attribute_value("blue").unwrap(),
+        String::from(":bytes") => to_attribute_value(serde_bytes::Bytes::new(b"ab")).unwrap(),
+    };
+    evaluate(expression, item, &names, &values)
+}
+
+fn eval_item() -> Item {
+    let mut item = item(json!({
+        "name": "Arthur",
+        "age": 10,
+        "address": { "lines": ["1 Main St", "Cottington"] },
+        "nothing": null,
+        "list": [1, "a"],
+    }));
+    item.insert(String::from("tags"), string_set(&["blue", "green"]));
+    item.insert(
+        String::from("bytes"),
+        to_attribute_value(serde_bytes::Bytes::new(b"abc")).unwrap(),
+    );
+    item
+}
+
+#[test]
+fn evaluate_comparisons() {
+    let item = eval_item();
+    assert!(eval("age = :ten", &item).unwrap());
+    assert!(eval("age = :ten_point_zero", &item).unwrap());
+    assert!(eval("age > :nine AND age >= :ten AND age <= :ten", &item).unwrap());
+    assert!(!eval("age < :nine", &item).unwrap());
+    assert!(eval("#name > :art", &item).unwrap());
+    assert!(eval("bytes > :bytes", &item).unwrap());
+    assert!(eval("age BETWEEN :nine AND :ten", &item).unwrap());
+    assert!(eval("#name IN (:a, :arthur)", &item).unwrap());
+    assert!(!eval("age IN (:zero, :one)", &item).unwrap());
+    // Strings are ordered by their bytes, so uppercase sorts before lowercase
+    assert!(eval("address.lines[1] < :a", &item).unwrap());
+}
+
+#[test]
+fn evaluate_type_mismatches_are_false() {
+    let item = eval_item();
+    assert!(!eval("#name = :ten", &item).unwrap());
+    assert!(!eval("#name < :ten", &item).unwrap());
+    assert!(!eval("#name >= :ten", &item).unwrap());
+    assert!(!eval("missing > :zero", &item).unwrap());
+    assert!(!eval("missing = :zero", &item).unwrap());
+    // `<>` is the negation of `=`
+    assert!(eval("#name <> :ten", &item).unwrap());
+    assert!(eval("missing <> :zero", &item).unwrap());
+}
+
+#[test]
+fn evaluate_functions() {
+    let item = eval_item();
+    assert!(eval("attribute_exists(address.lines[0])", &item).unwrap());
+    assert!(eval("attribute_not_exists(address.lines[2])", &item).unwrap());
+    assert!(eval("attribute_exists(nothing)", &item).unwrap());
+    assert!(eval("attribute_type(age, :n)", &item).unwrap());
+    assert!(eval("attribute_type(address, :m)", &item).unwrap());
+    assert!(!eval("attribute_type(age, :s)", &item).unwrap());
+    assert!(eval("begins_with(#name, :art)", &item).unwrap());
+    assert!(eval("begins_with(bytes, :bytes)", &item).unwrap());
+    assert!(!eval("begins_with(age, :a)", &item).unwrap());
+    assert!(eval("contains(#name, :art)", &item).unwrap());
+    assert!(eval("contains(#tags, :tag)", &item).unwrap());
+    assert!(eval("contains(list, :one)", &item).unwrap());
+    assert!(eval("contains(list, :a)", &item).unwrap());
+    assert!(!eval("contains(list, :ten)", &item).unwrap());
+    assert!(eval("size(#name) > :nine OR size(#tags) = :ten", &item).is_ok());
+    assert!(!eval("size(#name) > :nine", &item).unwrap());
+    assert!(eval("size(address.lines) < :ten", &item).unwrap());
+    assert!(!eval("size(missing) < :ten", &item).unwrap());
+}
+
+#[test]
+fn evaluate_logic_and_precedence() {
+    let item = eval_item();
+    assert!(eval("age = :zero OR age = :ten AND #name = :arthur", &item).unwrap());
+    assert!(!eval("(age = :zero OR age = :ten) AND #name = :a", &item).unwrap());
+    assert!(eval("NOT age = :zero", &item).unwrap());
+    assert!(eval("not (age = :zero and age = :ten)", &item).unwrap());
+    assert!(!eval("NOT NOT attribute_exists(missing)", &item).unwrap());
+}
+
+#[test]
+fn evaluate_errors() {
+    let item = eval_item();
+    let errors = [
+        "",
+        "age =",
+        "age = :ten)",
+        "age == :ten",
+        "age = :undefined",
+        "#undefined = :ten",
+        "age = :ten OR #undefined = :ten",
+        "unknown(age)",
+        "attribute_exists(:ten)",
+        "attribute_exists(age, :ten)",
+        "attribute_type(age, :bogus)",
+        "begins_with(#name, :ten)",
+        "age BETWEEN :ten AND :nine",
+        "size(age)",
+        "address.lines[x] = :ten",
+    ];
+    for expression in errors.iter() {
+        assert!(eval(expression, &item).is_err(), "{:?}", expression);
+    }
+}
+
+#[test]
+fn deep_nesting_is_refused() {
+    let item = eval_item();
+    let parens = format!("{}age = :ten{}", "(".repeat(2000), ")".repeat(2000));
+    assert_eq!(
+        eval(&parens, &item).unwrap_err().to_string(),
+        "Invalid expression: ConditionExpression is nested more than 100 levels deep"
+    );
+    let nots = format!("{}age = :ten", "NOT ".repeat(1000));
+    assert!(eval(&nots, &item).is_err());
+    let appends = format!(
+        "SET a = {}:one{}",
+        "list_append(:one, ".repeat(300),
+        ")".repeat(300)
+    );
+    assert!(update(&appends, &update_item()).is_err());
+
+    let shallow = format!("{}age = :ten{}", "(".repeat(100), ")".repeat(100));
+    assert!(eval(&shallow, &item).unwrap());
+
+    // Chains of ANDs and ORs aren't nesting, however long they are
+    let chain = vec!["age = :ten"; 200].join(" AND ");
+    assert!(eval(&chain, &item).unwrap());
+    let chain = vec!["age = :nine"; 200].join(" OR ");
+    assert!(!eval(&chain, &item).unwrap());
+    let chain = format!(
+        "{} OR age = :ten",
+        vec!["age = :nine AND age = :ten"; 110].join(" OR ")
+    );
+    assert!(eval(&chain, &item).unwrap());
+}
+
+#[test]
+fn evaluate_built_conditions() {
+    let item = eval_item();
+    let condition = Operand::path("age")
+        .between(5, 15)
+        .and(Condition::begins_with("name", "Art"))
+        .and(Operand::size(Path::attribute("address").key("lines")).eq(2));
+    assert!(condition.build().unwrap().evaluate(&item).unwrap());
+
+    let condition = Condition::matches(&json!({ "name": "Arthur", "missing": null }));
+    assert!(condition.build().unwrap().evaluate(&item).unwrap());
+
+    let condition = Condition::matches(&json!({ "name": "Ford" }));
+    assert!(!condition.build().unwrap().evaluate(&item).unwrap());
+}
+
+fn update(expression: &str, item: &Item) -> Result<UpdateOutcome> {
+    let names = hashmap! {
+        String::from("#name") => String::from("name"),
+    };
+    let values = hashmap! {
+        String::from(":one") => to_attribute_value(1).unwrap(),
+        String::from(":tenth") => AttributeValue {
+            n: Some(String::from("0.1")),
+            ..AttributeValue::default()
+        },
+        String::from(":big") => AttributeValue {
+            n: Some(String::from("9.9E125")),
+            ..AttributeValue::default()
+        },
+        String::from(":ford") => to_attribute_value("Ford").unwrap(),
+        String::from(":list") => to_attribute_value(vec![3, 4]).unwrap(),
+        String::from(":tags") => string_set(&["green", "red"]),
+        String::from(":blue") => string_set(&["blue"]),
+    };
+    apply(expression, item, &names, &values)
+}
+
+fn update_item() -> Item {
+    let mut item = item(json!({
+        "id": "abc",
+        "name": "Arthur",
+        "count": 0.2,
+        "list": [1, 2],
+        "address": { "lines": ["1 Main St", "Cottington"] },
+    }));
+    item.insert(String::from("tags"), string_set(&["blue", "green"]));
+    item
+}
+
+#[test]
+fn apply_set() {
+    let old = update_item();
+    let outcome = update(
+        "SET #name = :ford, count = count + :tenth, address.city = :ford, list[5] = :one",
+        &old,
+    )
+    .unwrap();
+    assert_eq!(outcome.new, {
+        let mut expected = item(json!({
+            "id": "abc",
+            "name": "Ford",
+            "count": 0.3,
+            "list": [1, 2, 1],
+            "address": { "lines": ["1 Main St", "Cottington"], "city": "Ford" },
+        }));
+        expected.insert(String::from("tags"), string_set(&["blue", "green"]));
+        expected
+    });
+    assert_eq!(outcome.old, old);
+    // Exact decimal arithmetic, not 0.30000000000000004
+    assert_eq!(outcome.new["count"].n.as_deref(), Some("0.3"));
+}
+
+#[test]
+fn apply_set_functions() {
+    let item = update_item();
+    let outcome = update(
+        "SET list = list_append(list, :list), other = if_not_exists(other, :one), #name = if_not_exists(#name, :ford), counter = if_not_exists(counter, :one) - :tenth",
+        &item,
+    )
+    .unwrap();
+    assert_eq!(
+        outcome.new["list"],
+        to_attribute_value(vec![1, 2, 3, 4]).unwrap()
+    );
+    assert_eq!(outcome.new["other"], to_attribute_value(1).unwrap());
+    assert_eq!(outcome.new["name"], to_attribute_value("Arthur").unwrap());
+    assert_eq!(outcome.new["counter"].n.as_deref(), Some("0.9"));
+}
+
+#[test]
+fn apply_actions_read_the_old_item() {
+    let item = update_item();
+    let outcome = update("SET a = #name, #name = :ford", &item).unwrap();
+    assert_eq!(outcome.new["a"], to_attribute_value("Arthur").unwrap());
+    assert_eq!(outcome.new["name"], to_attribute_value("Ford").unwrap());
+}
+
+#[test]
+fn apply_remove_add_delete() {
+    let item = update_item();
+    let outcome = update(
+        "REMOVE list[0], list[1], address.lines[0] ADD count :one, tags :tags, new_count :one DELETE missing :blue",
+        &item,
+    )
+    .unwrap();
+    assert_eq!(
+        outcome.new["list"],
+        to_attribute_value(Vec::<u8>::new()).unwrap()
+    );
+    assert_eq!(
+        outcome.new["address"],
+        to_attribute_value(json!({ "lines": ["Cottington"] })).unwrap()
+    );
+    assert_eq!(outcome.new["count"].n.as_deref(), Some("1.2"));
+    assert_eq!(outcome.new["tags"], string_set(&["blue", "green", "red"]));
+    assert_eq!(outcome.new["new_count"], to_attribute_value(1).unwrap());
+
+    let outcome = update("DELETE tags :tags", &item).unwrap();
+    assert_eq!(outcome.new["tags"], string_set(&["blue"]));
+
+    let outcome = update("delete tags :blue remove #name", &outcome.new).unwrap();
+    assert!(!outcome.new.contains_key("tags"));
+    assert!(!outcome.new.contains_key("name"));
+}
+
+#[test]
+fn apply_return_values() {
+    let item = update_item();
+    let outcome = update(
+        "SET #name = :ford, address.lines[1] = :ford REMOVE list",
+        &item,
+    )
+    .unwrap();
+
+    assert_eq!(outcome.attributes(ReturnValues::None), None);
+    assert_eq!(outcome.attributes(ReturnValues::AllOld), Some(item.clone()));
+    assert_eq!(
+        outcome.attributes(ReturnValues::AllNew),
+        Some(outcome.new.clone())
+    );
+    assert_eq!(
+        outcome.attributes(ReturnValues::UpdatedOld),
+        Some(
+            crate::to_item(json!({
+                "name": "Arthur",
+                "address": { "lines": ["Cottington"] },
+                "list": [1, 2],
+            }))
+            .unwrap()
+        )
+    );
+    assert_eq!(
+        outcome.attributes(ReturnValues::UpdatedNew),
+        Some(
+            crate::to_item(json!({
+                "name": "Ford",
+                "address": { "lines": ["Ford"] },
+            }))
+            .unwrap()
+        )
+    );
+
+    let outcome = update("SET #name = :ford", &Item::new()).unwrap();
+    assert_eq!(outcome.attributes(ReturnValues::AllOld), None);
+    assert_eq!(outcome.attributes(ReturnValues::UpdatedOld), None);
+
+    assert_eq!(
+        "UPDATED_NEW".parse::<ReturnValues>().unwrap(),
+        ReturnValues::UpdatedNew
+    );
+    assert_eq!(ReturnValues::AllOld.to_string(), "ALL_OLD");
+    assert!("ALL".parse::<ReturnValues>().is_err());
+}
+
+#[test]
+fn apply_errors() {
+    let item = update_item();
+    let errors = [
+        ("SET", "Invalid UpdateExpression: Syntax error; token: \"<EOF>\""),
+        (
+            "SET a = :one SET b = :one",
+            "Invalid UpdateExpression: The \"SET\" section can only be used once in an update expression;",
+        ),
+        (
+            "SET a = :undefined",
+            "Invalid UpdateExpression: An expression attribute value used in expression is not defined; attribute value: :undefined",
+        ),
+        (
+            "SET a = if_not_exists(a, :undefined)",
+            "Invalid UpdateExpression: An expression attribute value used in expression is not defined; attribute value: :undefined",
+        ),
+        (
+            "SET address = :one REMOVE address.lines",
+            "Invalid UpdateExpression: Two document paths overlap with each other; must remove or rewrite one of these paths; path one: [address], path two: [address, lines]",
+        ),
+        (
+            "SET a = :one REMOVE a",
+            "Invalid UpdateExpression: Two document paths conflict with each other; must remove or rewrite one of these paths; path one: [a], path two: [a]",
+        ),
+        (
+            "ADD #name :ford",
+            "Invalid UpdateExpression: Incorrect operand type for operator or function; operator: ADD, operand type: STRING",
+        ),
+        (
+            "DELETE tags :one",
+            "Invalid UpdateExpression: Incorrect operand type for operator or function; operator: DELETE, operand type: NUMBER",
+        ),
+        (
+            "SET a = missing",
+            "The provided expression refers to an attribute that does not exist in the item",
+        ),
+        (
+            "SET a = #name + :one",
+            "An operand in the update expression has an incorrect data type",
+        ),
+        (
+            "SET a = list_append(list, :one)",
+            "An operand in the update expression has an incorrect data type",
+        ),
+        (
+            "ADD #name :one",
+            "An operand in the update expression has an incorrect data type",
+        ),
+        (
+            "SET missing.a = :one",
+            "The document path provided in the update expression is invalid for update",
+        ),
+        (
+            "SET a = :big + :big",
+            "Number overflow. Attempting to store a number with magnitude larger than supported range",
+        ),
+    ];
+    for (expression, message) in errors.iter() {
+        assert_eq!(
+            update(expression, &item).unwrap_err().to_string(),
+            *message,
+            "{}",
+            expression
+        );
+    }
+}
+
+#[test]
+fn apply_round_trips_diff() {
+    let old = update_item();
+    let mut new = update_item();
+    new.insert(String::from("name"), to_attribute_value("Ford").unwrap());
+    new.remove("count");
+    new.insert(
+        String::from("list"),
+        to_attribute_value(vec![1, 2, 3]).unwrap(),
+    );
+    new.insert(String::from("tags"), string_set(&["blue", "red"]));
+
+    let outcome = diff(&old, &new).unwrap().unwrap().apply(&old).unwrap();
+    assert!(items_equal(&outcome.new, &new));
+}
+
+#[test]
+fn changed_fields_nested() {
+    let old = item(json!({
+        "id": "arthur",
+        "age": 42,
+        "address": { "city": "Cottington", "lines": ["155 Country Lane", "Cottington"] },
+        "friends": ["ford"],
+        "towel": true
+    }));
+    let new = item(json!({
+        "id": "arthur",
+        "age": 42.0,
+        "address": { "city": "Cottington", "lines": ["155 Country Lane", "London"] },
+        "friends": ["ford", "trillian"],
+        "planet": "Earth"
+    }));
+
+    let changes = changed_fields(&old, &new);
+    let paths: Vec<String> = changes.iter().map(|c| c.path.to_string()).collect();
+    assert_eq!(
+        paths,
+        vec!["address.lines[1]", "friends", "planet", "towel"]
+    );
+    assert_eq!(
+        changes[0].old,
+        Some(to_attribute_value("Cottington").unwrap())
+    );
+    assert_eq!(changes[0].new, Some(to_attribute_value("London").unwrap()));
+    assert_eq!(
+        changes[1].new.as_ref().unwrap().l.as_ref().unwrap().len(),
+        2
+    );
+    assert_eq!(changes[2].old, None);
+    assert_eq!(changes[3].new, None);
+}
+
+#[test]
+fn changed_fields_sets_and_types() {
+    let mut old = item(json!({ "scores": 1 }));
+    let mut new = item(json!({ "scores": "1" }));
+    old.insert(String::from("tags"), string_set(&["a", "b"]));
+    new.insert(String::from("tags"), string_set(&["b", "a"]));
+    old.insert(
+        String::from("ns"),
+        AttributeValue {
+            ns: Some(vec![String::from("1"), String::from("2.50")]),
+            ..AttributeValue::default()
+        },
+    );
+    new.insert(
+        String::from("ns"),
+        AttributeValue {
+            ns: Some(vec![String::from("2.5"), String::from("1")]),
+            ..AttributeValue::default()
+        },
+    );
+
+    let changes = changed_fields(&old, &new);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path, Path::attribute("scores"));
+
+    new.insert(String::from("tags"), string_set(&["a"]));
+    let changes = changed_fields(&old, &new);
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[1].path, Path::attribute("tags"));
+}
+
+#[test]
+fn changed_fields_numbers_with_extreme_exponents() {
+    for extreme in &["1.5e-9223372036854775808", "10e9223372036854775807"] {
+        let old = hashmap! { String::from("n") => AttributeValue { n: Some(extreme.to_string()), ..AttributeValue::default() } };
+        let new = hashmap! { String::from("n") => AttributeValue { n: Some(String::from("1")), ..AttributeValue::default() } };
+        let changes = changed_fields(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path.to_string(), "n");
+        assert_eq!(changes[0].old, old.get("n").cloned());
+    }
+}
+
+#[test]
+fn changed_fields_of_typed_values() {
+    #[derive(serde_derive::Serialize)]
+    struct User {
+        id: &'static str,
+        age: u8,
+    }
+
+    let changes = changed_fields_of(
+        &User {
+            id: "arthur",
+            age: 42,
+        },
+        &User {
+            id: "arthur",
+            age: 43,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        changes,
+        vec![FieldChange {
+            path: Path::attribute("age"),
+            old: Some(to_attribute_value(42).unwrap()),
+            new: Some(to_attribute_value(43).unwrap()),
+        }]
+    );
+
+    assert!(changed_fields_of(&1, &2).is_err());
+}
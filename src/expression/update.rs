@@ -0,0 +1,317 @@
+use super::{
+    values_equal, AttributeValue, Expression, Item, Path, Placeholders, MAX_EXPRESSION_LENGTH,
+};
+use crate::error::ErrorImpl;
+use crate::number::Number;
+use crate::Result;
+use std::collections::BTreeSet;
+
+/// Build the smallest [UpdateExpression] that turns `old` into `new`.
+///
+/// * Attributes that were added or changed are `SET`.
+/// * Attributes that were deleted are `REMOVE`d.
+/// * Top-level string, number, and binary sets gain and lose members through `ADD` and `DELETE`.
+/// * Changes deep inside maps and lists are written through nested document paths, such as
+///   `address.lines[1]`, rather than rewriting the whole attribute.
+/// * Elements appended to the end of a list are written with `list_append`.
+///
+/// Numbers are compared by value and sets without regard to order, so `to_item` output that
+/// only differs in formatting doesn't produce an update. If the items are equivalent, `None` is
+/// returned: DynamoDB rejects an empty UpdateExpression.
+///
+/// Paths never overlap. If the fine-grained expression is longer than DynamoDB's 4 KB limit,
+/// changed top-level attributes are replaced wholesale instead; if that is still too long, an
+/// error is returned.
+///
+/// ```
+/// # use serde::{Serialize, Deserialize};
+/// # use serde_dynamo::{expression, to_item};
+/// # use rusoto_dynamodb::UpdateItemInput;
+/// #
+/// # fn update() -> Result<(), Box<dyn std::error::Error>> {
+/// #[derive(Serialize, Deserialize)]
+/// pub struct User {
+///     id: String,
+///     name: String,
+///     age: u8,
+/// }
+///
+/// let old = to_item(User { id: "fSsgVtal8TpP".to_string(), name: "Arthur Dent".to_string(), age: 42 })?;
+/// let new = to_item(User { id: "fSsgVtal8TpP".to_string(), name: "Arthur Dent".to_string(), age: 43 })?;
+///
+/// let update = expression::diff(&old, &new)?.expect("the items differ");
+/// assert_eq!(update.expression, "SET #u0 = :u0");
+///
+/// let input = UpdateItemInput {
+///     table_name: "users".to_string(),
+///     update_expression: Some(update.expression.clone()),
+///     expression_attribute_names: update.expression_attribute_names(),
+///     expression_attribute_values: update.expression_attribute_values(),
+///     ..UpdateItemInput::default()
+/// };
+/// # Ok(())
+/// # }
+/// # update().unwrap()
+/// ```
+///
+/// [UpdateExpression]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Expressions.UpdateExpressions.html
+pub fn diff(old: &Item, new: &Item) -> Result<Option<Expression>> {
+    let mut actions = Vec::new();
+    diff_item(&mut actions, None, old, new, true);
+    if actions.is_empty() {
+        return Ok(None);
+    }
+
+    let expression = render(&actions);
+    if expression.expression.len() <= MAX_EXPRESSION_LENGTH {
+        return Ok(Some(expression));
+    }
+
+    let mut actions = Vec::new();
+    diff_item(&mut actions, None, old, new, false);
+    let expression = render(&actions);
+    if expression.expression.len() <= MAX_EXPRESSION_LENGTH {
+        Ok(Some(expression))
+    } else {
+        Err(ErrorImpl::ExpressionTooLong(expression.expression.len()).into())
+    }
+}
+
+#[derive(Debug)]
+enum Action {
+    Set(Path, AttributeValue),
+    Append(Path, AttributeValue),
+    Remove(Path),
+    Add(Path, AttributeValue),
+    Delete(Path, AttributeValue),
+}
+
+fn child(parent: Option<&Path>, key: &str) -> Path {
+    match parent {
+        Some(parent) => parent.clone().key(key),
+        None => Path::attribute(key),
+    }
+}
+
+/// Diff two maps (or, when `path` is `None`, two items).
+fn diff_item(actions: &mut Vec<Action>, path: Option<&Path>, old: &Item, new: &Item, nested: bool) {
+    // Sort the keys so the generated expression is deterministic
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for key in keys {
+        let path = child(path, key);
+        match (old.get(key), new.get(key)) {
+            (Some(old), Some(new)) => diff_value(actions, path, old, new, nested),
+            (None, Some(new)) => actions.push(Action::Set(path, new.clone())),
+            (Some(_), None) => actions.push(Action::Remove(path)),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+fn diff_value(
+    actions: &mut Vec<Action>,
+    path: Path,
+    old: &AttributeValue,
+    new: &AttributeValue,
+    nested: bool,
+) {
+    if values_equal(old, new) {
+        return;
+    }
+
+    if nested {
+        if let (Some(old_m), Some(new_m)) = (&old.m, &new.m) {
+            let mut children = Vec::new();
+            diff_item(&mut children, Some(&path), old_m, new_m, true);
+            // Only go deep when it's no more work than rewriting the whole map
+            if children.len() <= new_m.len() {
+                actions.extend(children);
+                return;
+            }
+        } else if let (Some(old_l), Some(new_l)) = (&old.l, &new.l) {
+            if let Some(children) = diff_list(&path, old_l, new_l) {
+                if children.len() <= new_l.len() {
+                    actions.extend(children);
+                    return;
+                }
+            }
+        }
+    }
+
+    if path.is_top_level() {
+        if let Some(action) = diff_set(&path, old, new) {
+            actions.push(action);
+            return;
+        }
+    }
+
+    actions.push(Action::Set(path, new.clone()));
+}
+
+/// Diff two lists element-by-element, or `None` if that would require overlapping paths.
+fn diff_list(path: &Path, old: &[AttributeValue], new: &[AttributeValue]) -> Option<Vec<Action>> {
+    let mut children = Vec::new();
+    let common = old.len().min(new.len());
+    for idx in 0..common {
+        diff_value(
+            &mut children,
+            path.clone().index(idx),
+            &old[idx],
+            &new[idx],
+            true,
+        );
+    }
+
+    if new.len() > old.len() {
+        // `list_append` acts on the list as a whole, so it can't be combined with changes to
+        // individual elements
+        if !children.is_empty() {
+            return None;
+        }
+        let tail = AttributeValue {
+            l: Some(new[common..].to_vec()),
+            ..AttributeValue::default()
+        };
+        children.push(Action::Append(path.clone(), tail));
+    } else {
+        for idx in common..old.len() {
+            children.push(Action::Remove(path.clone().index(idx)));
+        }
+    }
+
+    Some(children)
+}
+
+/// `ADD` or `DELETE` the members that changed between two sets of the same type.
+///
+/// DynamoDB doesn't allow both on the same attribute in one expression, so if members were both
+/// added and removed, `None` is returned and the set is rewritten instead.
+fn diff_set(path: &Path, old: &AttributeValue, new: &AttributeValue) -> Option<Action> {
+    let (added, removed) = if let (Some(old), Some(new)) = (&old.ss, &new.ss) {
+        let (added, removed) = set_difference(old, new, |s| s.clone());
+        (
+            AttributeValue {
+                ss: Some(added),
+                ..AttributeValue::default()
+            },
+            AttributeValue {
+                ss: Some(removed),
+                ..AttributeValue::default()
+            },
+        )
+    } else if let (Some(old), Some(new)) = (&old.ns, &new.ns) {
+        let (added, removed) =
+            set_difference(old, new, |n| Number::parse(n).map_err(|_| n.clone()));
+        (
+            AttributeValue {
+                ns: Some(added),
+                ..AttributeValue::default()
+            },
+            AttributeValue {
+                ns: Some(removed),
+                ..AttributeValue::default()
+            },
+        )
+    } else if let (Some(old), Some(new)) = (&old.bs, &new.bs) {
+        let (added, removed) = set_difference(old, new, |b| b.clone());
+        (
+            AttributeValue {
+                bs: Some(added),
+                ..AttributeValue::default()
+            },
+            AttributeValue {
+                bs: Some(removed),
+                ..AttributeValue::default()
+            },
+        )
+    } else {
+        return None;
+    };
+
+    match (set_len(&added), set_len(&removed)) {
+        (0, 0) => None,
+        (_, 0) => Some(Action::Add(path.clone(), added)),
+        (0, _) => Some(Action::Delete(path.clone(), removed)),
+        (_, _) => None,
+    }
+}
+
+fn set_difference<T, K, F>(old: &[T], new: &[T], key: F) -> (Vec<T>, Vec<T>)
+where
+    T: Clone,
+    K: Eq + std::hash::Hash,
+    F: Fn(&T) -> K,
+{
+    let old_keys: std::collections::HashSet<K> = old.iter().map(&key).collect();
+    let new_keys: std::collections::HashSet<K> = new.iter().map(&key).collect();
+    let added = new
+        .iter()
+        .filter(|v| !old_keys.contains(&key(v)))
+        .cloned()
+        .collect();
+    let removed = old
+        .iter()
+        .filter(|v| !new_keys.contains(&key(v)))
+        .cloned()
+        .collect();
+    (added, removed)
+}
+
+fn set_len(set: &AttributeValue) -> usize {
+    set.ss
+        .as_ref()
+        .map(Vec::len)
+        .or_else(|| set.ns.as_ref().map(Vec::len))
+        .or_else(|| set.bs.as_ref().map(Vec::len))
+        .unwrap_or(0)
+}
+
+fn render(actions: &[Action]) -> Expression {
+    let mut placeholders = Placeholders::new("u");
+    let mut set = Vec::new();
+    let mut remove = Vec::new();
+    let mut add = Vec::new();
+    let mut delete = Vec::new();
+
+    for action in actions {
+        match action {
+            Action::Set(path, value) => {
+                let path = placeholders.path(path);
+                let value = placeholders.value(value.clone());
+                set.push(format!("{} = {}", path, value));
+            }
+            Action::Append(path, value) => {
+                let path = placeholders.path(path);
+                let value = placeholders.value(value.clone());
+                set.push(format!("{0} = list_append({0}, {1})", path, value));
+            }
+            Action::Remove(path) => remove.push(placeholders.path(path)),
+            Action::Add(path, value) => {
+                let path = placeholders.path(path);
+                let value = placeholders.value(value.clone());
+                add.push(format!("{} {}", path, value));
+            }
+            Action::Delete(path, value) => {
+                let path = placeholders.path(path);
+                let value = placeholders.value(value.clone());
+                delete.push(format!("{} {}", path, value));
+            }
+        }
+    }
+
+    let mut clauses = Vec::new();
+    for (keyword, parts) in [
+        ("SET", set),
+        ("REMOVE", remove),
+        ("ADD", add),
+        ("DELETE", delete),
+    ]
+    .iter()
+    {
+        if !parts.is_empty() {
+            clauses.push(format!("{} {}", keyword, parts.join(", ")));
+        }
+    }
+
+    placeholders.finish(clauses.join(" "))
+}
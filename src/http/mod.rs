@@ -0,0 +1,337 @@
+//! DynamoDB's HTTP request and response bodies, without an SDK.
+//!
+//! DynamoDB's API is JSON over HTTP: every request is a `POST` whose `X-Amz-Target` header names
+//! the operation and whose body is a JSON object in [DynamoDB JSON]. This module builds those
+//! bodies from Rust types, and decodes response bodies back into them, leaving signing and
+//! sending the request to whatever HTTP client is at hand.
+//!
+//! Requests are rusoto's input structs, which are plain data, so every field DynamoDB accepts
+//! can be set. The functions here fill in the parts that come from Rust types.
+//!
+//! ```
+//! use serde_dynamo::http::{self, GetItemResponse, Operation};
+//! # use serde_derive::{Deserialize, Serialize};
+//! #
+//! # #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! # struct User { id: String, name: String }
+//! #
+//! # #[derive(Serialize)]
+//! # struct UserKey<'a> { id: &'a str }
+//! #
+//! # fn test() -> Result<(), Box<dyn std::error::Error>> {
+//!
+//! let input = http::get_item("users", UserKey { id: "fSsgVtal8TpP" })?;
+//! let target = input.target();
+//! let body = http::encode(&input)?;
+//! assert_eq!(target, "DynamoDB_20120810.GetItem");
+//! assert_eq!(body, br#"{"Key":{"id":{"S":"fSsgVtal8TpP"}},"TableName":"users"}"#);
+//!
+//! // Sign, send, and read the response, then
+//! # let response = br#"{"Item":{"id":{"S":"fSsgVtal8TpP"},"name":{"S":"Arthur Dent"}}}"#;
+//! let response = GetItemResponse::<User>::from_body(response)?;
+//! assert_eq!(response.item.unwrap().name, "Arthur Dent");
+//! # Ok(())
+//! # }
+//! # test().unwrap();
+//! ```
+//!
+//! [DynamoDB JSON]: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Programming.LowLevelAPI.html
+
+use crate::error::ErrorImpl;
+use crate::expression::Expression;
+use crate::{from_item, Item, Result};
+use rusoto_dynamodb::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::{self, Display};
+
+#[cfg(test)]
+mod tests;
+
+/// The `Content-Type` of DynamoDB requests and responses.
+pub const CONTENT_TYPE: &str = "application/x-amz-json-1.0";
+
+/// The prefix of the `X-Amz-Target` header, which is followed by the operation's name.
+pub const TARGET_PREFIX: &str = "DynamoDB_20120810.";
+
+/// A request body for one of DynamoDB's operations.
+pub trait Operation: Serialize {
+    /// The operation's name, like `GetItem`
+    const NAME: &'static str;
+
+    /// The value of the request's `X-Amz-Target` header, like `DynamoDB_20120810.GetItem`.
+    fn target(&self) -> String {
+        format!("{}{}", TARGET_PREFIX, Self::NAME)
+    }
+}
+
+macro_rules! operations {
+    ($($input:ident => $name:literal,)*) => {
+        $(
+            impl Operation for $input {
+                const NAME: &'static str = $name;
+            }
+        )*
+    };
+}
+
+operations! {
+    GetItemInput => "GetItem",
+    PutItemInput => "PutItem",
+    DeleteItemInput => "DeleteItem",
+    UpdateItemInput => "UpdateItem",
+    QueryInput => "Query",
+    ScanInput => "Scan",
+    BatchGetItemInput => "BatchGetItem",
+    BatchWriteItemInput => "BatchWriteItem",
+    TransactGetItemsInput => "TransactGetItems",
+    TransactWriteItemsInput => "TransactWriteItems",
+}
+
+/// Encode a request as the JSON body DynamoDB expects.
+pub fn encode<O: Operation>(input: &O) -> Result<Vec<u8>> {
+    serde_json::to_vec(input)
+        .map_err(|err| ErrorImpl::Message(format!("Failed to encode request: {}", err)).into())
+}
+
+/// A GetItem request for the item with the given key.
+pub fn get_item<K: Serialize>(table_name: impl Into<String>, key: K) -> Result<GetItemInput> {
+    Ok(GetItemInput {
+        table_name: table_name.into(),
+        key: crate::to_item(key)?,
+        ..GetItemInput::default()
+    })
+}
+
+/// A PutItem request storing `item`.
+pub fn put_item<T: Serialize>(table_name: impl Into<String>, item: T) -> Result<PutItemInput> {
+    Ok(PutItemInput {
+        table_name: table_name.into(),
+        item: crate::to_item(item)?,
+        ..PutItemInput::default()
+    })
+}
+
+/// A DeleteItem request for the item with the given key.
+pub fn delete_item<K: Serialize>(table_name: impl Into<String>, key: K) -> Result<DeleteItemInput> {
+    Ok(DeleteItemInput {
+        table_name: table_name.into(),
+        key: crate::to_item(key)?,
+        ..DeleteItemInput::default()
+    })
+}
+
+/// An UpdateItem request applying `update` to the item with the given key.
+///
+/// The update usually comes from [`expression::diff`](crate::expression::diff).
+pub fn update_item<K: Serialize>(
+    table_name: impl Into<String>,
+    key: K,
+    update: Expression,
+) -> Result<UpdateItemInput> {
+    Ok(UpdateItemInput {
+        table_name: table_name.into(),
+        key: crate::to_item(key)?,
+        expression_attribute_names: update.expression_attribute_names(),
+        expression_attribute_values: update.expression_attribute_values(),
+        update_expression: Some(update.expression),
+        ..UpdateItemInput::default()
+    })
+}
+
+/// A Query request for the items matching `key_condition`.
+///
+/// The condition usually comes from a [`KeyCondition`](crate::expression::KeyCondition).
+pub fn query(table_name: impl Into<String>, key_condition: Expression) -> QueryInput {
+    QueryInput {
+        table_name: table_name.into(),
+        expression_attribute_names: key_condition.expression_attribute_names(),
+        expression_attribute_values: key_condition.expression_attribute_values(),
+        key_condition_expression: Some(key_condition.expression),
+        ..QueryInput::default()
+    }
+}
+
+fn decode<O: DeserializeOwned>(body: &[u8]) -> Result<O> {
+    serde_json::from_slice(body)
+        .map_err(|err| ErrorImpl::Message(format!("Failed to decode response: {}", err)).into())
+}
+
+fn items<T: DeserializeOwned>(items: Option<Vec<Item>>) -> Result<Vec<T>> {
+    items
+        .unwrap_or_default()
+        .into_iter()
+        .map(from_item)
+        .collect()
+}
+
+/// The response to a GetItem request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetItemResponse<T> {
+    /// The item, or `None` if there is no item with the key
+    pub item: Option<T>,
+    /// The capacity the request used, if it asked for it
+    pub consumed_capacity: Option<ConsumedCapacity>,
+}
+
+impl<T: DeserializeOwned> GetItemResponse<T> {
+    /// Decode the body of a successful GetItem response.
+    pub fn from_body(body: &[u8]) -> Result<Self> {
+        let output: GetItemOutput = decode(body)?;
+        Ok(GetItemResponse {
+            item: output.item.map(from_item).transpose()?,
+            consumed_capacity: output.consumed_capacity,
+        })
+    }
+}
+
+/// The response to a PutItem, UpdateItem, or DeleteItem request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteResponse<T> {
+    /// The attributes the request's `ReturnValues` asked for, if any
+    pub attributes: Option<T>,
+    /// The capacity the request used, if it asked for it
+    pub consumed_capacity: Option<ConsumedCapacity>,
+}
+
+impl<T: DeserializeOwned> WriteResponse<T> {
+    /// Decode the body of a successful PutItem, UpdateItem, or DeleteItem response.
+    pub fn from_body(body: &[u8]) -> Result<Self> {
+        // The three responses have the same fields
+        let output: UpdateItemOutput = decode(body)?;
+        Ok(WriteResponse {
+            attributes: output.attributes.map(from_item).transpose()?,
+            consumed_capacity: output.consumed_capacity,
+        })
+    }
+}
+
+/// The response to a Query or Scan request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResponse<T> {
+    /// The items of this page
+    pub items: Vec<T>,
+    /// The number of items in this page
+    pub count: i64,
+    /// The number of items read before the filter expression was applied
+    pub scanned_count: i64,
+    /// Where the next page starts, to be passed as `ExclusiveStartKey`, or `None` on the last page
+    pub last_evaluated_key: Option<Item>,
+    /// The capacity the request used, if it asked for it
+    pub consumed_capacity: Option<ConsumedCapacity>,
+}
+
+impl<T: DeserializeOwned> QueryResponse<T> {
+    /// Decode the body of a successful Query or Scan response.
+    pub fn from_body(body: &[u8]) -> Result<Self> {
+        // Query and Scan responses have the same fields
+        let output: QueryOutput = decode(body)?;
+        Ok(QueryResponse {
+            items: items(output.items)?,
+            count: output.count.unwrap_or_default(),
+            scanned_count: output.scanned_count.unwrap_or_default(),
+            last_evaluated_key: output.last_evaluated_key,
+            consumed_capacity: output.consumed_capacity,
+        })
+    }
+}
+
+/// An error response from DynamoDB.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceError {
+    /// The full error type, like `com.amazonaws.dynamodb.v20120810#ConditionalCheckFailedException`
+    pub error_type: String,
+    /// The error message
+    pub message: String,
+    /// Why each item of a transaction failed, for `TransactionCanceledException`s
+    pub cancellation_reasons: Vec<CancellationReason>,
+}
+
+impl ServiceError {
+    /// Decode the body of an error response.
+    pub fn from_body(body: &[u8]) -> Result<Self> {
+        let mut body: serde_json::Map<String, serde_json::Value> = decode(body)?;
+        let mut take = |key: &str| match body.remove(key) {
+            Some(serde_json::Value::String(s)) => Some(s),
+            _ => None,
+        };
+        let error_type = take("__type");
+        // Most errors use `message`, but some use `Message`
+        let message = take("message").or_else(|| take("Message"));
+
+        let error_type = match error_type {
+            Some(error_type) => error_type,
+            None => {
+                return Err(ErrorImpl::Message(String::from(
+                    "Failed to decode response: not an error response",
+                ))
+                .into())
+            }
+        };
+        let cancellation_reasons = match body.remove("CancellationReasons") {
+            Some(serde_json::Value::Array(reasons)) => reasons
+                .into_iter()
+                .map(CancellationReason::from_json)
+                .collect::<Result<_>>()?,
+            _ => Vec::new(),
+        };
+        Ok(ServiceError {
+            error_type,
+            message: message.unwrap_or_default(),
+            cancellation_reasons,
+        })
+    }
+
+    /// The error's name without its namespace, like `ConditionalCheckFailedException`.
+    pub fn name(&self) -> &str {
+        match self.error_type.rfind('#') {
+            Some(hash) => &self.error_type[hash + 1..],
+            None => &self.error_type,
+        }
+    }
+}
+
+/// Why one item of a canceled transaction failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CancellationReason {
+    /// The reason, like `ConditionalCheckFailed`, or `None` if this item didn't fail
+    pub code: String,
+    /// A description of the failure
+    pub message: Option<String>,
+    /// The item, if the request asked for it with `ReturnValuesOnConditionCheckFailure`
+    pub item: Option<Item>,
+}
+
+impl CancellationReason {
+    fn from_json(value: serde_json::Value) -> Result<Self> {
+        let mut value = match value {
+            serde_json::Value::Object(value) => value,
+            _ => return Err(ErrorImpl::ExpectedMap.into()),
+        };
+        let mut take = |key: &str| match value.remove(key) {
+            Some(serde_json::Value::String(s)) => Some(s),
+            _ => None,
+        };
+        let code = take("Code").unwrap_or_default();
+        let message = take("Message");
+        let item = match value.remove("Item") {
+            Some(item) => Some(serde_json::from_value(item).map_err(|err| {
+                ErrorImpl::Message(format!("Failed to decode response: {}", err)).into()
+            })?),
+            None => None,
+        };
+        Ok(CancellationReason {
+            code,
+            message,
+            item,
+        })
+    }
+}
+
+impl Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name(), self.message)
+    }
+}
+
+impl std::error::Error for ServiceError {}
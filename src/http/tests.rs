@@ -0,0 +1,215 @@
+use super::*;
+use crate::expression::{KeyCondition, SortKeyCondition};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Post {
+    author: String,
+    at: u32,
+    #[serde(with = "serde_bytes")]
+    body: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct PostKey<'a> {
+    author: &'a str,
+    at: u32,
+}
+
+fn body<O: Operation>(input: &O) -> serde_json::Value {
+    serde_json::from_slice(&encode(input).unwrap()).unwrap()
+}
+
+#[test]
+fn encode_requests() {
+    let post = Post {
+        author: "arthur".to_string(),
+        at: 1,
+        body: b"\x00\xff".to_vec(),
+    };
+    let input = put_item("posts", &post).unwrap();
+    assert_eq!(input.target(), "DynamoDB_20120810.PutItem");
+    assert_eq!(
+        body(&input),
+        json!({
+            "TableName": "posts",
+            "Item": {
+                "author": { "S": "arthur" },
+                "at": { "N": "1" },
+                "body": { "B": "AP8=" },
+            },
+        })
+    );
+
+    let input = DeleteItemInput {
+        return_values: Some(String::from("ALL_OLD")),
+        ..delete_item(
+            "posts",
+            PostKey {
+                author: "arthur",
+                at: 1,
+            },
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        body(&input),
+        json!({
+            "TableName": "posts",
+            "Key": { "author": { "S": "arthur" }, "at": { "N": "1" } },
+            "ReturnValues": "ALL_OLD",
+        })
+    );
+
+    let update = crate::expression::diff(
+        &crate::to_item(json!({ "likes": 1 })).unwrap(),
+        &crate::to_item(json!({ "likes": 2 })).unwrap(),
+    )
+    .unwrap()
+    .unwrap();
+    let input = update_item(
+        "posts",
+        PostKey {
+            author: "arthur",
+            at: 1,
+        },
+        update,
+    )
+    .unwrap();
+    assert_eq!(
+        body(&input),
+        json!({
+            "TableName": "posts",
+            "Key": { "author": { "S": "arthur" }, "at": { "N": "1" } },
+            "UpdateExpression": "SET #u0 = :u0",
+            "ExpressionAttributeNames": { "#u0": "likes" },
+            "ExpressionAttributeValues": { ":u0": { "N": "2" } },
+        })
+    );
+
+    let key_condition = KeyCondition::partition_key("author", "arthur")
+        .sort_key("at", SortKeyCondition::gt(1))
+        .build()
+        .unwrap();
+    let input = QueryInput {
+        limit: Some(10),
+        ..query("posts", key_condition)
+    };
+    assert_eq!(input.target(), "DynamoDB_20120810.Query");
+    let body = body(&input);
+    assert_eq!(body["Limit"], 10);
+    assert_eq!(body["TableName"], "posts");
+    assert!(body["KeyConditionExpression"].is_string());
+    assert_eq!(
+        body["ExpressionAttributeValues"].as_object().unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn decode_responses() {
+    let response = GetItemResponse::<Post>::from_body(
+        br#"{
+            "Item": { "author": { "S": "arthur" }, "at": { "N": "1" }, "body": { "B": "AP8=" } },
+            "ConsumedCapacity": { "TableName": "posts", "CapacityUnits": 0.5 }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        response.item,
+        Some(Post {
+            author: "arthur".to_string(),
+            at: 1,
+            body: b"\x00\xff".to_vec(),
+        })
+    );
+    assert_eq!(
+        response.consumed_capacity.unwrap().capacity_units,
+        Some(0.5)
+    );
+
+    let response = GetItemResponse::<Post>::from_body(b"{}").unwrap();
+    assert_eq!(response.item, None);
+
+    let response = WriteResponse::<serde_json::Value>::from_body(
+        br#"{ "Attributes": { "likes": { "N": "2" } } }"#,
+    )
+    .unwrap();
+    assert_eq!(response.attributes, Some(json!({ "likes": 2 })));
+
+    let response = QueryResponse::<serde_json::Value>::from_body(
+        br#"{
+            "Items": [{ "at": { "N": "2" } }, { "at": { "N": "3" } }],
+            "Count": 2,
+            "ScannedCount": 5,
+            "LastEvaluatedKey": { "author": { "S": "arthur" }, "at": { "N": "3" } }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(response.items, vec![json!({ "at": 2 }), json!({ "at": 3 })]);
+    assert_eq!((response.count, response.scanned_count), (2, 5));
+    assert_eq!(
+        response.last_evaluated_key,
+        Some(crate::to_item(json!({ "author": "arthur", "at": 3 })).unwrap())
+    );
+
+    let response =
+        QueryResponse::<Post>::from_body(br#"{ "Count": 0, "ScannedCount": 0 }"#).unwrap();
+    assert!(response.items.is_empty());
+    assert_eq!(response.last_evaluated_key, None);
+
+    // Items that don't fit the type are errors
+    assert!(QueryResponse::<Post>::from_body(br#"{ "Items": [{}] }"#).is_err());
+    assert!(GetItemResponse::<Post>::from_body(b"<html>").is_err());
+}
+
+#[test]
+fn decode_errors() {
+    let error = ServiceError::from_body(
+        br#"{
+            "__type": "com.amazonaws.dynamodb.v20120810#ConditionalCheckFailedException",
+            "message": "The conditional request failed"
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(error.name(), "ConditionalCheckFailedException");
+    assert_eq!(
+        error.to_string(),
+        "ConditionalCheckFailedException: The conditional request failed"
+    );
+
+    let error = ServiceError::from_body(
+        br#"{
+            "__type": "com.amazonaws.dynamodb.v20120810#TransactionCanceledException",
+            "Message": "Transaction cancelled",
+            "CancellationReasons": [
+                { "Code": "None" },
+                {
+                    "Code": "ConditionalCheckFailed",
+                    "Message": "The conditional request failed",
+                    "Item": { "likes": { "N": "2" } }
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(error.message, "Transaction cancelled");
+    assert_eq!(
+        error.cancellation_reasons,
+        vec![
+            CancellationReason {
+                code: "None".to_string(),
+                message: None,
+                item: None,
+            },
+            CancellationReason {
+                code: "ConditionalCheckFailed".to_string(),
+                message: Some("The conditional request failed".to_string()),
+                item: Some(crate::to_item(json!({ "likes": 2 })).unwrap()),
+            },
+        ]
+    );
+
+    assert!(ServiceError::from_body(b"{}").is_err());
+}
@@ -0,0 +1,225 @@
+//! Lossless conversions between [`Item`]s and JSON.
+//!
+//! Deserializing an item as a `serde_json::Value` with [`from_item`](crate::from_item) loses
+//! what JSON has no type for: sets become arrays, binary becomes arrays of numbers, and numbers
+//! are rounded to what an `f64` can hold. [`item_to_json`] and [`json_to_item`] instead follow a
+//! convention that keeps every attribute value as it is, so an item can be stored in or sent
+//! through a JSON-only system and restored exactly.
+//!
+//! | DynamoDB | JSON |
+//! |----------|------|
+//! | `S` | a string |
+//! | `N` | a number, if a double holds it exactly as written, or else `{"$N": "0.10"}` |
+//! | `BOOL` | `true` or `false` |
+//! | `NULL` | `null` |
+//! | `L` | an array |
+//! | `M` | an object, or `{"$M": {...}}` if it has one key, starting with `$` |
+//! | `B` | `{"$B": "AAH/"}`, in standard base64 |
+//! | `SS` | `{"$SS": ["a", "b"]}` |
+//! | `NS` | `{"$NS": ["1", "2.5"]}`, with the numbers as written |
+//! | `BS` | `{"$BS": ["AAH/"]}`, in standard base64 |
+//!
+//! So common data stays plain JSON, and only values JSON can't represent are tagged, as objects
+//! with a single `$` key. Maps that would look like a tag are wrapped in `$M`, so they can't be
+//! mistaken for one. Objects with a single `$` key that isn't one of these tags are maps.
+//!
+//! Integers beyond ±(2^53 - 1) are tagged even though JSON can write them, because systems that
+//! read JSON numbers as doubles, like JavaScript, would round them.
+//!
+//! ```
+//! use serde_dynamo::json::{item_to_json, json_to_item};
+//! # use rusoto_dynamodb::AttributeValue;
+//! # use serde_json::json;
+//! # use std::collections::HashMap;
+//!
+//! # fn test() -> serde_dynamo::Result<()> {
+//! let mut item = HashMap::new();
+//! item.insert(
+//!     String::from("tags"),
+//!     AttributeValue { ss: Some(vec![String::from("human")]), ..AttributeValue::default() },
+//! );
+//! item.insert(
+//!     String::from("price"),
+//!     AttributeValue { n: Some(String::from("9.990")), ..AttributeValue::default() },
+//! );
+//!
+//! let json = item_to_json(&item);
+//! assert_eq!(json, json!({ "tags": { "$SS": ["human"] }, "price": { "$N": "9.990" } }));
+//! assert_eq!(json_to_item(&json)?, item);
+//! # Ok(())
+//! # }
+//! # test().unwrap()
+//! ```
+
+use crate::error::ErrorImpl;
+use crate::{AttributeValue, Item, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{Map, Number, Value};
+
+#[cfg(test)]
+mod tests;
+
+/// Convert an item to JSON, tagging what JSON has no type for.
+pub fn item_to_json(item: &Item) -> Value {
+    Value::Object(
+        item.iter()
+            .map(|(name, value)| (name.clone(), attribute_value_to_json(value)))
+            .collect(),
+    )
+}
+
+/// Convert JSON made by [`item_to_json`] back to the item.
+///
+/// Plain JSON objects convert too, as if every value were untagged. Fails if `value` isn't an
+/// object, or if a tag's contents are invalid.
+pub fn json_to_item(value: &Value) -> Result<Item> {
+    match value {
+        Value::Object(object) => map(object),
+        _ => Err(invalid("an item has to be an object")),
+    }
+}
+
+/// Convert an attribute value to JSON, tagging it if JSON has no type for it.
+///
+/// A value with none of its fields set becomes `null`.
+pub fn attribute_value_to_json(value: &AttributeValue) -> Value {
+    if let Some(s) = &value.s {
+        Value::String(s.clone())
+    } else if let Some(n) = &value.n {
+        match n.parse::<Number>() {
+            Ok(number) if number.to_string() == *n && fits_double(&number) => Value::Number(number),
+            _ => tagged("$N", Value::String(n.clone())),
+        }
+    } else if let Some(b) = &value.b {
+        tagged("$B", Value::String(STANDARD.encode(b)))
+    } else if let Some(bool) = value.bool {
+        Value::Bool(bool)
+    } else if let Some(ss) = &value.ss {
+        tagged("$SS", ss.iter().cloned().map(Value::String).collect())
+    } else if let Some(ns) = &value.ns {
+        tagged("$NS", ns.iter().cloned().map(Value::String).collect())
+    } else if let Some(bs) = &value.bs {
+        let bs = bs.iter().map(|b| Value::String(STANDARD.encode(b)));
+        tagged("$BS", bs.collect())
+    } else if let Some(l) = &value.l {
+        Value::Array(l.iter().map(attribute_value_to_json).collect())
+    } else if let Some(m) = &value.m {
+        let json = item_to_json(m);
+        if looks_tagged(&json) {
+            tagged("$M", json)
+        } else {
+            json
+        }
+    } else {
+        Value::Null
+    }
+}
+
+/// The largest integer a double holds exactly, along with every integer below it: 2^53 - 1.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Whether a JSON system that reads numbers as doubles, like JavaScript, keeps `number` as it is.
+fn fits_double(number: &Number) -> bool {
+    if let Some(u) = number.as_u64() {
+        u <= MAX_SAFE_INTEGER
+    } else if let Some(i) = number.as_i64() {
+        i.unsigned_abs() <= MAX_SAFE_INTEGER
+    } else {
+        true
+    }
+}
+
+/// Convert JSON made by [`attribute_value_to_json`] back to the attribute value.
+pub fn json_to_attribute_value(value: &Value) -> Result<AttributeValue> {
+    let mut converted = AttributeValue::default();
+    match value {
+        Value::Null => converted.null = Some(true),
+        Value::Bool(bool) => converted.bool = Some(*bool),
+        Value::Number(number) => converted.n = Some(number.to_string()),
+        Value::String(s) => converted.s = Some(s.clone()),
+        Value::Array(l) => {
+            converted.l = Some(
+                l.iter()
+                    .map(json_to_attribute_value)
+                    .collect::<Result<_>>()?,
+            )
+        }
+        Value::Object(object) => match tag(object) {
+            Some(("$N", Value::String(n))) => converted.n = Some(n.clone()),
+            Some(("$B", b)) => converted.b = Some(binary(b)?.into()),
+            Some(("$SS", ss)) => converted.ss = Some(strings(ss)?),
+            Some(("$NS", ns)) => converted.ns = Some(strings(ns)?),
+            Some(("$BS", Value::Array(bs))) => {
+                converted.bs = Some(
+                    bs.iter()
+                        .map(|b| Ok(binary(b)?.into()))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            Some(("$M", Value::Object(m))) => converted.m = Some(map(m)?),
+            Some((tag @ "$N", _)) | Some((tag @ "$BS", _)) | Some((tag @ "$M", _)) => {
+                return Err(invalid(format!("invalid contents of {}", tag)))
+            }
+            _ => converted.m = Some(map(object)?),
+        },
+    }
+    Ok(converted)
+}
+
+fn map(object: &Map<String, Value>) -> Result<Item> {
+    object
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), json_to_attribute_value(value)?)))
+        .collect()
+}
+
+fn tagged(tag: &str, value: Value) -> Value {
+    let mut object = Map::new();
+    object.insert(tag.to_string(), value);
+    Value::Object(object)
+}
+
+/// Whether JSON is an object with a single key starting with `$`, the shape of a tag.
+fn looks_tagged(value: &Value) -> bool {
+    match value {
+        Value::Object(object) => object.len() == 1 && object.keys().all(|key| key.starts_with('$')),
+        _ => false,
+    }
+}
+
+/// The tag of an object, and what it tags, if the object is a known tag.
+fn tag(object: &Map<String, Value>) -> Option<(&str, &Value)> {
+    if object.len() != 1 {
+        return None;
+    }
+    let (key, value) = object.iter().next()?;
+    match key.as_str() {
+        "$N" | "$B" | "$SS" | "$NS" | "$BS" | "$M" => Some((key.as_str(), value)),
+        _ => None,
+    }
+}
+
+fn binary(value: &Value) -> Result<Vec<u8>> {
+    match value {
+        Value::String(b) => STANDARD
+            .decode(b)
+            .map_err(|err| invalid(format!("invalid base64: {}", err))),
+        _ => Err(invalid("binary has to be a base64 string")),
+    }
+}
+
+fn strings(value: &Value) -> Result<Vec<String>> {
+    let strings = match value {
+        Value::Array(values) => values
+            .iter()
+            .map(|value| value.as_str().map(String::from))
+            .collect::<Option<_>>(),
+        _ => None,
+    };
+    strings.ok_or_else(|| invalid("a set has to be an array of strings"))
+}
+
+fn invalid(message: impl Into<String>) -> crate::Error {
+    ErrorImpl::Message(format!("Invalid JSON item: {}", message.into())).into()
+}
@@ -0,0 +1,212 @@
+use super::*;
+use maplit::hashmap;
+use serde_json::json;
+
+fn every_type() -> Item {
+    hashmap! {
+        String::from("s") => AttributeValue {
+            s: Some(String::from("Arthur Dent")),
+            ..AttributeValue::default()
+        },
+        String::from("n") => AttributeValue {
+            n: Some(String::from("42")),
+            ..AttributeValue::default()
+        },
+        String::from("float") => AttributeValue {
+            n: Some(String::from("4.5")),
+            ..AttributeValue::default()
+        },
+        String::from("exact") => AttributeValue {
+            n: Some(String::from("0.10")),
+            ..AttributeValue::default()
+        },
+        String::from("huge") => AttributeValue {
+            n: Some(String::from("123456789012345678901234567890")),
+            ..AttributeValue::default()
+        },
+        String::from("bool") => AttributeValue {
+            bool: Some(true),
+            ..AttributeValue::default()
+        },
+        String::from("null") => AttributeValue {
+            null: Some(true),
+            ..AttributeValue::default()
+        },
+        String::from("b") => AttributeValue {
+            b: Some(vec![0, 1, 255].into()),
+            ..AttributeValue::default()
+        },
+        String::from("ss") => AttributeValue {
+            ss: Some(vec![String::from("b"), String::from("a")]),
+            ..AttributeValue::default()
+        },
+        String::from("ns") => AttributeValue {
+            ns: Some(vec![String::from("1.50"), String::from("2")]),
+            ..AttributeValue::default()
+        },
+        String::from("bs") => AttributeValue {
+            bs: Some(vec![vec![1].into(), vec![2, 3].into()]),
+            ..AttributeValue::default()
+        },
+        String::from("l") => AttributeValue {
+            l: Some(vec![
+                AttributeValue {
+                    s: Some(String::from("a")),
+                    ..AttributeValue::default()
+                },
+                AttributeValue {
+                    n: Some(String::from("1e3")),
+                    ..AttributeValue::default()
+                },
+            ]),
+            ..AttributeValue::default()
+        },
+        String::from("m") => AttributeValue {
+            m: Some(hashmap! {
+                String::from("city") => AttributeValue {
+                    s: Some(String::from("Cottington")),
+                    ..AttributeValue::default()
+                },
+            }),
+            ..AttributeValue::default()
+        },
+        String::from("tag_like") => AttributeValue {
+            m: Some(hashmap! {
+                String::from("$SS") => AttributeValue {
+                    s: Some(String::from("not a set")),
+                    ..AttributeValue::default()
+                },
+            }),
+            ..AttributeValue::default()
+        },
+        String::from("empty") => AttributeValue {
+            m: Some(Item::new()),
+            ..AttributeValue::default()
+        },
+    }
+}
+
+#[test]
+fn item_to_json_tags_what_json_lacks() {
+    assert_eq!(
+        item_to_json(&every_type()),
+        json!({
+            "s": "Arthur Dent",
+            "n": 42,
+            "float": 4.5,
+            "exact": { "$N": "0.10" },
+            "huge": { "$N": "123456789012345678901234567890" },
+            "bool": true,
+            "null": null,
+            "b": { "$B": "AAH/" },
+            "ss": { "$SS": ["b", "a"] },
+            "ns": { "$NS": ["1.50", "2"] },
+            "bs": { "$BS": ["AQ==", "AgM="] },
+            "l": ["a", { "$N": "1e3" }],
+            "m": { "city": "Cottington" },
+            "tag_like": { "$M": { "$SS": "not a set" } },
+            "empty": {}
+        })
+    );
+}
+
+#[test]
+fn round_trip() {
+    let item = every_type();
+    let json = item_to_json(&item);
+    assert_eq!(json_to_item(&json).unwrap(), item);
+
+    // Through a string, as a JSON-only system would store it
+    let json: Value = serde_json::from_str(&json.to_string()).unwrap();
+    assert_eq!(json_to_item(&json).unwrap(), item);
+}
+
+#[test]
+fn integers_beyond_doubles_are_tagged() {
+    let integers = [
+        ("9007199254740991", json!(9007199254740991_u64)),
+        ("-9007199254740991", json!(-9007199254740991_i64)),
+        ("9007199254740992", json!({ "$N": "9007199254740992" })),
+        ("-9007199254740992", json!({ "$N": "-9007199254740992" })),
+        ("9007199254740993", json!({ "$N": "9007199254740993" })),
+        (
+            "18446744073709551615",
+            json!({ "$N": "18446744073709551615" }),
+        ),
+        (
+            "-9223372036854775808",
+            json!({ "$N": "-9223372036854775808" }),
+        ),
+    ];
+    for (integer, json) in integers.iter() {
+        let number = AttributeValue {
+            n: Some(integer.to_string()),
+            ..AttributeValue::default()
+        };
+        assert_eq!(attribute_value_to_json(&number), *json, "{}", integer);
+        assert_eq!(json_to_attribute_value(json).unwrap(), number);
+    }
+}
+
+#[test]
+fn plain_json() {
+    assert_eq!(
+        json_to_item(&json!({ "name": "Arthur", "age": 42, "$other": 1 })).unwrap(),
+        hashmap! {
+            String::from("name") => AttributeValue {
+                s: Some(String::from("Arthur")),
+                ..AttributeValue::default()
+            },
+            String::from("age") => AttributeValue {
+                n: Some(String::from("42")),
+                ..AttributeValue::default()
+            },
+            String::from("$other") => AttributeValue {
+                n: Some(String::from("1")),
+                ..AttributeValue::default()
+            },
+        }
+    );
+    assert_eq!(
+        json_to_attribute_value(&json!({ "$unknown": 1 })).unwrap(),
+        AttributeValue {
+            m: Some(hashmap! {
+                String::from("$unknown") => AttributeValue {
+                    n: Some(String::from("1")),
+                    ..AttributeValue::default()
+                },
+            }),
+            ..AttributeValue::default()
+        }
+    );
+}
+
+#[test]
+fn invalid_json() {
+    let error = |json: Value| json_to_attribute_value(&json).unwrap_err().to_string();
+
+    assert_eq!(
+        json_to_item(&json!([1])).unwrap_err().to_string(),
+        "Invalid JSON item: an item has to be an object"
+    );
+    assert_eq!(
+        error(json!({ "$B": "not base64!" })),
+        "Invalid JSON item: invalid base64: Invalid symbol 32, offset 3."
+    );
+    assert_eq!(
+        error(json!({ "$B": 1 })),
+        "Invalid JSON item: binary has to be a base64 string"
+    );
+    assert_eq!(
+        error(json!({ "$SS": [1] })),
+        "Invalid JSON item: a set has to be an array of strings"
+    );
+    assert_eq!(
+        error(json!({ "$N": 1 })),
+        "Invalid JSON item: invalid contents of $N"
+    );
+    assert_eq!(
+        error(json!({ "$M": [] })),
+        "Invalid JSON item: invalid contents of $M"
+    );
+}
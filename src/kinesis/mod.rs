@@ -0,0 +1,152 @@
+//! Typed records of Kinesis Data Streams for DynamoDB.
+//!
+//! A table can stream its changes to a Kinesis data stream instead of to DynamoDB Streams. Each
+//! Kinesis record's data is then a JSON document describing one change, with the keys and images
+//! in DynamoDB JSON:
+//!
+//! ```json
+//! {
+//!     "awsRegion": "us-east-1",
+//!     "eventID": "4b25bd0d-f7ed-4a8b-a4a2-1fd4a4bc5f53",
+//!     "eventName": "INSERT",
+//!     "userIdentity": null,
+//!     "recordFormat": "application/json",
+//!     "tableName": "users",
+//!     "dynamodb": {
+//!         "ApproximateCreationDateTime": 1646776444906,
+//!         "Keys": { "id": { "S": "arthur" } },
+//!         "NewImage": { "id": { "S": "arthur" }, "name": { "S": "Arthur Dent" } },
+//!         "SizeBytes": 48
+//!     },
+//!     "eventSource": "aws:dynamodb"
+//! }
+//! ```
+//!
+//! [`from_slice`] parses that document into the same typed [`StreamRecord`] DynamoDB Streams'
+//! records convert into, so a consumer doesn't change with the transport:
+//!
+//! ```
+//! use serde_dynamo::kinesis;
+//! use serde_dynamo::stream::{Change, StreamRecord};
+//! # use serde_derive::Deserialize;
+//! #
+//! # #[derive(Deserialize)]
+//! # struct User { id: String, name: String }
+//! # #[derive(Deserialize)]
+//! # struct UserKey { id: String }
+//!
+//! fn process(data: &[u8]) -> serde_dynamo::Result<()> {
+//!     let record: StreamRecord<User, UserKey> = kinesis::from_slice(data)?;
+//!     if let Change::Insert(Some(user)) = record.change {
+//!         println!("Welcome, {}", user.name);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Kinesis clients and `aws_lambda_events` hand over the data already decoded from base64. For
+//! data still in base64, such as in the raw JSON of a Kinesis event, use [`from_base64`].
+//!
+//! Records from Kinesis carry no stream view type, so their images are whichever the record has.
+//! Nor do they carry a sequence number of their own: the Kinesis record's sequence number orders
+//! them instead.
+
+use crate::error::ErrorImpl;
+use crate::stream::{RawRecord, StreamRecord, UserIdentity};
+use crate::{Item, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[cfg(test)]
+mod tests;
+
+/// Parse the data of a Kinesis record into a typed stream record.
+///
+/// Fails if the data isn't a change record of a DynamoDB table, or if its keys or images can't
+/// be deserialized as `K` and `T`.
+pub fn from_slice<T, K>(data: &[u8]) -> Result<StreamRecord<T, K>>
+where
+    T: DeserializeOwned,
+    K: DeserializeOwned,
+{
+    let record: Record = serde_json::from_slice(data).map_err(|err| -> crate::Error {
+        ErrorImpl::InvalidStreamRecord(format!("failed to parse Kinesis data: {}", err)).into()
+    })?;
+    let change = record.dynamodb;
+    // Milliseconds, unless the table was set up to stream microseconds
+    let approximate_creation_time =
+        change.approximate_creation_date_time.map(|time| {
+            match change.approximate_creation_date_time_precision.as_deref() {
+                Some("MICROSECOND") => UNIX_EPOCH + Duration::from_micros(time),
+                _ => UNIX_EPOCH + Duration::from_millis(time),
+            }
+        });
+    RawRecord {
+        event_id: record.event_id,
+        event_name: &record.event_name,
+        table_name: record.table_name,
+        sequence_number: None,
+        approximate_creation_time,
+        user_identity: record.user_identity.map(|identity| UserIdentity {
+            type_: identity.type_,
+            principal_id: identity.principal_id,
+        }),
+        view_type: None,
+        keys: change.keys,
+        new_image: change.new_image,
+        old_image: change.old_image,
+    }
+    .into_typed()
+}
+
+/// Parse the base64-encoded data of a Kinesis record into a typed stream record.
+pub fn from_base64<T, K>(data: &str) -> Result<StreamRecord<T, K>>
+where
+    T: DeserializeOwned,
+    K: DeserializeOwned,
+{
+    let data = STANDARD.decode(data).map_err(|err| -> crate::Error {
+        ErrorImpl::InvalidStreamRecord(format!("invalid base64 Kinesis data: {}", err)).into()
+    })?;
+    from_slice(&data)
+}
+
+/// The JSON document of a change, which is all a Kinesis record's data is.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    #[serde(rename = "eventID", default)]
+    event_id: Option<String>,
+    event_name: String,
+    #[serde(default)]
+    table_name: Option<String>,
+    #[serde(default)]
+    user_identity: Option<Identity>,
+    dynamodb: Change,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Identity {
+    #[serde(rename = "type", default)]
+    type_: String,
+    #[serde(default)]
+    principal_id: String,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Change {
+    #[serde(default)]
+    approximate_creation_date_time: Option<u64>,
+    #[serde(default)]
+    approximate_creation_date_time_precision: Option<String>,
+    #[serde(default)]
+    keys: Item,
+    #[serde(default)]
+    new_image: Option<Item>,
+    #[serde(default)]
+    old_image: Option<Item>,
+}
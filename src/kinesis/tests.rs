@@ -0,0 +1,162 @@
+use super::*;
+use crate::stream::Change;
+use serde_derive::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct User {
+    id: String,
+    name: String,
+    #[serde(with = "serde_bytes")]
+    avatar: Vec<u8>,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct UserKey {
+    id: String,
+}
+
+fn user(name: &str) -> User {
+    User {
+        id: String::from("arthur"),
+        name: String::from(name),
+        avatar: vec![0, 1, 255],
+        tags: vec![String::from("human")],
+    }
+}
+
+fn image(name: &str) -> serde_json::Value {
+    json!({
+        "id": { "S": "arthur" },
+        "name": { "S": name },
+        "avatar": { "B": "AAH/" },
+        "tags": { "SS": ["human"] }
+    })
+}
+
+fn data(event_name: &str, dynamodb: serde_json::Value) -> Vec<u8> {
+    serde_json::to_vec(&json!({
+        "awsRegion": "us-east-1",
+        "eventID": "4b25bd0d-f7ed-4a8b-a4a2-1fd4a4bc5f53",
+        "eventName": event_name,
+        "userIdentity": null,
+        "recordFormat": "application/json",
+        "tableName": "users",
+        "dynamodb": dynamodb,
+        "eventSource": "aws:dynamodb"
+    }))
+    .unwrap()
+}
+
+#[test]
+fn insert() {
+    let data = data(
+        "INSERT",
+        json!({
+            "ApproximateCreationDateTime": 1646776444906u64,
+            "Keys": { "id": { "S": "arthur" } },
+            "NewImage": image("Arthur Dent"),
+            "SizeBytes": 48
+        }),
+    );
+    let record: StreamRecord<User, UserKey> = from_slice(&data).unwrap();
+
+    assert_eq!(record.change, Change::Insert(Some(user("Arthur Dent"))));
+    assert_eq!(record.keys.id, "arthur");
+    assert_eq!(record.table_name.as_deref(), Some("users"));
+    assert_eq!(
+        record.event_id.as_deref(),
+        Some("4b25bd0d-f7ed-4a8b-a4a2-1fd4a4bc5f53")
+    );
+    assert_eq!(
+        record.approximate_creation_time,
+        Some(UNIX_EPOCH + Duration::from_millis(1646776444906))
+    );
+    assert_eq!(record.sequence_number, None);
+    assert_eq!(record.view_type, None);
+    assert_eq!(record.user_identity, None);
+}
+
+#[test]
+fn modify_and_remove() {
+    let data = data(
+        "MODIFY",
+        json!({
+            "ApproximateCreationDateTime": 1646776444906123u64,
+            "ApproximateCreationDateTimePrecision": "MICROSECOND",
+            "Keys": { "id": { "S": "arthur" } },
+            "NewImage": image("Arthur Dent"),
+            "OldImage": image("Arthur"),
+            "SizeBytes": 96
+        }),
+    );
+    let record: StreamRecord<User, UserKey> = from_slice(&data).unwrap();
+    assert_eq!(
+        record.change,
+        Change::Modify {
+            old: Some(user("Arthur")),
+            new: Some(user("Arthur Dent")),
+        }
+    );
+    assert_eq!(
+        record.approximate_creation_time,
+        Some(UNIX_EPOCH + Duration::from_micros(1646776444906123))
+    );
+
+    let mut data: serde_json::Value = serde_json::from_slice(&self::data(
+        "REMOVE",
+        json!({
+            "Keys": { "id": { "S": "arthur" } },
+            "OldImage": image("Arthur"),
+            "SizeBytes": 48
+        }),
+    ))
+    .unwrap();
+    data["userIdentity"] = json!({
+        "type": "Service",
+        "principalId": "dynamodb.amazonaws.com"
+    });
+    let record: StreamRecord<User, UserKey> =
+        from_slice(&serde_json::to_vec(&data).unwrap()).unwrap();
+    assert_eq!(record.change, Change::Remove(Some(user("Arthur"))));
+    assert!(record.is_ttl_delete());
+}
+
+#[test]
+fn base64() {
+    let data = data(
+        "INSERT",
+        json!({
+            "Keys": { "id": { "S": "arthur" } },
+            "NewImage": image("Arthur Dent"),
+            "SizeBytes": 48
+        }),
+    );
+    let record: StreamRecord<User, UserKey> = from_base64(&STANDARD.encode(&data)).unwrap();
+    assert_eq!(record.change, Change::Insert(Some(user("Arthur Dent"))));
+
+    let err = from_base64::<User, UserKey>("not base64!").unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Invalid stream record: invalid base64 Kinesis data"));
+}
+
+#[test]
+fn invalid_data() {
+    let err = from_slice::<User, UserKey>(b"{\"eventName\": \"INSERT\"}").unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Invalid stream record: failed to parse Kinesis data"));
+
+    let data = data(
+        "INSERT",
+        json!({
+            "Keys": { "id": { "S": "arthur" } },
+            "NewImage": { "id": { "S": "arthur" } },
+            "SizeBytes": 12
+        }),
+    );
+    let err = from_slice::<User, UserKey>(&data).unwrap_err();
+    assert_eq!(err.to_string(), "missing field `name`");
+}
@@ -0,0 +1,187 @@
+use crate::stream::{Change, StreamRecord};
+use aws_lambda_events::event::dynamodb::Event;
+use aws_lambda_events::streams::{DynamoDbBatchItemFailure, DynamoDbEventResponse};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::future::Future;
+
+/// Runs a typed handler on the records of a DynamoDB Streams event, and reports the records that
+/// failed so Lambda retries from the first of them.
+///
+/// Each record is converted into a [`Change`] and passed to the handler. A record that can't be
+/// converted fails just like one the handler returns an error for. The function has to be set up
+/// with `ReportBatchItemFailures` for Lambda to use the [`BatchReport::response`].
+///
+/// For stream event sources, Lambda doesn't retry only the reported records: it checkpoints
+/// before the failed record with the lowest sequence number, and delivers every record after it
+/// again, including those that succeeded. So by default, the processor stops handling a shard's
+/// records at its first failure and reports the rest as failed too: no record is handled again
+/// after it succeeded, and an item's changes are handled in order. See
+/// [`ordered`](BatchProcessor::ordered) to handle them anyway.
+///
+/// ```
+/// use aws_lambda_events::event::dynamodb::Event;
+/// use aws_lambda_events::streams::DynamoDbEventResponse;
+/// use serde_dynamo::lambda::BatchProcessor;
+/// use serde_dynamo::stream::Change;
+/// # use serde_derive::Deserialize;
+/// #
+/// # #[derive(Deserialize)]
+/// # struct User { id: String, name: String }
+/// # async fn welcome(user: &User) -> Result<(), std::io::Error> { Ok(()) }
+///
+/// async fn handler(event: Event) -> DynamoDbEventResponse {
+///     let report = BatchProcessor::new()
+///         .process(event, |change: Change<User>| async move {
+///             match change {
+///                 Change::Insert(Some(user)) => welcome(&user).await,
+///                 _ => Ok(()),
+///             }
+///         })
+///         .await;
+///     for failed in &report.failed {
+///         eprintln!("{:?} failed: {}", failed.sequence_number, failed.reason);
+///     }
+///     report.response()
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BatchProcessor {
+    ordered: bool,
+}
+
+impl Default for BatchProcessor {
+    fn default() -> Self {
+        BatchProcessor { ordered: true }
+    }
+}
+
+impl BatchProcessor {
+    /// A processor that stops handling a shard's records at its first failure.
+    pub fn new() -> Self {
+        BatchProcessor::default()
+    }
+
+    /// Whether to stop handling a shard's records at its first failure, and report the records
+    /// after it as failed too, so they are retried in order. This is the default.
+    ///
+    /// With `false`, every record is handled whether or not the ones before it failed. Lambda
+    /// still delivers every record after the first failure again, so the handler has to be
+    /// idempotent, and has to cope with seeing an item's changes out of order.
+    ///
+    /// Records are told apart by their stream's ARN, since Lambda gives each batch records of a
+    /// single shard.
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// Handle the records of `event` one after the other.
+    pub async fn process<T, F, Fut, E>(&self, event: Event, handler: F) -> BatchReport<E>
+    where
+        T: DeserializeOwned,
+        F: Fn(Change<T>) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        let mut report = BatchReport {
+            processed: 0,
+            failed: Vec::new(),
+        };
+        let mut failed_streams = HashSet::new();
+
+        for record in event.records {
+            let sequence_number = record.change.sequence_number.clone();
+            let event_id = record.event_id.clone();
+            let stream = record.event_source_arn.clone();
+
+            let reason = if self.ordered && failed_streams.contains(&stream) {
+                Some(RecordFailure::Skipped)
+            } else {
+                match StreamRecord::<T, IgnoredAny>::try_from(record) {
+                    Ok(record) => handler(record.change)
+                        .await
+                        .err()
+                        .map(RecordFailure::Handler),
+                    Err(err) => Some(RecordFailure::Decode(err)),
+                }
+            };
+
+            match reason {
+                Some(reason) => {
+                    failed_streams.insert(stream);
+                    report.failed.push(FailedRecord {
+                        event_id,
+                        sequence_number,
+                        reason,
+                    });
+                }
+                None => report.processed += 1,
+            }
+        }
+        report
+    }
+}
+
+/// What happened to the records of an event a [`BatchProcessor`] handled.
+#[derive(Debug)]
+pub struct BatchReport<E> {
+    /// How many records were handled successfully
+    pub processed: usize,
+    /// The records that weren't, in the event's order
+    pub failed: Vec<FailedRecord<E>>,
+}
+
+impl<E> BatchReport<E> {
+    /// Whether every record was handled successfully.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// The partial batch response for Lambda, listing the sequence numbers of the failed
+    /// records.
+    pub fn response(&self) -> DynamoDbEventResponse {
+        DynamoDbEventResponse {
+            batch_item_failures: self
+                .failed
+                .iter()
+                .map(|failed| DynamoDbBatchItemFailure {
+                    item_identifier: failed.sequence_number.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A record a [`BatchProcessor`] couldn't handle.
+#[derive(Debug)]
+pub struct FailedRecord<E> {
+    /// The record's event ID
+    pub event_id: String,
+    /// The record's sequence number, which identifies it to Lambda
+    pub sequence_number: Option<String>,
+    /// Why it failed
+    pub reason: RecordFailure<E>,
+}
+
+/// Why a record failed.
+#[derive(Debug)]
+pub enum RecordFailure<E> {
+    /// The record couldn't be converted into a [`Change`]
+    Decode(crate::Error),
+    /// The handler returned an error
+    Handler(E),
+    /// An earlier record of the same shard failed, and the processor is ordered
+    Skipped,
+}
+
+impl<E: Display> Display for RecordFailure<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordFailure::Decode(err) => Display::fmt(err, f),
+            RecordFailure::Handler(err) => Display::fmt(err, f),
+            RecordFailure::Skipped => f.write_str("Skipped after an earlier record failed"),
+        }
+    }
+}
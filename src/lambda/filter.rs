@@ -0,0 +1,310 @@
+use crate::error::ErrorImpl;
+use crate::expression::{AttributeType, Path, PathElement};
+use crate::{to_attribute_value, AttributeValue, Result};
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+/// Builds the filter criteria patterns of a Lambda event source mapping for a DynamoDB stream.
+///
+/// A pattern is JSON over a stream record, with the keys and images in DynamoDB JSON, so a filter
+/// on a string attribute has to be written as `{"status": {"S": ["active"]}}`. The builder
+/// encodes values with [`to_attribute_value`] to get that wrapping right:
+///
+/// ```
+/// use serde_dynamo::lambda::{FieldFilter, FilterPattern};
+///
+/// # fn test() -> serde_dynamo::Result<()> {
+/// let pattern = FilterPattern::new()
+///     .event_name("INSERT")
+///     .new_image("status", FieldFilter::equals("active"))
+///     .new_image("age", FieldFilter::between(18, 65))
+///     .to_pattern()?;
+/// assert_eq!(
+///     pattern,
+///     r#"{"dynamodb":{"NewImage":{"age":{"N":[{"numeric":[">=",18,"<=",65]}]},"status":{"S":["active"]}}},"eventName":["INSERT"]}"#
+/// );
+/// # Ok(())
+/// # }
+/// # test().unwrap()
+/// ```
+///
+/// A record has to match every field's filter, but filters added for the same field match if
+/// either does. Nested attributes are filtered through their paths, such as `address.city`.
+#[derive(Debug, Clone, Default)]
+pub struct FilterPattern {
+    event_names: Vec<String>,
+    fields: Vec<(&'static str, Path, FieldFilter)>,
+}
+
+impl FilterPattern {
+    /// A pattern that matches every record.
+    pub fn new() -> Self {
+        FilterPattern::default()
+    }
+
+    /// Only match records of the event `name`: `INSERT`, `MODIFY` or `REMOVE`.
+    ///
+    /// With more than one, records of any of them match.
+    pub fn event_name(mut self, name: impl Into<String>) -> Self {
+        self.event_names.push(name.into());
+        self
+    }
+
+    /// Filter on an attribute of the record's `Keys`.
+    pub fn keys(self, path: impl Into<Path>, filter: FieldFilter) -> Self {
+        self.field("Keys", path.into(), filter)
+    }
+
+    /// Filter on an attribute of the record's `NewImage`.
+    pub fn new_image(self, path: impl Into<Path>, filter: FieldFilter) -> Self {
+        self.field("NewImage", path.into(), filter)
+    }
+
+    /// Filter on an attribute of the record's `OldImage`.
+    pub fn old_image(self, path: impl Into<Path>, filter: FieldFilter) -> Self {
+        self.field("OldImage", path.into(), filter)
+    }
+
+    fn field(mut self, image: &'static str, path: Path, filter: FieldFilter) -> Self {
+        self.fields.push((image, path, filter));
+        self
+    }
+
+    /// The pattern as JSON.
+    ///
+    /// Fails if a value can't be filtered on, or if one field is filtered as different types.
+    pub fn build(&self) -> Result<Value> {
+        let mut pattern = Map::new();
+        if !self.event_names.is_empty() {
+            let names = self.event_names.iter().cloned().map(Value::String);
+            pattern.insert(String::from("eventName"), Value::Array(names.collect()));
+        }
+        let mut images = Map::new();
+        for (image, path, filter) in &self.fields {
+            let (attribute_type, matchers) = filter.0.clone()?;
+            let image = object(&mut images, image);
+            insert(image, path, path.elements(), attribute_type, matchers)?;
+        }
+        if !images.is_empty() {
+            pattern.insert(String::from("dynamodb"), Value::Object(images));
+        }
+        Ok(Value::Object(pattern))
+    }
+
+    /// The pattern as the string an event source mapping's `FilterCriteria` takes.
+    pub fn to_pattern(&self) -> Result<String> {
+        Ok(self.build()?.to_string())
+    }
+}
+
+/// The object at `key` of `map`, made empty if there isn't one.
+fn object<'a>(map: &'a mut Map<String, Value>, key: &str) -> &'a mut Map<String, Value> {
+    let value = map.entry(key).or_insert_with(|| Value::Object(Map::new()));
+    match value {
+        Value::Object(object) => object,
+        _ => unreachable!("only objects are inserted"),
+    }
+}
+
+/// Add the matchers of a filter at `path`, nesting maps in `M`.
+fn insert(
+    map: &mut Map<String, Value>,
+    path: &Path,
+    elements: &[PathElement],
+    attribute_type: AttributeType,
+    matchers: Vec<Value>,
+) -> Result<()> {
+    let (name, rest) = match elements.split_first() {
+        Some((PathElement::Attribute(name), rest)) => (name, rest),
+        _ => {
+            return Err(ErrorImpl::Message(format!(
+                "Can't filter on {}: filter patterns can't index into lists",
+                path
+            ))
+            .into())
+        }
+    };
+    let field = object(map, name);
+    let type_key = if rest.is_empty() {
+        attribute_type.to_string()
+    } else {
+        String::from("M")
+    };
+    if let Some(other) = field.keys().find(|key| **key != type_key) {
+        return Err(ErrorImpl::Message(format!(
+            "Can't filter on {} as both {} and {}",
+            path, other, type_key
+        ))
+        .into());
+    }
+    if rest.is_empty() {
+        match field
+            .entry(type_key)
+            .or_insert_with(|| Value::Array(Vec::new()))
+        {
+            Value::Array(existing) => existing.extend(matchers),
+            _ => unreachable!("only arrays are inserted"),
+        }
+        Ok(())
+    } else {
+        insert(
+            object(field, &type_key),
+            path,
+            rest,
+            attribute_type,
+            matchers,
+        )
+    }
+}
+
+/// What a [`FilterPattern`] matches an attribute against.
+///
+/// Values are encoded with [`to_attribute_value`], and have to be strings, numbers, booleans,
+/// or null.
+#[derive(Debug, Clone)]
+pub struct FieldFilter(Result<(AttributeType, Vec<Value>)>);
+
+impl FieldFilter {
+    /// Match attributes equal to `value`.
+    pub fn equals<T: Serialize>(value: T) -> Self {
+        Self::any_of(std::iter::once(value))
+    }
+
+    /// Match attributes equal to any of `values`, which have to be of one type.
+    pub fn any_of<I, T>(values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+    {
+        FieldFilter(scalars(values))
+    }
+
+    /// Match attributes equal to none of `values`, which have to be of one type.
+    ///
+    /// Attributes of another type than the values don't match either.
+    pub fn anything_but<I, T>(values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+    {
+        FieldFilter(scalars(values).map(|(attribute_type, values)| {
+            let mut matcher = Map::new();
+            matcher.insert(String::from("anything-but"), Value::Array(values));
+            (attribute_type, vec![Value::Object(matcher)])
+        }))
+    }
+
+    /// Match strings that start with `prefix`.
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        FieldFilter(Ok((
+            AttributeType::S,
+            vec![operator("prefix", prefix.into())],
+        )))
+    }
+
+    /// Match numbers greater than `value`.
+    pub fn gt<T: Serialize>(value: T) -> Self {
+        Self::numeric(vec![(">", value)])
+    }
+
+    /// Match numbers greater than or equal to `value`.
+    pub fn ge<T: Serialize>(value: T) -> Self {
+        Self::numeric(vec![(">=", value)])
+    }
+
+    /// Match numbers less than `value`.
+    pub fn lt<T: Serialize>(value: T) -> Self {
+        Self::numeric(vec![("<", value)])
+    }
+
+    /// Match numbers less than or equal to `value`.
+    pub fn le<T: Serialize>(value: T) -> Self {
+        Self::numeric(vec![("<=", value)])
+    }
+
+    /// Match numbers from `low` to `high`, inclusive.
+    pub fn between<T: Serialize>(low: T, high: T) -> Self {
+        Self::numeric(vec![(">=", low), ("<=", high)])
+    }
+
+    /// Match attributes of `attribute_type`, whatever their value.
+    pub fn exists(attribute_type: AttributeType) -> Self {
+        FieldFilter(Ok((attribute_type, vec![operator("exists", true)])))
+    }
+
+    /// Match records without the attribute, or where it isn't of `attribute_type`.
+    pub fn not_exists(attribute_type: AttributeType) -> Self {
+        FieldFilter(Ok((attribute_type, vec![operator("exists", false)])))
+    }
+
+    fn numeric<T: Serialize>(bounds: Vec<(&'static str, T)>) -> Self {
+        let bounds = bounds
+            .into_iter()
+            .map(|(comparator, value)| {
+                let number = to_attribute_value(value)?
+                    .n
+                    .and_then(|n| n.parse::<Number>().ok())
+                    .ok_or_else(|| -> crate::Error {
+                        ErrorImpl::Message(String::from("Numeric filters need numbers")).into()
+                    })?;
+                Ok(vec![Value::from(comparator), Value::Number(number)])
+            })
+            .collect::<Result<Vec<_>>>();
+        FieldFilter(bounds.map(|bounds| {
+            let bounds = bounds.into_iter().flatten().collect();
+            (
+                AttributeType::N,
+                vec![operator("numeric", Value::Array(bounds))],
+            )
+        }))
+    }
+}
+
+fn operator(name: &str, operand: impl Into<Value>) -> Value {
+    let mut matcher = Map::new();
+    matcher.insert(name.to_string(), operand.into());
+    Value::Object(matcher)
+}
+
+/// Encode values as the type they are, and the JSON they appear as in stream records.
+fn scalars<I, T>(values: I) -> Result<(AttributeType, Vec<Value>)>
+where
+    I: IntoIterator<Item = T>,
+    T: Serialize,
+{
+    let mut attribute_type = None;
+    let mut scalars = Vec::new();
+    for value in values {
+        let (value_type, scalar) = scalar(to_attribute_value(value)?)?;
+        if attribute_type.is_some_and(|attribute_type| attribute_type != value_type) {
+            return Err(ErrorImpl::Message(String::from(
+                "Values filtered on together have to be of one type",
+            ))
+            .into());
+        }
+        attribute_type = Some(value_type);
+        scalars.push(scalar);
+    }
+    match attribute_type {
+        Some(attribute_type) => Ok((attribute_type, scalars)),
+        None => Err(ErrorImpl::Message(String::from("Filters need at least one value")).into()),
+    }
+}
+
+fn scalar(value: AttributeValue) -> Result<(AttributeType, Value)> {
+    if let Some(s) = value.s {
+        Ok((AttributeType::S, Value::String(s)))
+    } else if let Some(n) = value.n {
+        // Stream records carry numbers as strings
+        Ok((AttributeType::N, Value::String(n)))
+    } else if let Some(bool) = value.bool {
+        Ok((AttributeType::Bool, Value::Bool(bool)))
+    } else if let Some(null) = value.null {
+        Ok((AttributeType::Null, Value::Bool(null)))
+    } else {
+        Err(ErrorImpl::Message(String::from(
+            "Only strings, numbers, booleans and null can be filtered on",
+        ))
+        .into())
+    }
+}
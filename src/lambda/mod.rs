@@ -0,0 +1,189 @@
+//! Typed images of the DynamoDB Streams records that [`aws_lambda_events`] hands to Lambda
+//! functions.
+//!
+//! A Lambda function subscribed to a table's stream receives its `Keys`, `NewImage` and
+//! `OldImage` in the stream's own attribute value type, rather than rusoto's. [`StreamImages`]
+//! deserializes them directly:
+//!
+//! ```
+//! use aws_lambda_events::event::dynamodb::Event;
+//! use serde_dynamo::lambda::StreamImages;
+//! # use serde_derive::Deserialize;
+//! #
+//! # #[derive(Deserialize)]
+//! # struct User { id: String, name: String }
+//!
+//! fn handle(event: Event) -> serde_dynamo::Result<()> {
+//!     for record in event.records {
+//!         if record.event_name == "INSERT" {
+//!             let user: User = record.new_image()?;
+//!             println!("Welcome, {}", user.name);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Sets, numbers and binary values come across as they are, so anything [`from_item`] can read
+//! from a table, it can read from the stream.
+//!
+//! A whole record converts into a typed [`stream::StreamRecord`], whose
+//! [`Change`](stream::Change) says what happened to the item:
+//!
+//! ```
+//! use aws_lambda_events::event::dynamodb::EventRecord;
+//! use serde_dynamo::stream::{Change, StreamRecord};
+//! use std::convert::TryFrom;
+//! # use serde_derive::Deserialize;
+//! #
+//! # #[derive(Deserialize)]
+//! # struct User { id: String, name: String }
+//! # #[derive(Deserialize)]
+//! # struct UserKey { id: String }
+//!
+//! fn handle(record: EventRecord) -> serde_dynamo::Result<()> {
+//!     let record = StreamRecord::<User, UserKey>::try_from(record)?;
+//!     match record.change {
+//!         Change::Insert(Some(user)) => println!("Welcome, {}", user.name),
+//!         Change::Modify { old: Some(old), new: Some(new) } if old.name != new.name => {
+//!             println!("{} is now {}", old.name, new.name)
+//!         }
+//!         Change::Remove(_) if record.is_ttl_delete() => println!("{} expired", record.keys.id),
+//!         _ => {}
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! A [`BatchProcessor`] does this for every record of an event, handing each
+//! [`Change`](stream::Change) to a handler, and builds the partial batch response that has Lambda
+//! retry from the first record that failed. A [`FilterPattern`] keeps records from reaching the function
+//! at all, by building the filter criteria of its event source mapping.
+
+use crate::error::ErrorImpl;
+use crate::stream::{self, RawRecord, StreamViewType, UserIdentity};
+use crate::{from_item, AttributeValue, Item, Result};
+use aws_lambda_events::event::dynamodb::{
+    EventRecord, StreamRecord, StreamViewType as LambdaStreamViewType,
+};
+use serde::de::DeserializeOwned;
+use serde_dynamo_4::AttributeValue as StreamAttributeValue;
+use std::convert::TryFrom;
+
+mod batch;
+mod filter;
+
+#[cfg(test)]
+mod tests;
+
+pub use crate::stream::StreamImages;
+pub use batch::{BatchProcessor, BatchReport, FailedRecord, RecordFailure};
+pub use filter::{FieldFilter, FilterPattern};
+
+/// An item the way [`aws_lambda_events`] represents it.
+pub type StreamItem = serde_dynamo_4::Item;
+
+impl StreamImages for StreamRecord {
+    fn keys<K: DeserializeOwned>(&self) -> Result<K> {
+        image(&self.keys, "Keys")
+    }
+
+    fn new_image<T: DeserializeOwned>(&self) -> Result<T> {
+        image(&self.new_image, "NewImage")
+    }
+
+    fn old_image<T: DeserializeOwned>(&self) -> Result<T> {
+        image(&self.old_image, "OldImage")
+    }
+}
+
+impl StreamImages for EventRecord {
+    fn keys<K: DeserializeOwned>(&self) -> Result<K> {
+        self.change.keys()
+    }
+
+    fn new_image<T: DeserializeOwned>(&self) -> Result<T> {
+        self.change.new_image()
+    }
+
+    fn old_image<T: DeserializeOwned>(&self) -> Result<T> {
+        self.change.old_image()
+    }
+}
+
+impl<T, K> TryFrom<EventRecord> for stream::StreamRecord<T, K>
+where
+    T: DeserializeOwned,
+    K: DeserializeOwned,
+{
+    type Error = crate::Error;
+
+    /// Deserialize the record's keys and images.
+    ///
+    /// Fails if the record lacks an image its change and its stream's view type call for, such
+    /// as the `NewImage` of an `INSERT` to a `NEW_IMAGE` stream.
+    fn try_from(record: EventRecord) -> Result<Self> {
+        let change = record.change;
+        RawRecord {
+            event_id: Some(record.event_id),
+            event_name: &record.event_name,
+            table_name: record.table_name,
+            sequence_number: change.sequence_number,
+            approximate_creation_time: Some(change.approximate_creation_date_time.into()),
+            user_identity: record.user_identity.map(|identity| UserIdentity {
+                type_: identity.type_,
+                principal_id: identity.principal_id,
+            }),
+            view_type: change.stream_view_type.map(|view_type| match view_type {
+                LambdaStreamViewType::KeysOnly => StreamViewType::KeysOnly,
+                LambdaStreamViewType::NewImage => StreamViewType::NewImage,
+                LambdaStreamViewType::OldImage => StreamViewType::OldImage,
+                LambdaStreamViewType::NewAndOldImages => StreamViewType::NewAndOldImages,
+            }),
+            keys: from_stream_item(change.keys),
+            new_image: Some(from_stream_item(change.new_image)),
+            old_image: Some(from_stream_item(change.old_image)),
+        }
+        .into_typed()
+    }
+}
+
+/// Deserialize one of a record's images, which `aws_lambda_events` leaves empty when the record
+/// doesn't have it.
+fn image<T: DeserializeOwned>(item: &StreamItem, name: &'static str) -> Result<T> {
+    if item.is_empty() {
+        return Err(ErrorImpl::MissingImage(name).into());
+    }
+    from_item(from_stream_item(item.clone()))
+}
+
+/// Convert an item from a stream record to rusoto's, without losing anything.
+pub fn from_stream_item(item: StreamItem) -> Item {
+    let item: std::collections::HashMap<String, StreamAttributeValue> = item.into();
+    item.into_iter()
+        .map(|(name, value)| (name, from_stream_value(value)))
+        .collect()
+}
+
+/// Convert an attribute value from a stream record to rusoto's, without losing anything: sets
+/// stay sets, and binary stays binary.
+pub fn from_stream_value(value: StreamAttributeValue) -> AttributeValue {
+    let mut converted = AttributeValue::default();
+    match value {
+        StreamAttributeValue::S(s) => converted.s = Some(s),
+        StreamAttributeValue::N(n) => converted.n = Some(n),
+        StreamAttributeValue::B(b) => converted.b = Some(b.into()),
+        StreamAttributeValue::Ss(ss) => converted.ss = Some(ss),
+        StreamAttributeValue::Ns(ns) => converted.ns = Some(ns),
+        StreamAttributeValue::Bs(bs) => {
+            converted.bs = Some(bs.into_iter().map(Into::into).collect())
+        }
+        StreamAttributeValue::L(l) => {
+            converted.l = Some(l.into_iter().map(from_stream_value).collect())
+        }
+        StreamAttributeValue::M(m) => converted.m = Some(from_stream_item(m.into())),
+        StreamAttributeValue::Bool(bool) => converted.bool = Some(bool),
+        StreamAttributeValue::Null(null) => converted.null = Some(null),
+    }
+    converted
+}
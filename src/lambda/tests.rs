@@ -0,0 +1,439 @@
+use super::*;
+use aws_lambda_events::event::dynamodb::Event;
+use serde_derive::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct User {
+    id: String,
+    name: String,
+    age: u8,
+    #[serde(with = "serde_bytes")]
+    avatar: Vec<u8>,
+    tags: Vec<String>,
+    scores: Vec<u32>,
+    address: Address,
+    nickname: Option<String>,
+    verified: bool,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Address {
+    city: String,
+    lines: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct UserKey {
+    id: String,
+}
+
+fn record(event_name: &str, dynamodb: serde_json::Value) -> EventRecord {
+    let event: Event = serde_json::from_value(json!({
+        "Records": [{
+            "eventID": "c4ca4238a0b923820dcc509a6f75849b",
+            "eventName": event_name,
+            "eventVersion": "1.1",
+            "eventSource": "aws:dynamodb",
+            "awsRegion": "us-east-1",
+            "dynamodb": dynamodb,
+            "eventSourceARN": "arn:aws:dynamodb:us-east-1:123456789012:table/users/stream/2015-06-27T00:48:05.899"
+        }]
+    }))
+    .unwrap();
+    event.records.into_iter().next().unwrap()
+}
+
+fn new_image() -> serde_json::Value {
+    json!({
+        "id": { "S": "arthur" },
+        "name": { "S": "Arthur Dent" },
+        "age": { "N": "42" },
+        "avatar": { "B": "AAH/" },
+        "tags": { "SS": ["human", "earthling"] },
+        "scores": { "NS": ["1", "2"] },
+        "address": { "M": {
+            "city": { "S": "Cottington" },
+            "lines": { "L": [{ "S": "155 Country Lane" }] }
+        } },
+        "nickname": { "NULL": true },
+        "verified": { "BOOL": false }
+    })
+}
+
+#[test]
+fn new_image_of_an_insert() {
+    let record = record(
+        "INSERT",
+        json!({
+            "ApproximateCreationDateTime": 1479499740,
+            "Keys": { "id": { "S": "arthur" } },
+            "NewImage": new_image(),
+            "SequenceNumber": "13021600000000001596893679",
+            "SizeBytes": 112,
+            "StreamViewType": "NEW_IMAGE"
+        }),
+    );
+
+    let user: User = record.new_image().unwrap();
+    assert_eq!(
+        user,
+        User {
+            id: String::from("arthur"),
+            name: String::from("Arthur Dent"),
+            age: 42,
+            avatar: vec![0, 1, 255],
+            tags: vec![String::from("human"), String::from("earthling")],
+            scores: vec![1, 2],
+            address: Address {
+                city: String::from("Cottington"),
+                lines: vec![String::from("155 Country Lane")],
+            },
+            nickname: None,
+            verified: false,
+        }
+    );
+    let key: UserKey = record.keys().unwrap();
+    assert_eq!(key.id, "arthur");
+    assert_eq!(record.change.new_image::<User>().unwrap(), user);
+}
+
+#[test]
+fn missing_image() {
+    let record = record(
+        "REMOVE",
+        json!({
+            "ApproximateCreationDateTime": 1479499740,
+            "Keys": { "id": { "S": "arthur" } },
+            "SequenceNumber": "13021600000000001596893679",
+            "SizeBytes": 38,
+            "StreamViewType": "KEYS_ONLY"
+        }),
+    );
+
+    let err = record.old_image::<User>().unwrap_err();
+    assert_eq!(err.to_string(), "The stream record has no OldImage");
+    let err = record.new_image::<User>().unwrap_err();
+    assert_eq!(err.to_string(), "The stream record has no NewImage");
+    assert!(record.keys::<UserKey>().is_ok());
+}
+
+#[test]
+fn converts_without_losing_anything() {
+    let image: StreamItem = serde_json::from_value(new_image()).unwrap();
+    let item = from_stream_item(image);
+
+    assert_eq!(item["tags"].ss.as_ref().unwrap().len(), 2);
+    assert_eq!(
+        item["scores"].ns,
+        Some(vec![String::from("1"), String::from("2")])
+    );
+    assert_eq!(item["avatar"].b.as_deref(), Some(&[0, 1, 255][..]));
+    assert_eq!(item["nickname"].null, Some(true));
+    assert_eq!(
+        item["address"].m.as_ref().unwrap()["lines"]
+            .l
+            .as_ref()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let bs = from_stream_value(StreamAttributeValue::Bs(vec![vec![1], vec![2, 3]]));
+    assert_eq!(bs.bs.unwrap().len(), 2);
+}
+
+#[test]
+fn typed_stream_records() {
+    use crate::stream::{Change, StreamRecord, StreamViewType};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Name {
+        id: String,
+        name: String,
+    }
+
+    let modify = record(
+        "MODIFY",
+        json!({
+            "ApproximateCreationDateTime": 1479499740,
+            "Keys": { "id": { "S": "arthur" } },
+            "NewImage": { "id": { "S": "arthur" }, "name": { "S": "Arthur Dent" } },
+            "OldImage": { "id": { "S": "arthur" }, "name": { "S": "Arthur" } },
+            "SequenceNumber": "13021600000000001596893679",
+            "SizeBytes": 112,
+            "StreamViewType": "NEW_AND_OLD_IMAGES"
+        }),
+    );
+    let typed = StreamRecord::<Name, UserKey>::try_from(modify).unwrap();
+    assert_eq!(typed.keys.id, "arthur");
+    assert_eq!(
+        typed.sequence_number.as_deref(),
+        Some("13021600000000001596893679")
+    );
+    assert_eq!(
+        typed.approximate_creation_time,
+        Some(UNIX_EPOCH + Duration::from_secs(1479499740))
+    );
+    assert_eq!(typed.view_type, Some(StreamViewType::NewAndOldImages));
+    match typed.change {
+        Change::Modify {
+            old: Some(old),
+            new: Some(new),
+        } => {
+            assert_eq!(old.name, "Arthur");
+            assert_eq!(new.name, "Arthur Dent");
+        }
+        change => panic!("unexpected change {:?}", change),
+    }
+
+    let mut expired = record(
+        "REMOVE",
+        json!({
+            "ApproximateCreationDateTime": 1479499740,
+            "Keys": { "id": { "S": "arthur" } },
+            "SizeBytes": 38,
+            "StreamViewType": "NEW_AND_OLD_IMAGES"
+        }),
+    );
+    expired.user_identity = Some(aws_lambda_events::event::dynamodb::UserIdentity {
+        type_: String::from("Service"),
+        principal_id: String::from("dynamodb.amazonaws.com"),
+    });
+    let err = StreamRecord::<Name, UserKey>::try_from(expired.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid stream record: it has no OldImage, though its view type NEW_AND_OLD_IMAGES includes it"
+    );
+    expired.change.stream_view_type =
+        Some(aws_lambda_events::event::dynamodb::StreamViewType::KeysOnly);
+    let typed = StreamRecord::<Name, UserKey>::try_from(expired).unwrap();
+    assert_eq!(typed.change, Change::Remove(None));
+    assert!(typed.is_ttl_delete());
+}
+
+fn event(records: Vec<(&str, &str, serde_json::Value)>) -> Event {
+    let records: Vec<_> = records
+        .into_iter()
+        .map(|(sequence_number, stream, new_image)| {
+            json!({
+                "eventID": sequence_number,
+                "eventName": "INSERT",
+                "awsRegion": "us-east-1",
+                "eventSourceARN": stream,
+                "dynamodb": {
+                    "ApproximateCreationDateTime": 1479499740,
+                    "Keys": { "id": new_image["id"].clone() },
+                    "NewImage": new_image,
+                    "SequenceNumber": sequence_number,
+                    "SizeBytes": 26,
+                    "StreamViewType": "NEW_IMAGE"
+                }
+            })
+        })
+        .collect();
+    serde_json::from_value(json!({ "Records": records })).unwrap()
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Name {
+    id: String,
+    name: String,
+}
+
+fn batch() -> Event {
+    event(vec![
+        (
+            "1",
+            "a",
+            json!({ "id": { "S": "1" }, "name": { "S": "Arthur" } }),
+        ),
+        ("2", "a", json!({ "id": { "S": "2" } })),
+        (
+            "3",
+            "a",
+            json!({ "id": { "S": "3" }, "name": { "S": "Marvin" } }),
+        ),
+        (
+            "4",
+            "a",
+            json!({ "id": { "S": "4" }, "name": { "S": "Ford" } }),
+        ),
+        (
+            "5",
+            "b",
+            json!({ "id": { "S": "5" }, "name": { "S": "Zaphod" } }),
+        ),
+    ])
+}
+
+async fn refuse_marvin(change: stream::Change<Name>) -> std::result::Result<(), String> {
+    match change {
+        stream::Change::Insert(Some(user)) if user.name == "Marvin" => {
+            Err(String::from("Marvin is too depressed"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn failures(response: &aws_lambda_events::streams::DynamoDbEventResponse) -> Vec<&str> {
+    response
+        .batch_item_failures
+        .iter()
+        .map(|failure| failure.item_identifier.as_deref().unwrap())
+        .collect()
+}
+
+#[test]
+fn unordered_batch_processor() {
+    let report = futures::executor::block_on(
+        BatchProcessor::new()
+            .ordered(false)
+            .process(batch(), refuse_marvin),
+    );
+
+    assert_eq!(report.processed, 3);
+    assert!(!report.is_complete());
+    assert_eq!(failures(&report.response()), vec!["2", "3"]);
+    assert_eq!(report.failed[0].event_id, "2");
+    assert_eq!(report.failed[0].reason.to_string(), "missing field `name`");
+    assert!(matches!(report.failed[0].reason, RecordFailure::Decode(_)));
+    assert_eq!(
+        report.failed[1].reason.to_string(),
+        "Marvin is too depressed"
+    );
+    assert!(matches!(report.failed[1].reason, RecordFailure::Handler(_)));
+}
+
+#[test]
+fn batch_processor() {
+    let report = futures::executor::block_on(BatchProcessor::new().process(batch(), refuse_marvin));
+
+    // The first failure of stream a skips the rest of a, but not b
+    assert_eq!(report.processed, 2);
+    assert_eq!(failures(&report.response()), vec!["2", "3", "4"]);
+    assert!(matches!(report.failed[1].reason, RecordFailure::Skipped));
+    assert!(matches!(report.failed[2].reason, RecordFailure::Skipped));
+}
+
+#[test]
+fn complete_batch() {
+    let event = event(vec![(
+        "1",
+        "a",
+        json!({ "id": { "S": "1" }, "name": { "S": "Arthur" } }),
+    )]);
+    let report = futures::executor::block_on(BatchProcessor::new().process(event, refuse_marvin));
+    assert!(report.is_complete());
+    assert!(report.response().batch_item_failures.is_empty());
+}
+
+#[test]
+fn filter_patterns() {
+    use crate::expression::{AttributeType, Path};
+
+    let pattern = FilterPattern::new()
+        .event_name("INSERT")
+        .event_name("MODIFY")
+        .keys("id", FieldFilter::prefix("user#"))
+        .new_image("status", FieldFilter::any_of(vec!["active", "pending"]))
+        .new_image("status", FieldFilter::equals("new"))
+        .new_image("verified", FieldFilter::equals(true))
+        .new_image("age", FieldFilter::gt(17))
+        .new_image("score", FieldFilter::equals(4.5))
+        .new_image("deleted_at", FieldFilter::not_exists(AttributeType::N))
+        .new_image(
+            Path::attribute("address").key("country"),
+            FieldFilter::anything_but(vec!["FR", "DE"]),
+        )
+        .old_image("email", FieldFilter::exists(AttributeType::S))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        pattern,
+        json!({
+            "eventName": ["INSERT", "MODIFY"],
+            "dynamodb": {
+                "Keys": { "id": { "S": [{ "prefix": "user#" }] } },
+                "NewImage": {
+                    "status": { "S": ["active", "pending", "new"] },
+                    "verified": { "BOOL": [true] },
+                    "age": { "N": [{ "numeric": [">", 17] }] },
+                    "score": { "N": ["4.5"] },
+                    "deleted_at": { "N": [{ "exists": false }] },
+                    "address": { "M": { "country": { "S": [{ "anything-but": ["FR", "DE"] }] } } }
+                },
+                "OldImage": { "email": { "S": [{ "exists": true }] } }
+            }
+        })
+    );
+}
+
+#[test]
+fn filter_pattern_ranges() {
+    let pattern = FilterPattern::new()
+        .new_image("age", FieldFilter::between(18, 65))
+        .new_image("balance", FieldFilter::le(-0.5))
+        .to_pattern()
+        .unwrap();
+    let pattern: serde_json::Value = serde_json::from_str(&pattern).unwrap();
+    assert_eq!(
+        pattern,
+        json!({
+            "dynamodb": { "NewImage": {
+                "age": { "N": [{ "numeric": [">=", 18, "<=", 65] }] },
+                "balance": { "N": [{ "numeric": ["<=", -0.5] }] }
+            } }
+        })
+    );
+
+    assert_eq!(FilterPattern::new().to_pattern().unwrap(), "{}");
+}
+
+#[test]
+fn invalid_filter_patterns() {
+    use crate::expression::Path;
+
+    let error = |pattern: FilterPattern| pattern.build().unwrap_err().to_string();
+
+    assert_eq!(
+        error(FilterPattern::new().new_image("age", FieldFilter::gt("old"))),
+        "Numeric filters need numbers"
+    );
+    assert_eq!(
+        error(FilterPattern::new().new_image("tags", FieldFilter::equals(vec!["a"]))),
+        "Only strings, numbers, booleans and null can be filtered on"
+    );
+    assert_eq!(
+        error(FilterPattern::new().new_image("id", FieldFilter::any_of(Vec::<String>::new()))),
+        "Filters need at least one value"
+    );
+    assert_eq!(
+        error(
+            FilterPattern::new()
+                .new_image("id", FieldFilter::equals("arthur"))
+                .new_image("id", FieldFilter::equals(42))
+        ),
+        "Can't filter on id as both S and N"
+    );
+    assert_eq!(
+        error(
+            FilterPattern::new()
+                .new_image("address", FieldFilter::equals("Cottington"))
+                .new_image(
+                    Path::attribute("address").key("city"),
+                    FieldFilter::equals("Cottington")
+                )
+        ),
+        "Can't filter on address.city as both S and M"
+    );
+    assert_eq!(
+        error(FilterPattern::new().new_image(
+            Path::attribute("lines").index(0),
+            FieldFilter::equals("155 Country Lane")
+        )),
+        "Can't filter on lines[0]: filter patterns can't index into lists"
+    );
+}
@@ -225,6 +225,62 @@
 //! # }
 //! ```
 //!
+//! ## Expressions
+//!
+//! The [`expression`] module builds DynamoDB expressions, along with their attribute name and
+//! value placeholders, from the same [`Item`]s. For example, [`expression::diff`] turns an old
+//! and a new version of an item into the UpdateExpression that gets from one to the other.
+//!
+//! ## Client helpers
+//!
+//! With the `client` feature, `serde_dynamo::client` drives rusoto's `DynamoDb` client with typed
+//! data: `BatchWriter` and `BatchGetter` split writes and reads into BatchWriteItem and
+//! BatchGetItem calls, and retry what DynamoDB leaves unprocessed. `TransactWriter` and
+//! `TransactGetter` build transactions, checking DynamoDB's limits before the call is sent.
+//! `Paginate` turns Query and Scan inputs into streams of items that follow `LastEvaluatedKey`,
+//! and `Table` wraps a client in a typed repository of a single table's items.
+//!
+//! ## aws-sdk-dynamodb
+//!
+//! With the `aws-sdk` feature, `serde_dynamo::aws_sdk` adds methods to the fluent builders and
+//! outputs of `aws-sdk-dynamodb`, such as `.item_from(&user)?` and `.typed_item::<User>()?`, that
+//! convert between Rust types and the SDK's items. The `aws-sdk-convert` feature, which `aws-sdk`
+//! turns on, converts attribute values and items between rusoto and the SDK directly, with
+//! `From` and `TryFrom`, for code that uses both.
+//!
+//! ## DynamoDB Streams
+//!
+//! With the `lambda` feature, `serde_dynamo::lambda::StreamImages` deserializes the keys and
+//! images of the DynamoDB Streams records in `aws_lambda_events`' events, as in
+//! `let user: User = record.new_image()?`. It also turns them into `serde_dynamo::stream`'s
+//! typed change events: inserts, modifications and removals of a Rust type, with their metadata.
+//! A batch processor runs a typed handler over a whole event and reports the records that
+//! failed, and filter patterns for the event source mapping are built from typed values.
+//! The `streams` feature does the same for the records `rusoto_dynamodbstreams` reads with
+//! `GetRecords`, in `serde_dynamo::dynamodbstreams`, and the `kinesis` feature for tables that
+//! stream to Kinesis Data Streams instead, parsing each Kinesis record's data in
+//! `serde_dynamo::kinesis`.
+//!
+//! ## Pagination cursors
+//!
+//! With the `cursor` feature, `serde_dynamo::cursor::Cursor` turns a `LastEvaluatedKey` into an
+//! opaque, URL-safe string for clients of a paginated API, optionally signed so they can't tamper
+//! with it, and turns it back into an `ExclusiveStartKey`.
+//!
+//! ## Without an SDK
+//!
+//! With the `http` feature, `serde_dynamo::http` builds the JSON bodies of DynamoDB's HTTP API
+//! from Rust types and decodes responses back into them, for code that signs and sends its own
+//! requests.
+//!
+//! ## Testing
+//!
+//! With the `mock` feature, `serde_dynamo::mock::MockDynamoDb` implements rusoto's `DynamoDb`
+//! trait with in-memory tables, so code that talks to DynamoDB can be tested without a network.
+//! Tables can be seeded from DynamoDB JSON fixtures. The `mock-server` feature adds
+//! `serde_dynamo::mock::MockServer`, which serves the same tables over DynamoDB's HTTP protocol
+//! on a loopback port, for clients like `aws-sdk-dynamodb`.
+//!
 //! ## JSON
 //!
 //! DynamoDB's items share strong similarities with JSON, and it is very common to store JSON data
@@ -278,6 +334,18 @@
 //! # }
 //! ```
 //!
+//! Going the other way, from an `Item` to JSON, sets and binary data turn into arrays, and
+//! numbers are rounded. To keep an item exactly as it is in JSON, such as to store it somewhere
+//! that only takes JSON, the `json` feature's `serde_dynamo::json::item_to_json` and
+//! `json_to_item` tag what JSON has no type for, and restore it.
+//!
+//! ## Other formats
+//!
+//! Data can move between DynamoDB and any other serde format without a Rust type in between.
+//! [`transcode_to_item`] and [`transcode_to_attribute_value`] read from any serde `Deserializer`,
+//! such as YAML or CBOR, and [`Natural`] writes an `Item` to any serde `Serializer` the way it
+//! would be deserialized, rather than in DynamoDB's tagged form.
+//!
 //! [DynamoDB]: https://aws.amazon.com/dynamodb/
 //! [rusoto_dynamodb]: https://docs.rs/rusoto_dynamodb
 //! [get_item]: https://docs.rs/rusoto_dynamodb/0.45.0/rusoto_dynamodb/trait.DynamoDb.html#tymethod.get_item
@@ -293,13 +361,41 @@
 use rusoto_dynamodb::AttributeValue;
 use std::collections::HashMap;
 
+#[cfg(feature = "aws-sdk")]
+pub mod aws_sdk;
+#[cfg(feature = "aws-sdk-convert")]
+pub mod aws_sdk_convert;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "cursor")]
+pub mod cursor;
 mod de;
+#[cfg(feature = "streams")]
+pub mod dynamodbstreams;
 mod error;
+pub mod expression;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "kinesis")]
+pub mod kinesis;
+#[cfg(feature = "lambda")]
+pub mod lambda;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod number;
 mod ser;
+#[cfg(any(feature = "client", feature = "mock"))]
+mod size;
+#[cfg(any(feature = "kinesis", feature = "lambda", feature = "streams"))]
+pub mod stream;
+mod transcode;
 
 pub use de::{from_attribute_value, from_item, Deserializer};
 pub use error::{Error, Result};
 pub use ser::{to_attribute_value, to_item, Serializer};
+pub use transcode::{transcode_to_attribute_value, transcode_to_item, Natural};
 
 use error::ErrorImpl;
 
@@ -0,0 +1,1195 @@
+use super::table::{Index, Key, KeySchema, Table};
+use super::Failure;
+use crate::expression::{
+    self, parse_projection, placeholders, Context, ParsedCondition, ParsedUpdate, PathElement,
+    ReturnValues, UpdateOutcome,
+};
+use crate::size::item_size;
+use crate::Item;
+use rusoto_dynamodb::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+type Names = HashMap<String, String>;
+type Values = HashMap<String, AttributeValue>;
+
+/// Every table of a [`MockDynamoDb`](super::MockDynamoDb).
+#[derive(Debug, Default)]
+pub(crate) struct Database {
+    tables: BTreeMap<String, Table>,
+    /// How many requests of a batch are processed, before the rest are returned as unprocessed
+    pub(crate) batch_limit: Option<usize>,
+}
+
+/// What a single write does to the item at its key.
+enum Write {
+    Put(Item),
+    Delete,
+    Nothing,
+}
+
+/// Fail if a request uses one of DynamoDB's legacy parameters, which the mock doesn't support.
+fn reject_legacy(parameters: &[(&str, bool)]) -> Result<(), Failure> {
+    match parameters.iter().find(|(_, present)| *present) {
+        Some((name, _)) => Err(Failure::Validation(format!(
+            "MockDynamoDb does not support the legacy {} parameter; use expressions instead",
+            name
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Check the names and values maps against the expressions of a request, the way DynamoDB
+/// does: the maps may not be empty, and every entry has to be used by some expression.
+fn check_placeholders(
+    expressions: &[(&str, Option<&String>)],
+    names: Option<&Names>,
+    values: Option<&Values>,
+) -> Result<(), Failure> {
+    if names.is_some_and(|names| names.is_empty()) {
+        return Err(Failure::Validation(String::from(
+            "ExpressionAttributeNames must not be empty",
+        )));
+    }
+    if values.is_some_and(|values| values.is_empty()) {
+        return Err(Failure::Validation(String::from(
+            "ExpressionAttributeValues must not be empty",
+        )));
+    }
+
+    let mut used_names = HashSet::new();
+    let mut used_values = HashSet::new();
+    for (kind, expression) in expressions {
+        if let Some(expression) = expression {
+            let (names, values) = placeholders(kind, expression)?;
+            used_names.extend(names);
+            used_values.extend(values);
+        }
+    }
+
+    let unused = |kind: &str, keys: Vec<&String>| {
+        let mut keys: Vec<_> = keys.into_iter().map(String::as_str).collect();
+        keys.sort_unstable();
+        Failure::Validation(format!(
+            "Value provided in {} unused in expressions: keys: {{{}}}",
+            kind,
+            keys.join(", ")
+        ))
+    };
+    if let Some(names) = names {
+        let keys: Vec<_> = names.keys().filter(|k| !used_names.contains(*k)).collect();
+        if !keys.is_empty() {
+            return Err(unused("ExpressionAttributeNames", keys));
+        }
+    }
+    if let Some(values) = values {
+        let keys: Vec<_> = values
+            .keys()
+            .filter(|k| !used_values.contains(*k))
+            .collect();
+        if !keys.is_empty() {
+            return Err(unused("ExpressionAttributeValues", keys));
+        }
+    }
+    Ok(())
+}
+
+fn return_values(value: Option<&str>, allowed: &[ReturnValues]) -> Result<ReturnValues, Failure> {
+    let return_values = match value {
+        Some(value) => value.parse()?,
+        None => ReturnValues::None,
+    };
+    if allowed.contains(&return_values) {
+        Ok(return_values)
+    } else {
+        Err(Failure::Validation(String::from(
+            "Return values set to invalid value",
+        )))
+    }
+}
+
+/// Whether the item at `key` satisfies `condition`. A missing item is treated as empty.
+fn passes(
+    table: &Table,
+    key: &Key,
+    condition: Option<&String>,
+    names: &Names,
+    values: &Values,
+) -> Result<bool, Failure> {
+    match condition {
+        Some(condition) => {
+            let empty = Item::new();
+            let item = table.items.get(key).unwrap_or(&empty);
+            Ok(ParsedCondition::parse(condition)?.evaluate(item, names, values)?)
+        }
+        None => Ok(true),
+    }
+}
+
+/// Apply an UpdateExpression to the item at `key`, creating it if it doesn't exist.
+fn update(
+    table: &Table,
+    key: &Key,
+    key_item: &Item,
+    expression: Option<&String>,
+    names: &Names,
+    values: &Values,
+) -> Result<UpdateOutcome, Failure> {
+    let base = table
+        .items
+        .get(key)
+        .cloned()
+        .unwrap_or_else(|| key_item.clone());
+    let outcome = match expression {
+        Some(expression) => ParsedUpdate::parse(expression)?.apply(&base, names, values)?,
+        None => UpdateOutcome {
+            old: base.clone(),
+            new: base,
+            updated: Vec::new(),
+        },
+    };
+
+    for path in &outcome.updated {
+        if let Some(PathElement::Attribute(name)) = path.elements().first() {
+            if table.schema.contains(name) {
+                return Err(Failure::Validation(format!(
+                    "One or more parameter values were invalid: Cannot update attribute {}. This attribute is part of the key",
+                    name
+                )));
+            }
+        }
+    }
+    table.check_item(&outcome.new)?;
+    Ok(outcome)
+}
+
+/// The paths of a ProjectionExpression, resolved against the names map.
+fn projection(
+    expression: Option<&String>,
+    names: &Names,
+) -> Result<Option<Vec<expression::Path>>, Failure> {
+    let expression = match expression {
+        Some(expression) => expression,
+        None => return Ok(None),
+    };
+    let values = Values::new();
+    let context = Context {
+        kind: "ProjectionExpression",
+        names,
+        values: &values,
+    };
+    let mut paths = Vec::new();
+    for path in parse_projection(expression)? {
+        paths.push(context.path(&path)?);
+    }
+    Ok(Some(paths))
+}
+
+fn project(item: &Item, paths: &Option<Vec<expression::Path>>) -> Item {
+    match paths {
+        Some(paths) => expression::project(item, paths),
+        None => item.clone(),
+    }
+}
+
+/// A page of a query or scan.
+struct Page {
+    items: Option<Vec<Item>>,
+    count: i64,
+    scanned_count: i64,
+    last_evaluated_key: Option<Item>,
+    /// The size of every item read, matched or not
+    read_size: usize,
+}
+
+/// The options shared by Query and Scan.
+struct Read<'a> {
+    table: &'a Table,
+    index: Option<&'a Index>,
+    exclusive_start_key: Option<&'a Item>,
+    limit: Option<i64>,
+    select: Option<&'a String>,
+    filter: Option<ParsedCondition>,
+    projection: Option<Vec<expression::Path>>,
+    names: Names,
+    values: Values,
+}
+
+impl Read<'_> {
+    fn schema(&self) -> &KeySchema {
+        self.index
+            .map(|index| &index.schema)
+            .unwrap_or(&self.table.schema)
+    }
+
+    /// Where `ExclusiveStartKey` puts the read, as a key in the table or index and in the table.
+    fn start(&self) -> Result<Option<(Key, Key)>, Failure> {
+        match self.exclusive_start_key {
+            Some(start) => {
+                let invalid = || {
+                    Failure::Validation(String::from(
+                        "The provided starting key is invalid: The provided key element does not match the schema",
+                    ))
+                };
+                let table_key = self.table.schema.key_of(start)?.ok_or_else(invalid)?;
+                let key = self.schema().key_of(start)?.ok_or_else(invalid)?;
+                Ok(Some((key, table_key)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Evaluate `entries`, which follow `ExclusiveStartKey` in the order they should be
+    /// returned, into a page.
+    fn page(
+        &self,
+        entries: impl Iterator<Item = Result<((Key, Key), Item), Failure>>,
+    ) -> Result<Page, Failure> {
+        let limit = match self.limit {
+            Some(limit) if limit < 1 => {
+                return Err(Failure::Validation(format!(
+                    "1 validation error detected: Value '{}' at 'limit' failed to satisfy constraint: Member must have value greater than or equal to 1",
+                    limit
+                )))
+            }
+            Some(limit) => limit as usize,
+            None => usize::MAX,
+        };
+        let count_only = match self.select.map(String::as_str) {
+            None
+            | Some("ALL_ATTRIBUTES")
+            | Some("ALL_PROJECTED_ATTRIBUTES")
+            | Some("SPECIFIC_ATTRIBUTES") => false,
+            Some("COUNT") => true,
+            Some(other) => {
+                return Err(Failure::Validation(format!(
+                    "1 validation error detected: Value '{}' at 'select' failed to satisfy constraint: Member must satisfy enum value set: [SPECIFIC_ATTRIBUTES, COUNT, ALL_ATTRIBUTES, ALL_PROJECTED_ATTRIBUTES]",
+                    other
+                )))
+            }
+        };
+
+        let mut page = Page {
+            items: if count_only { None } else { Some(Vec::new()) },
+            count: 0,
+            scanned_count: 0,
+            last_evaluated_key: None,
+            read_size: 0,
+        };
+        for entry in entries.take(limit) {
+            let (_, item) = entry?;
+            page.scanned_count += 1;
+            page.read_size += item_size(&item);
+            if page.scanned_count as usize == limit {
+                // Like DynamoDB, hand out a key whenever the limit is reached, even if it turns
+                // out nothing is left
+                let mut key = self.table.schema.key_item(&item);
+                key.extend(self.schema().key_item(&item));
+                page.last_evaluated_key = Some(key);
+            }
+            if let Some(filter) = &self.filter {
+                if !filter.evaluate(&item, &self.names, &self.values)? {
+                    continue;
+                }
+            }
+            page.count += 1;
+            if let Some(items) = &mut page.items {
+                items.push(project(&item, &self.projection));
+            }
+        }
+        Ok(page)
+    }
+}
+
+/// What a Query or Scan needs to report the capacity it consumed.
+struct ReadCapacity {
+    table_name: String,
+    return_consumed_capacity: Option<String>,
+    consistent_read: bool,
+}
+
+impl ReadCapacity {
+    /// The capacity of reading `size` bytes, if the request asked for it: a unit for every 4 KB,
+    /// or half of one for eventually consistent reads.
+    fn consumed(&self, size: usize) -> Result<Option<ConsumedCapacity>, Failure> {
+        match self.return_consumed_capacity.as_deref() {
+            None | Some("NONE") => return Ok(None),
+            Some("INDEXES") | Some("TOTAL") => {}
+            Some(other) => {
+                return Err(Failure::Validation(format!(
+                    "1 validation error detected: Value '{}' at 'returnConsumedCapacity' failed to satisfy constraint: Member must satisfy enum value set: [INDEXES, TOTAL, NONE]",
+                    other
+                )))
+            }
+        }
+        let units = size.div_ceil(4096).max(1) as f64;
+        Ok(Some(ConsumedCapacity {
+            table_name: Some(self.table_name.clone()),
+            capacity_units: Some(if self.consistent_read {
+                units
+            } else {
+                units / 2.0
+            }),
+            ..ConsumedCapacity::default()
+        }))
+    }
+}
+
+/// Check that a KeyConditionExpression only tests the key the way DynamoDB allows: an equality
+/// test on the partition key, and optionally one test of the sort key.
+fn check_key_condition(
+    condition: &expression::parser::Condition,
+    schema: &KeySchema,
+    context: &Context<'_>,
+) -> Result<(), Failure> {
+    use expression::parser::{Condition, Operand};
+
+    let unsupported = || Failure::Validation(String::from("Query key condition not supported"));
+    let missed = || {
+        Failure::Validation(format!(
+            "Query condition missed key schema element: {}",
+            schema.partition.name
+        ))
+    };
+
+    let mut conditions = Vec::new();
+    let mut stack = vec![condition];
+    while let Some(condition) = stack.pop() {
+        match condition {
+            Condition::And(conditions) => stack.extend(conditions.iter().rev()),
+            Condition::Or(..) => {
+                return Err(Failure::Validation(String::from(
+                    "Invalid operator used in KeyConditionExpression: OR",
+                )))
+            }
+            Condition::Not(..) => {
+                return Err(Failure::Validation(String::from(
+                    "Invalid operator used in KeyConditionExpression: NOT",
+                )))
+            }
+            other => conditions.push(other),
+        }
+    }
+
+    let key_name = |operand: &Operand| -> Result<Option<String>, Failure> {
+        match operand {
+            Operand::Path(path) => {
+                let path = context.path(path)?;
+                match path.elements() {
+                    [PathElement::Attribute(name)] => Ok(Some(name.clone())),
+                    _ => Err(unsupported()),
+                }
+            }
+            Operand::Value(_) => Ok(None),
+            Operand::Size(_) => Err(unsupported()),
+        }
+    };
+
+    let mut partition_key_tested = false;
+    let mut tested = HashSet::new();
+    for condition in conditions {
+        let (name, equality) = match condition {
+            Condition::Compare(left, comparator, right) => {
+                match (key_name(left)?, key_name(right)?) {
+                    (Some(name), None) | (None, Some(name)) => {
+                        (name, *comparator == expression::Comparator::Eq)
+                    }
+                    _ => return Err(unsupported()),
+                }
+            }
+            Condition::Between(operand, low, high) => {
+                match (key_name(operand)?, key_name(low)?, key_name(high)?) {
+                    (Some(name), None, None) => (name, false),
+                    _ => return Err(unsupported()),
+                }
+            }
+            Condition::Function(function, args) if function == "begins_with" && args.len() == 2 => {
+                match (key_name(&args[0])?, key_name(&args[1])?) {
+                    (Some(name), None) => (name, false),
+                    _ => return Err(unsupported()),
+                }
+            }
+            _ => return Err(unsupported()),
+        };
+
+        if !schema.contains(&name) {
+            return Err(missed());
+        }
+        if !tested.insert(name.clone()) {
+            return Err(unsupported());
+        }
+        if name == schema.partition.name {
+            if !equality {
+                return Err(unsupported());
+            }
+            partition_key_tested = true;
+        }
+    }
+
+    if partition_key_tested {
+        Ok(())
+    } else {
+        Err(missed())
+    }
+}
+
+impl Database {
+    fn table(&self, name: &str) -> Result<&Table, Failure> {
+        self.tables
+            .get(name)
+            .ok_or_else(|| Failure::ResourceNotFound(String::from("Requested resource not found")))
+    }
+
+    fn table_mut(&mut self, name: &str) -> Result<&mut Table, Failure> {
+        self.tables
+            .get_mut(name)
+            .ok_or_else(|| Failure::ResourceNotFound(String::from("Requested resource not found")))
+    }
+
+    /// Every item of a table, in key order.
+    pub(crate) fn items(&self, table: &str) -> Option<Vec<Item>> {
+        self.tables
+            .get(table)
+            .map(|table| table.items.values().cloned().collect())
+    }
+
+    /// Store items without any checks beyond the table's key.
+    pub(crate) fn load(&mut self, table: &str, items: Vec<Item>) -> Result<(), Failure> {
+        let table = self.table_mut(table)?;
+        for item in items {
+            let key = table.check_item(&item)?;
+            table.insert(key, item);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn create_table(
+        &mut self,
+        input: CreateTableInput,
+    ) -> Result<CreateTableOutput, Failure> {
+        if self.tables.contains_key(&input.table_name) {
+            return Err(Failure::ResourceInUse(format!(
+                "Table already exists: {}",
+                input.table_name
+            )));
+        }
+        let table = Table::new(&input)?;
+        let description = table.description();
+        self.tables.insert(input.table_name, table);
+        Ok(CreateTableOutput {
+            table_description: Some(description),
+        })
+    }
+
+    pub(crate) fn delete_table(
+        &mut self,
+        input: DeleteTableInput,
+    ) -> Result<DeleteTableOutput, Failure> {
+        let table = self.tables.remove(&input.table_name).ok_or_else(|| {
+            Failure::ResourceNotFound(format!(
+                "Requested resource not found: Table: {} not found",
+                input.table_name
+            ))
+        })?;
+        Ok(DeleteTableOutput {
+            table_description: Some(TableDescription {
+                table_status: Some(String::from("DELETING")),
+                ..table.description()
+            }),
+        })
+    }
+
+    pub(crate) fn describe_table(
+        &self,
+        input: DescribeTableInput,
+    ) -> Result<DescribeTableOutput, Failure> {
+        let table = self.tables.get(&input.table_name).ok_or_else(|| {
+            Failure::ResourceNotFound(format!(
+                "Requested resource not found: Table: {} not found",
+                input.table_name
+            ))
+        })?;
+        Ok(DescribeTableOutput {
+            table: Some(table.description()),
+        })
+    }
+
+    pub(crate) fn list_tables(&self, input: ListTablesInput) -> Result<ListTablesOutput, Failure> {
+        let limit = input.limit.unwrap_or(100).clamp(1, 100) as usize;
+        let mut names = self.tables.keys().filter(|name| {
+            input
+                .exclusive_start_table_name
+                .as_ref()
+                .is_none_or(|start| *name > start)
+        });
+        let table_names: Vec<String> = names.by_ref().take(limit).cloned().collect();
+        let last_evaluated_table_name = match names.next() {
+            Some(_) => table_names.last().cloned(),
+            None => None,
+        };
+        Ok(ListTablesOutput {
+            last_evaluated_table_name,
+            table_names: Some(table_names),
+        })
+    }
+
+    pub(crate) fn get_item(&self, input: GetItemInput) -> Result<GetItemOutput, Failure> {
+        reject_legacy(&[("AttributesToGet", input.attributes_to_get.is_some())])?;
+        check_placeholders(
+            &[("ProjectionExpression", input.projection_expression.as_ref())],
+            input.expression_attribute_names.as_ref(),
+            None,
+        )?;
+        let names = input.expression_attribute_names.unwrap_or_default();
+        let projection = projection(input.projection_expression.as_ref(), &names)?;
+
+        let table = self.table(&input.table_name)?;
+        let key = table.schema.request_key(&input.key)?;
+        Ok(GetItemOutput {
+            item: table.items.get(&key).map(|item| project(item, &projection)),
+            ..GetItemOutput::default()
+        })
+    }
+
+    pub(crate) fn put_item(&mut self, input: PutItemInput) -> Result<PutItemOutput, Failure> {
+        reject_legacy(&[
+            ("Expected", input.expected.is_some()),
+            ("ConditionalOperator", input.conditional_operator.is_some()),
+        ])?;
+        check_placeholders(
+            &[("ConditionExpression", input.condition_expression.as_ref())],
+            input.expression_attribute_names.as_ref(),
+            input.expression_attribute_values.as_ref(),
+        )?;
+        let return_values = return_values(
+            input.return_values.as_deref(),
+            &[ReturnValues::None, ReturnValues::AllOld],
+        )?;
+        let names = input.expression_attribute_names.unwrap_or_default();
+        let values = input.expression_attribute_values.unwrap_or_default();
+
+        let table = self.table_mut(&input.table_name)?;
+        let key = table.check_item(&input.item)?;
+        if !passes(
+            table,
+            &key,
+            input.condition_expression.as_ref(),
+            &names,
+            &values,
+        )? {
+            return Err(Failure::conditional_check_failed());
+        }
+        let old = table.insert(key, input.item);
+        Ok(PutItemOutput {
+            attributes: old.filter(|_| return_values == ReturnValues::AllOld),
+            ..PutItemOutput::default()
+        })
+    }
+
+    pub(crate) fn delete_item(
+        &mut self,
+        input: DeleteItemInput,
+    ) -> Result<DeleteItemOutput, Failure> {
+        reject_legacy(&[
+            ("Expected", input.expected.is_some()),
+            ("ConditionalOperator", input.conditional_operator.is_some()),
+        ])?;
+        check_placeholders(
+            &[("ConditionExpression", input.condition_expression.as_ref())],
+            input.expression_attribute_names.as_ref(),
+            input.expression_attribute_values.as_ref(),
+        )?;
+        let return_values = return_values(
+            input.return_values.as_deref(),
+            &[ReturnValues::None, ReturnValues::AllOld],
+        )?;
+        let names = input.expression_attribute_names.unwrap_or_default();
+        let values = input.expression_attribute_values.unwrap_or_default();
+
+        let table = self.table_mut(&input.table_name)?;
+        let key = table.schema.request_key(&input.key)?;
+        if !passes(
+            table,
+            &key,
+            input.condition_expression.as_ref(),
+            &names,
+            &values,
+        )? {
+            return Err(Failure::conditional_check_failed());
+        }
+        let old = table.remove(&key);
+        Ok(DeleteItemOutput {
+            attributes: old.filter(|_| return_values == ReturnValues::AllOld),
+            ..DeleteItemOutput::default()
+        })
+    }
+
+    pub(crate) fn update_item(
+        &mut self,
+        input: UpdateItemInput,
+    ) -> Result<UpdateItemOutput, Failure> {
+        reject_legacy(&[
+            ("AttributeUpdates", input.attribute_updates.is_some()),
+            ("Expected", input.expected.is_some()),
+            ("ConditionalOperator", input.conditional_operator.is_some()),
+        ])?;
+        check_placeholders(
+            &[
+                ("ConditionExpression", input.condition_expression.as_ref()),
+                ("UpdateExpression", input.update_expression.as_ref()),
+            ],
+            input.expression_attribute_names.as_ref(),
+            input.expression_attribute_values.as_ref(),
+        )?;
+        let return_values = return_values(
+            input.return_values.as_deref(),
+            &[
+                ReturnValues::None,
+                ReturnValues::AllOld,
+                ReturnValues::UpdatedOld,
+                ReturnValues::AllNew,
+                ReturnValues::UpdatedNew,
+            ],
+        )?;
+        let names = input.expression_attribute_names.unwrap_or_default();
+        let values = input.expression_attribute_values.unwrap_or_default();
+
+        let table = self.table_mut(&input.table_name)?;
+        let key = table.schema.request_key(&input.key)?;
+        if !passes(
+            table,
+            &key,
+            input.condition_expression.as_ref(),
+            &names,
+            &values,
+        )? {
+            return Err(Failure::conditional_check_failed());
+        }
+        let outcome = update(
+            table,
+            &key,
+            &input.key,
+            input.update_expression.as_ref(),
+            &names,
+            &values,
+        )?;
+
+        let existed = table.items.contains_key(&key);
+        let attributes = match return_values {
+            ReturnValues::AllOld | ReturnValues::UpdatedOld if !existed => None,
+            return_values => outcome.attributes(return_values),
+        };
+        table.insert(key, outcome.new);
+        Ok(UpdateItemOutput {
+            attributes,
+            ..UpdateItemOutput::default()
+        })
+    }
+
+    pub(crate) fn query(&self, input: QueryInput) -> Result<QueryOutput, Failure> {
+        let capacity = ReadCapacity {
+            table_name: input.table_name.clone(),
+            return_consumed_capacity: input.return_consumed_capacity.clone(),
+            consistent_read: input.consistent_read.unwrap_or(false),
+        };
+        reject_legacy(&[
+            ("KeyConditions", input.key_conditions.is_some()),
+            ("QueryFilter", input.query_filter.is_some()),
+            ("AttributesToGet", input.attributes_to_get.is_some()),
+            ("ConditionalOperator", input.conditional_operator.is_some()),
+        ])?;
+        check_placeholders(
+            &[
+                (
+                    "KeyConditionExpression",
+                    input.key_condition_expression.as_ref(),
+                ),
+                ("FilterExpression", input.filter_expression.as_ref()),
+                ("ProjectionExpression", input.projection_expression.as_ref()),
+            ],
+            input.expression_attribute_names.as_ref(),
+            input.expression_attribute_values.as_ref(),
+        )?;
+        let key_condition = input.key_condition_expression.as_ref().ok_or_else(|| {
+            Failure::Validation(String::from(
+                "Either the KeyConditions or KeyConditionExpression parameter must be specified in the request.",
+            ))
+        })?;
+        let names = input.expression_attribute_names.unwrap_or_default();
+        let values = input.expression_attribute_values.unwrap_or_default();
+
+        let table = self.table(&input.table_name)?;
+        let index = match &input.index_name {
+            Some(name) => Some(table.index(name)?),
+            None => None,
+        };
+        let read = Read {
+            table,
+            index,
+            exclusive_start_key: input.exclusive_start_key.as_ref(),
+            limit: input.limit,
+            select: input.select.as_ref(),
+            filter: input
+                .filter_expression
+                .as_ref()
+                .map(|filter| ParsedCondition::parse_as("FilterExpression", filter))
+                .transpose()?,
+            projection: projection(input.projection_expression.as_ref(), &names)?,
+            names,
+            values,
+        };
+
+        let key_condition = ParsedCondition::parse_as("KeyConditionExpression", key_condition)?;
+        let context = Context {
+            kind: "KeyConditionExpression",
+            names: &read.names,
+            values: &read.values,
+        };
+        check_key_condition(key_condition.syntax(), read.schema(), &context)?;
+
+        // The items a key condition matches are next to each other in key order, so the query
+        // ends at the first item after them
+        let start = read.start()?;
+        let entries = table
+            .view(
+                index,
+                start.as_ref(),
+                input.scan_index_forward.unwrap_or(true),
+            )
+            .map(|entry| {
+                let matches = key_condition.evaluate(&entry.1, &read.names, &read.values)?;
+                Ok((matches, entry))
+            })
+            .skip_while(|matched| matches!(matched, Ok((false, _))))
+            .take_while(|matched| !matches!(matched, Ok((false, _))))
+            .map(|matched| matched.map(|(_, entry)| entry));
+
+        let page = read.page(entries)?;
+        Ok(QueryOutput {
+            count: Some(page.count),
+            items: page.items,
+            last_evaluated_key: page.last_evaluated_key,
+            scanned_count: Some(page.scanned_count),
+            consumed_capacity: capacity.consumed(page.read_size)?,
+        })
+    }
+
+    pub(crate) fn scan(&self, input: ScanInput) -> Result<ScanOutput, Failure> {
+        let capacity = ReadCapacity {
+            table_name: input.table_name.clone(),
+            return_consumed_capacity: input.return_consumed_capacity.clone(),
+            consistent_read: input.consistent_read.unwrap_or(false),
+        };
+        reject_legacy(&[
+            ("ScanFilter", input.scan_filter.is_some()),
+            ("AttributesToGet", input.attributes_to_get.is_some()),
+            ("ConditionalOperator", input.conditional_operator.is_some()),
+        ])?;
+        check_placeholders(
+            &[
+                ("FilterExpression", input.filter_expression.as_ref()),
+                ("ProjectionExpression", input.projection_expression.as_ref()),
+            ],
+            input.expression_attribute_names.as_ref(),
+            input.expression_attribute_values.as_ref(),
+        )?;
+        let segments = match (input.segment, input.total_segments) {
+            (Some(segment), Some(total)) if (0..total).contains(&segment) => {
+                Some((segment as u64, total as u64))
+            }
+            (None, None) => None,
+            (Some(_), None) => {
+                return Err(Failure::Validation(String::from(
+                    "The TotalSegments parameter is required but was not present in the request when Segment parameter is present",
+                )))
+            }
+            (None, Some(_)) => {
+                return Err(Failure::Validation(String::from(
+                    "The Segment parameter is required but was not present in the request when parameter TotalSegments is present",
+                )))
+            }
+            (Some(_), Some(_)) => {
+                return Err(Failure::Validation(String::from(
+                    "The Segment parameter is zero-based and must be less than parameter TotalSegments",
+                )))
+            }
+        };
+        let names = input.expression_attribute_names.unwrap_or_default();
+        let values = input.expression_attribute_values.unwrap_or_default();
+
+        let table = self.table(&input.table_name)?;
+        let index = match &input.index_name {
+            Some(name) => Some(table.index(name)?),
+            None => None,
+        };
+        let read = Read {
+            table,
+            index,
+            exclusive_start_key: input.exclusive_start_key.as_ref(),
+            limit: input.limit,
+            select: input.select.as_ref(),
+            filter: input
+                .filter_expression
+                .as_ref()
+                .map(|filter| ParsedCondition::parse_as("FilterExpression", filter))
+                .transpose()?,
+            projection: projection(input.projection_expression.as_ref(), &names)?,
+            names,
+            values,
+        };
+
+        let start = read.start()?;
+        let entries = table
+            .view(index, start.as_ref(), true)
+            .filter(|((key, _), _)| match segments {
+                // Split items between segments by partition key, as DynamoDB does
+                Some((segment, total)) => {
+                    let mut hasher = DefaultHasher::new();
+                    key.0.hash(&mut hasher);
+                    hasher.finish() % total == segment
+                }
+                None => true,
+            })
+            .map(Ok);
+
+        let page = read.page(entries)?;
+        Ok(ScanOutput {
+            count: Some(page.count),
+            items: page.items,
+            last_evaluated_key: page.last_evaluated_key,
+            scanned_count: Some(page.scanned_count),
+            consumed_capacity: capacity.consumed(page.read_size)?,
+        })
+    }
+
+    pub(crate) fn batch_get_item(
+        &self,
+        input: BatchGetItemInput,
+    ) -> Result<BatchGetItemOutput, Failure> {
+        if input.request_items.is_empty() {
+            return Err(Failure::Validation(String::from(
+                "1 validation error detected: Value at 'requestItems' failed to satisfy constraint: Member must have length greater than or equal to 1",
+            )));
+        }
+        if input.request_items.values().any(|r| r.keys.is_empty()) {
+            return Err(Failure::Validation(String::from(
+                "1 validation error detected: Value at 'requestItems' failed to satisfy constraint: Map value must satisfy constraint: [Member must satisfy constraint: [Member must have length less than or equal to 100, Member must have length greater than or equal to 1]]",
+            )));
+        }
+        let total: usize = input.request_items.values().map(|r| r.keys.len()).sum();
+        if total > 100 {
+            return Err(Failure::Validation(String::from(
+                "Too many items requested for the BatchGetItem call",
+            )));
+        }
+
+        let mut limit = self.batch_limit.unwrap_or(total);
+        let mut responses = HashMap::new();
+        let mut unprocessed = HashMap::new();
+        for (table_name, mut request) in input.request_items {
+            reject_legacy(&[("AttributesToGet", request.attributes_to_get.is_some())])?;
+            check_placeholders(
+                &[(
+                    "ProjectionExpression",
+                    request.projection_expression.as_ref(),
+                )],
+                request.expression_attribute_names.as_ref(),
+                None,
+            )?;
+            let names = request
+                .expression_attribute_names
+                .clone()
+                .unwrap_or_default();
+            let projection = projection(request.projection_expression.as_ref(), &names)?;
+
+            let table = self.table(&table_name)?;
+            let mut seen = HashSet::new();
+            let mut keys = Vec::with_capacity(request.keys.len());
+            for key in &request.keys {
+                let key = table.schema.request_key(key)?;
+                if !seen.insert(key.clone()) {
+                    return Err(Failure::duplicate_keys());
+                }
+                keys.push(key);
+            }
+
+            let processed = limit.min(keys.len());
+            limit -= processed;
+            let items = keys[..processed]
+                .iter()
+                .filter_map(|key| table.items.get(key))
+                .map(|item| project(item, &projection))
+                .collect();
+            responses.insert(table_name.clone(), items);
+            if processed < keys.len() {
+                request.keys.drain(..processed);
+                unprocessed.insert(table_name, request);
+            }
+        }
+
+        Ok(BatchGetItemOutput {
+            responses: Some(responses),
+            unprocessed_keys: Some(unprocessed),
+            ..BatchGetItemOutput::default()
+        })
+    }
+
+    pub(crate) fn batch_write_item(
+        &mut self,
+        input: BatchWriteItemInput,
+    ) -> Result<BatchWriteItemOutput, Failure> {
+        let total: usize = input.request_items.values().map(Vec::len).sum();
+        if total == 0 || total > 25 {
+            return Err(Failure::Validation(String::from(
+                "1 validation error detected: Value at 'requestItems' failed to satisfy constraint: Map value must satisfy constraint: [Member must have length less than or equal to 25, Member must have length greater than or equal to 1]",
+            )));
+        }
+
+        // Check every request before writing anything
+        let mut writes = Vec::with_capacity(total);
+        for (table_name, requests) in &input.request_items {
+            let table = self.table(table_name)?;
+            let mut seen = HashSet::new();
+            for request in requests {
+                let (key, write) = match (&request.put_request, &request.delete_request) {
+                    (Some(put), None) => (table.check_item(&put.item)?, Write::Put(put.item.clone())),
+                    (None, Some(delete)) => (table.schema.request_key(&delete.key)?, Write::Delete),
+                    _ => {
+                        return Err(Failure::Validation(String::from(
+                            "Supplied AttributeValue has more than one datatypes set, must contain exactly one of the supported datatypes",
+                        )))
+                    }
+                };
+                if !seen.insert(key.clone()) {
+                    return Err(Failure::duplicate_keys());
+                }
+                writes.push(((table_name.clone(), key, write), request));
+            }
+        }
+
+        let limit = self.batch_limit.unwrap_or(total);
+        let mut unprocessed: HashMap<String, Vec<WriteRequest>> = HashMap::new();
+        for ((table_name, _, _), request) in &writes[limit.min(total)..] {
+            unprocessed
+                .entry(table_name.clone())
+                .or_default()
+                .push((*request).clone());
+        }
+        writes.truncate(limit);
+        self.write_all(writes.into_iter().map(|(write, _)| write).collect());
+        Ok(BatchWriteItemOutput {
+            unprocessed_items: Some(unprocessed),
+            ..BatchWriteItemOutput::default()
+        })
+    }
+
+    pub(crate) fn transact_write_items(
+        &mut self,
+        input: TransactWriteItemsInput,
+    ) -> Result<TransactWriteItemsOutput, Failure> {
+        if input.transact_items.is_empty() || input.transact_items.len() > 100 {
+            return Err(Failure::Validation(String::from(
+                "1 validation error detected: Value at 'transactItems' failed to satisfy constraint: Member must have length less than or equal to 100, Member must have length greater than or equal to 1",
+            )));
+        }
+
+        let mut writes = Vec::with_capacity(input.transact_items.len());
+        let mut reasons = Vec::with_capacity(input.transact_items.len());
+        let mut seen = HashSet::new();
+        for action in input.transact_items {
+            let (table_name, key, write, passed) = match action {
+                TransactWriteItem {
+                    condition_check: Some(check),
+                    delete: None,
+                    put: None,
+                    update: None,
+                } => {
+                    let (names, values) = transact_placeholders(
+                        &[("ConditionExpression", Some(&check.condition_expression))],
+                        check.expression_attribute_names,
+                        check.expression_attribute_values,
+                    )?;
+                    let table = self.table(&check.table_name)?;
+                    let key = table.schema.request_key(&check.key)?;
+                    let passed = passes(
+                        table,
+                        &key,
+                        Some(&check.condition_expression),
+                        &names,
+                        &values,
+                    )?;
+                    (check.table_name, key, Write::Nothing, passed)
+                }
+                TransactWriteItem {
+                    condition_check: None,
+                    delete: None,
+                    put: Some(put),
+                    update: None,
+                } => {
+                    let (names, values) = transact_placeholders(
+                        &[("ConditionExpression", put.condition_expression.as_ref())],
+                        put.expression_attribute_names,
+                        put.expression_attribute_values,
+                    )?;
+                    let table = self.table(&put.table_name)?;
+                    let key = table.check_item(&put.item)?;
+                    let passed = passes(
+                        table,
+                        &key,
+                        put.condition_expression.as_ref(),
+                        &names,
+                        &values,
+                    )?;
+                    (put.table_name, key, Write::Put(put.item), passed)
+                }
+                TransactWriteItem {
+                    condition_check: None,
+                    delete: Some(delete),
+                    put: None,
+                    update: None,
+                } => {
+                    let (names, values) = transact_placeholders(
+                        &[("ConditionExpression", delete.condition_expression.as_ref())],
+                        delete.expression_attribute_names,
+                        delete.expression_attribute_values,
+                    )?;
+                    let table = self.table(&delete.table_name)?;
+                    let key = table.schema.request_key(&delete.key)?;
+                    let passed = passes(
+                        table,
+                        &key,
+                        delete.condition_expression.as_ref(),
+                        &names,
+                        &values,
+                    )?;
+                    (delete.table_name, key, Write::Delete, passed)
+                }
+                TransactWriteItem {
+                    condition_check: None,
+                    delete: None,
+                    put: None,
+                    update: Some(update_action),
+                } => {
+                    let (names, values) = transact_placeholders(
+                        &[
+                            (
+                                "ConditionExpression",
+                                update_action.condition_expression.as_ref(),
+                            ),
+                            ("UpdateExpression", Some(&update_action.update_expression)),
+                        ],
+                        update_action.expression_attribute_names,
+                        update_action.expression_attribute_values,
+                    )?;
+                    let table = self.table(&update_action.table_name)?;
+                    let key = table.schema.request_key(&update_action.key)?;
+                    let passed = passes(
+                        table,
+                        &key,
+                        update_action.condition_expression.as_ref(),
+                        &names,
+                        &values,
+                    )?;
+                    let outcome = update(
+                        table,
+                        &key,
+                        &update_action.key,
+                        Some(&update_action.update_expression),
+                        &names,
+                        &values,
+                    )?;
+                    (
+                        update_action.table_name,
+                        key,
+                        Write::Put(outcome.new),
+                        passed,
+                    )
+                }
+                _ => {
+                    return Err(Failure::Validation(String::from(
+                        "TransactItems can only contain one of Check, Put, Update or Delete",
+                    )))
+                }
+            };
+
+            if !seen.insert((table_name.clone(), key.clone())) {
+                return Err(Failure::Validation(String::from(
+                    "Transaction request cannot include multiple operations on one item",
+                )));
+            }
+            reasons.push(if passed {
+                "None"
+            } else {
+                "ConditionalCheckFailed"
+            });
+            writes.push((table_name, key, write));
+        }
+
+        if reasons.iter().any(|reason| *reason != "None") {
+            return Err(Failure::TransactionCanceled(reasons));
+        }
+        self.write_all(writes);
+        Ok(TransactWriteItemsOutput::default())
+    }
+
+    pub(crate) fn transact_get_items(
+        &self,
+        input: TransactGetItemsInput,
+    ) -> Result<TransactGetItemsOutput, Failure> {
+        if input.transact_items.is_empty() || input.transact_items.len() > 100 {
+            return Err(Failure::Validation(String::from(
+                "1 validation error detected: Value at 'transactItems' failed to satisfy constraint: Member must have length less than or equal to 100, Member must have length greater than or equal to 1",
+            )));
+        }
+
+        let mut responses = Vec::with_capacity(input.transact_items.len());
+        for TransactGetItem { get } in input.transact_items {
+            check_placeholders(
+                &[("ProjectionExpression", get.projection_expression.as_ref())],
+                get.expression_attribute_names.as_ref(),
+                None,
+            )?;
+            let names = get.expression_attribute_names.unwrap_or_default();
+            let projection = projection(get.projection_expression.as_ref(), &names)?;
+            let table = self.table(&get.table_name)?;
+            let key = table.schema.request_key(&get.key)?;
+            responses.push(ItemResponse {
+                item: table.items.get(&key).map(|item| project(item, &projection)),
+            });
+        }
+        Ok(TransactGetItemsOutput {
+            responses: Some(responses),
+            ..TransactGetItemsOutput::default()
+        })
+    }
+
+    /// Perform writes that have already been checked.
+    fn write_all(&mut self, writes: Vec<(String, Key, Write)>) {
+        for (table_name, key, write) in writes {
+            let table = self
+                .tables
+                .get_mut(&table_name)
+                .expect("tables are checked before writing");
+            match write {
+                Write::Put(item) => {
+                    table.insert(key, item);
+                }
+                Write::Delete => {
+                    table.remove(&key);
+                }
+                Write::Nothing => {}
+            }
+        }
+    }
+}
+
+/// Check the placeholders of one action of a transaction, and default its maps.
+fn transact_placeholders(
+    expressions: &[(&str, Option<&String>)],
+    names: Option<Names>,
+    values: Option<Values>,
+) -> Result<(Names, Values), Failure> {
+    check_placeholders(expressions, names.as_ref(), values.as_ref())?;
+    Ok((names.unwrap_or_default(), values.unwrap_or_default()))
+}
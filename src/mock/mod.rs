@@ -0,0 +1,424 @@
+//! An in-memory DynamoDB for tests.
+//!
+//! [`MockDynamoDb`] implements rusoto's [`DynamoDb`] trait on top of in-memory tables, so code
+//! written against `&dyn DynamoDb` or a generic `D: DynamoDb` can be tested without a network.
+//!
+//! ```
+//! use maplit::hashmap;
+//! use rusoto_dynamodb::{
+//!     AttributeDefinition, CreateTableInput, DynamoDb, GetItemInput, KeySchemaElement,
+//!     PutItemInput,
+//! };
+//! use serde_dynamo::mock::MockDynamoDb;
+//! use serde_dynamo::{from_item, to_attribute_value, to_item};
+//! # use serde_derive::{Deserialize, Serialize};
+//! #
+//! # #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! # struct User { id: String, name: String }
+//! #
+//! # async fn test() -> Result<(), Box<dyn std::error::Error>> {
+//!
+//! let client = MockDynamoDb::new();
+//! client
+//!     .create_table(CreateTableInput {
+//!         table_name: "users".to_string(),
+//!         key_schema: vec![KeySchemaElement {
+//!             attribute_name: "id".to_string(),
+//!             key_type: "HASH".to_string(),
+//!         }],
+//!         attribute_definitions: vec![AttributeDefinition {
+//!             attribute_name: "id".to_string(),
+//!             attribute_type: "S".to_string(),
+//!         }],
+//!         ..CreateTableInput::default()
+//!     })
+//!     .await?;
+//!
+//! let user = User { id: "fSsgVtal8TpP".to_string(), name: "Arthur Dent".to_string() };
+//! client
+//!     .put_item(PutItemInput {
+//!         table_name: "users".to_string(),
+//!         item: to_item(&user)?,
+//!         condition_expression: Some("attribute_not_exists(id)".to_string()),
+//!         ..PutItemInput::default()
+//!     })
+//!     .await?;
+//!
+//! let output = client
+//!     .get_item(GetItemInput {
+//!         table_name: "users".to_string(),
+//!         key: hashmap! { "id".to_string() => to_attribute_value(&user.id)? },
+//!         ..GetItemInput::default()
+//!     })
+//!     .await?;
+//! assert_eq!(from_item::<User>(output.item.unwrap())?, user);
+//! # Ok(())
+//! # }
+//! # futures::executor::block_on(test()).unwrap();
+//! ```
+//!
+//! Supported operations are GetItem, PutItem, DeleteItem, UpdateItem, Query, Scan,
+//! BatchGetItem, BatchWriteItem, TransactGetItems, TransactWriteItems, CreateTable,
+//! DeleteTable, DescribeTable, and ListTables. Expressions are evaluated with the
+//! [`expression`](crate::expression) module, and global and local secondary indexes are kept up
+//! to date on every write. Requests are checked for the mistakes DynamoDB rejects, such as
+//! unused placeholders or keys that don't match the schema, and fail with DynamoDB's messages.
+//!
+//! Some differences remain:
+//!
+//! * The legacy parameters (`Expected`, `AttributeUpdates`, `KeyConditions`, ...) are rejected.
+//! * Scans return items in key order, and pages aren't limited to 1 MB.
+//! * Capacity, item collection metrics, and item size limits aren't tracked.
+//! * Every other operation fails with a validation error.
+//!
+//! With the `mock-server` feature, `MockServer` serves the same tables over HTTP, for code that
+//! uses `aws-sdk-dynamodb` or DynamoDB's HTTP API instead of rusoto's trait.
+
+use crate::error::ErrorImpl;
+use crate::{Item, Result};
+use async_trait::async_trait;
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::*;
+use std::fmt::{self, Display};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+mod database;
+#[cfg(feature = "mock-server")]
+mod server;
+mod table;
+
+#[cfg(test)]
+mod tests;
+
+use database::Database;
+#[cfg(feature = "mock-server")]
+pub use server::MockServer;
+
+/// An in-memory implementation of rusoto's [`DynamoDb`] trait.
+///
+/// See the [module documentation](self) for what is supported. Clones share the same tables.
+#[derive(Debug, Clone, Default)]
+pub struct MockDynamoDb {
+    database: Arc<Mutex<Database>>,
+}
+
+impl MockDynamoDb {
+    /// A database without any tables.
+    pub fn new() -> Self {
+        MockDynamoDb::default()
+    }
+
+    fn database(&self) -> MutexGuard<'_, Database> {
+        lock(&self.database)
+    }
+
+    /// Create a table, the same way `create_table` does, without needing an executor.
+    pub fn add_table(&self, input: CreateTableInput) -> Result<()> {
+        self.database()
+            .create_table(input)
+            .map_err(Failure::into_error)?;
+        Ok(())
+    }
+
+    /// Store the items of a fixture in a table.
+    ///
+    /// The fixture is DynamoDB JSON, either a list of items or an object with an `Items` list
+    /// (the format of `aws dynamodb scan` output):
+    ///
+    /// ```json
+    /// [
+    ///     { "id": { "S": "fSsgVtal8TpP" }, "name": { "S": "Arthur Dent" } }
+    /// ]
+    /// ```
+    pub fn load_fixture(&self, table_name: &str, json: &str) -> Result<()> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Fixture {
+            Items(Vec<Item>),
+            Scan {
+                #[serde(rename = "Items")]
+                items: Vec<Item>,
+            },
+        }
+
+        let items = match serde_json::from_str(json)
+            .map_err(|err| ErrorImpl::Message(format!("Invalid fixture: {}", err)).into())?
+        {
+            Fixture::Items(items) | Fixture::Scan { items } => items,
+        };
+        self.database()
+            .load(table_name, items)
+            .map_err(Failure::into_error)?;
+        Ok(())
+    }
+
+    /// Process at most `limit` requests of each BatchWriteItem and BatchGetItem call, and return
+    /// the rest as `UnprocessedItems` or `UnprocessedKeys`, the way DynamoDB does when a table is
+    /// throttled. `None`, the default, processes every request.
+    pub fn limit_batches(&self, limit: Option<usize>) {
+        self.database().batch_limit = limit;
+    }
+
+    /// Every item in a table, in key order, or `None` if there is no such table.
+    pub fn items(&self, table_name: &str) -> Option<Vec<Item>> {
+        self.database().items(table_name)
+    }
+}
+
+fn lock(database: &Mutex<Database>) -> MutexGuard<'_, Database> {
+    // A panic while holding the lock can't leave a table half-written, since every operation
+    // checks everything before it writes
+    database
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Why an operation failed, before it's turned into the operation's own error type.
+#[derive(Debug)]
+pub(crate) enum Failure {
+    Validation(String),
+    ResourceNotFound(String),
+    ResourceInUse(String),
+    ConditionalCheckFailed(String),
+    /// The reason each item of a transaction failed, or `"None"`
+    TransactionCanceled(Vec<&'static str>),
+}
+
+impl Failure {
+    fn conditional_check_failed() -> Self {
+        Failure::ConditionalCheckFailed(String::from("The conditional request failed"))
+    }
+
+    fn duplicate_keys() -> Self {
+        Failure::Validation(String::from(
+            "Provided list of item keys contains duplicates",
+        ))
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Validation(message)
+            | Failure::ResourceNotFound(message)
+            | Failure::ResourceInUse(message)
+            | Failure::ConditionalCheckFailed(message) => f.write_str(message),
+            Failure::TransactionCanceled(reasons) => write!(
+                f,
+                "Transaction cancelled, please refer cancellation reasons for specific reasons [{}]",
+                reasons.join(", ")
+            ),
+        }
+    }
+}
+
+impl From<crate::Error> for Failure {
+    fn from(err: crate::Error) -> Self {
+        Failure::Validation(err.to_string())
+    }
+}
+
+impl Failure {
+    fn into_error(self) -> crate::Error {
+        ErrorImpl::Validation(self.to_string()).into()
+    }
+}
+
+/// Turn a [`Failure`] into an operation's error, using the service errors the operation has.
+macro_rules! rusoto_error {
+    ($($error:ident { $($variant:ident),* })*) => {
+        $(
+            impl From<Failure> for RusotoError<$error> {
+                fn from(failure: Failure) -> Self {
+                    match failure {
+                        $(failure @ Failure::$variant(..) => {
+                            RusotoError::Service($error::$variant(failure.to_string()))
+                        })*
+                        other => RusotoError::Validation(other.to_string()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+rusoto_error! {
+    GetItemError { ResourceNotFound }
+    PutItemError { ResourceNotFound, ConditionalCheckFailed }
+    DeleteItemError { ResourceNotFound, ConditionalCheckFailed }
+    UpdateItemError { ResourceNotFound, ConditionalCheckFailed }
+    QueryError { ResourceNotFound }
+    ScanError { ResourceNotFound }
+    BatchGetItemError { ResourceNotFound }
+    BatchWriteItemError { ResourceNotFound }
+    TransactGetItemsError { ResourceNotFound }
+    TransactWriteItemsError { ResourceNotFound, TransactionCanceled }
+    CreateTableError { ResourceInUse }
+    DeleteTableError { ResourceNotFound }
+    DescribeTableError { ResourceNotFound }
+    ListTablesError {}
+}
+
+fn unsupported<E>(operation: &str) -> RusotoError<E> {
+    RusotoError::Validation(format!("MockDynamoDb does not support {}", operation))
+}
+
+/// Implement [`DynamoDb`] with the given methods, and have every other operation fail.
+///
+/// The whole impl is generated here, so `#[async_trait]` sees the expanded methods.
+macro_rules! impl_dynamodb {
+    (
+        { $($supported:tt)* }
+        unsupported { $($method:ident($input:ty) -> $output:ty, $error:ty;)* }
+    ) => {
+        #[async_trait]
+        impl DynamoDb for MockDynamoDb {
+            $($supported)*
+
+            $(
+                async fn $method(&self, _input: $input) -> Result<$output, RusotoError<$error>> {
+                    Err(unsupported(stringify!($method)))
+                }
+            )*
+        }
+    };
+}
+
+impl_dynamodb! {
+{
+    async fn get_item(&self, input: GetItemInput) -> Result<GetItemOutput, RusotoError<GetItemError>> {
+        Ok(self.database().get_item(input)?)
+    }
+
+    async fn put_item(&self, input: PutItemInput) -> Result<PutItemOutput, RusotoError<PutItemError>> {
+        Ok(self.database().put_item(input)?)
+    }
+
+    async fn delete_item(
+        &self,
+        input: DeleteItemInput,
+    ) -> Result<DeleteItemOutput, RusotoError<DeleteItemError>> {
+        Ok(self.database().delete_item(input)?)
+    }
+
+    async fn update_item(
+        &self,
+        input: UpdateItemInput,
+    ) -> Result<UpdateItemOutput, RusotoError<UpdateItemError>> {
+        Ok(self.database().update_item(input)?)
+    }
+
+    async fn query(&self, input: QueryInput) -> Result<QueryOutput, RusotoError<QueryError>> {
+        Ok(self.database().query(input)?)
+    }
+
+    async fn scan(&self, input: ScanInput) -> Result<ScanOutput, RusotoError<ScanError>> {
+        Ok(self.database().scan(input)?)
+    }
+
+    async fn batch_get_item(
+        &self,
+        input: BatchGetItemInput,
+    ) -> Result<BatchGetItemOutput, RusotoError<BatchGetItemError>> {
+        Ok(self.database().batch_get_item(input)?)
+    }
+
+    async fn batch_write_item(
+        &self,
+        input: BatchWriteItemInput,
+    ) -> Result<BatchWriteItemOutput, RusotoError<BatchWriteItemError>> {
+        Ok(self.database().batch_write_item(input)?)
+    }
+
+    async fn transact_get_items(
+        &self,
+        input: TransactGetItemsInput,
+    ) -> Result<TransactGetItemsOutput, RusotoError<TransactGetItemsError>> {
+        Ok(self.database().transact_get_items(input)?)
+    }
+
+    async fn transact_write_items(
+        &self,
+        input: TransactWriteItemsInput,
+    ) -> Result<TransactWriteItemsOutput, RusotoError<TransactWriteItemsError>> {
+        Ok(self.database().transact_write_items(input)?)
+    }
+
+    async fn create_table(
+        &self,
+        input: CreateTableInput,
+    ) -> Result<CreateTableOutput, RusotoError<CreateTableError>> {
+        Ok(self.database().create_table(input)?)
+    }
+
+    async fn delete_table(
+        &self,
+        input: DeleteTableInput,
+    ) -> Result<DeleteTableOutput, RusotoError<DeleteTableError>> {
+        Ok(self.database().delete_table(input)?)
+    }
+
+    async fn describe_table(
+        &self,
+        input: DescribeTableInput,
+    ) -> Result<DescribeTableOutput, RusotoError<DescribeTableError>> {
+        Ok(self.database().describe_table(input)?)
+    }
+
+    async fn list_tables(
+        &self,
+        input: ListTablesInput,
+    ) -> Result<ListTablesOutput, RusotoError<ListTablesError>> {
+        Ok(self.database().list_tables(input)?)
+    }
+
+    async fn describe_endpoints(
+        &self,
+    ) -> Result<DescribeEndpointsResponse, RusotoError<DescribeEndpointsError>> {
+        Err(unsupported("describe_endpoints"))
+    }
+
+    async fn describe_limits(
+        &self,
+    ) -> Result<DescribeLimitsOutput, RusotoError<DescribeLimitsError>> {
+        Err(unsupported("describe_limits"))
+    }
+}
+
+unsupported {
+        batch_execute_statement(BatchExecuteStatementInput) -> BatchExecuteStatementOutput, BatchExecuteStatementError;
+        create_backup(CreateBackupInput) -> CreateBackupOutput, CreateBackupError;
+        create_global_table(CreateGlobalTableInput) -> CreateGlobalTableOutput, CreateGlobalTableError;
+        delete_backup(DeleteBackupInput) -> DeleteBackupOutput, DeleteBackupError;
+        describe_backup(DescribeBackupInput) -> DescribeBackupOutput, DescribeBackupError;
+        describe_continuous_backups(DescribeContinuousBackupsInput) -> DescribeContinuousBackupsOutput, DescribeContinuousBackupsError;
+        describe_contributor_insights(DescribeContributorInsightsInput) -> DescribeContributorInsightsOutput, DescribeContributorInsightsError;
+        describe_export(DescribeExportInput) -> DescribeExportOutput, DescribeExportError;
+        describe_global_table(DescribeGlobalTableInput) -> DescribeGlobalTableOutput, DescribeGlobalTableError;
+        describe_global_table_settings(DescribeGlobalTableSettingsInput) -> DescribeGlobalTableSettingsOutput, DescribeGlobalTableSettingsError;
+        describe_kinesis_streaming_destination(DescribeKinesisStreamingDestinationInput) -> DescribeKinesisStreamingDestinationOutput, DescribeKinesisStreamingDestinationError;
+        describe_table_replica_auto_scaling(DescribeTableReplicaAutoScalingInput) -> DescribeTableReplicaAutoScalingOutput, DescribeTableReplicaAutoScalingError;
+        describe_time_to_live(DescribeTimeToLiveInput) -> DescribeTimeToLiveOutput, DescribeTimeToLiveError;
+        disable_kinesis_streaming_destination(KinesisStreamingDestinationInput) -> KinesisStreamingDestinationOutput, DisableKinesisStreamingDestinationError;
+        enable_kinesis_streaming_destination(KinesisStreamingDestinationInput) -> KinesisStreamingDestinationOutput, EnableKinesisStreamingDestinationError;
+        execute_statement(ExecuteStatementInput) -> ExecuteStatementOutput, ExecuteStatementError;
+        execute_transaction(ExecuteTransactionInput) -> ExecuteTransactionOutput, ExecuteTransactionError;
+        export_table_to_point_in_time(ExportTableToPointInTimeInput) -> ExportTableToPointInTimeOutput, ExportTableToPointInTimeError;
+        list_backups(ListBackupsInput) -> ListBackupsOutput, ListBackupsError;
+        list_contributor_insights(ListContributorInsightsInput) -> ListContributorInsightsOutput, ListContributorInsightsError;
+        list_exports(ListExportsInput) -> ListExportsOutput, ListExportsError;
+        list_global_tables(ListGlobalTablesInput) -> ListGlobalTablesOutput, ListGlobalTablesError;
+        list_tags_of_resource(ListTagsOfResourceInput) -> ListTagsOfResourceOutput, ListTagsOfResourceError;
+        restore_table_from_backup(RestoreTableFromBackupInput) -> RestoreTableFromBackupOutput, RestoreTableFromBackupError;
+        restore_table_to_point_in_time(RestoreTableToPointInTimeInput) -> RestoreTableToPointInTimeOutput, RestoreTableToPointInTimeError;
+        untag_resource(UntagResourceInput) -> (), UntagResourceError;
+        tag_resource(TagResourceInput) -> (), TagResourceError;
+        update_continuous_backups(UpdateContinuousBackupsInput) -> UpdateContinuousBackupsOutput, UpdateContinuousBackupsError;
+        update_contributor_insights(UpdateContributorInsightsInput) -> UpdateContributorInsightsOutput, UpdateContributorInsightsError;
+        update_global_table(UpdateGlobalTableInput) -> UpdateGlobalTableOutput, UpdateGlobalTableError;
+        update_global_table_settings(UpdateGlobalTableSettingsInput) -> UpdateGlobalTableSettingsOutput, UpdateGlobalTableSettingsError;
+        update_table(UpdateTableInput) -> UpdateTableOutput, UpdateTableError;
+        update_table_replica_auto_scaling(UpdateTableReplicaAutoScalingInput) -> UpdateTableReplicaAutoScalingOutput, UpdateTableReplicaAutoScalingError;
+        update_time_to_live(UpdateTimeToLiveInput) -> UpdateTimeToLiveOutput, UpdateTimeToLiveError;
+    }
+}
@@ -0,0 +1,419 @@
+use super::database::Database;
+use super::{lock, Failure, MockDynamoDb};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// The prefix of the `X-Amz-Target` header of every DynamoDB operation.
+const TARGET_PREFIX: &str = "DynamoDB_20120810.";
+
+const CONTENT_TYPE: &str = "application/x-amz-json-1.0";
+
+/// The largest request body the server reads, DynamoDB's own limit of 16 MB.
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+/// The longest request line or header the server reads, in bytes.
+const MAX_LINE: usize = 8 * 1024;
+
+/// The most headers the server reads in a request.
+const MAX_HEADERS: usize = 100;
+
+/// A local HTTP endpoint speaking DynamoDB's JSON protocol, backed by a [`MockDynamoDb`].
+///
+/// The server listens on a loopback port until it is dropped. Any DynamoDB client, such as
+/// `aws-sdk-dynamodb`, rusoto, or the AWS CLI, can use it by pointing its endpoint at
+/// [`endpoint`](MockServer::endpoint). Credentials and request signatures aren't checked.
+///
+/// The server shares its tables with the [`MockDynamoDb`] it was started from, so tables can be
+/// created and seeded from a test, and inspected once the code under test has run.
+///
+/// ```
+/// use serde_dynamo::mock::{MockDynamoDb, MockServer};
+/// use std::io::{Read, Write};
+///
+/// # fn test() -> std::io::Result<()> {
+/// let dynamodb = MockDynamoDb::new();
+/// let server = MockServer::start(&dynamodb)?;
+///
+/// let body = "{}";
+/// let mut stream = std::net::TcpStream::connect(server.address())?;
+/// write!(
+///     stream,
+///     "POST / HTTP/1.1\r\n\
+///      Host: localhost\r\n\
+///      Connection: close\r\n\
+///      X-Amz-Target: DynamoDB_20120810.ListTables\r\n\
+///      Content-Type: application/x-amz-json-1.0\r\n\
+///      Content-Length: {}\r\n\r\n{}",
+///     body.len(),
+///     body,
+/// )?;
+///
+/// let mut response = String::new();
+/// stream.read_to_string(&mut response)?;
+/// assert!(response.starts_with("HTTP/1.1 200 OK"));
+/// assert!(response.ends_with(r#"{"TableNames":[]}"#));
+/// # Ok(())
+/// # }
+/// # test().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MockServer {
+    address: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Start serving the tables of `dynamodb` on a free loopback port.
+    pub fn start(dynamodb: &MockDynamoDb) -> io::Result<MockServer> {
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        let address = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let server = Server {
+            database: Arc::clone(&dynamodb.database),
+            requests: AtomicU64::new(0),
+        };
+        let thread = {
+            let stopped = Arc::clone(&stopped);
+            thread::Builder::new()
+                .name(String::from("mock-dynamodb"))
+                .spawn(move || server.accept(listener, &stopped))?
+        };
+
+        Ok(MockServer {
+            address,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    /// The address the server listens on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The URL to use as a DynamoDB client's endpoint, like `http://127.0.0.1:49152`.
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.address)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake up the accepting thread so it sees the flag. If that fails, the thread may never
+        // wake up, so it's left to stop on its next connection instead of being waited for.
+        let woken = TcpStream::connect(self.address).is_ok();
+        if let Some(thread) = self.thread.take() {
+            if woken {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+struct Server {
+    database: Arc<Mutex<Database>>,
+    requests: AtomicU64,
+}
+
+impl Server {
+    fn accept(self, listener: TcpListener, stopped: &AtomicBool) {
+        let server = Arc::new(self);
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            let server = Arc::clone(&server);
+            // Connections that outlive the server are served until the client closes them
+            let _ = thread::Builder::new()
+                .name(String::from("mock-dynamodb-connection"))
+                .spawn(move || {
+                    let _ = server.serve(stream);
+                });
+        }
+    }
+
+    /// Answer the requests of one connection, until the client closes it.
+    fn serve(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        while let Some(request) = Request::read(&mut reader, &mut writer)? {
+            let reply = match request.method.as_str() {
+                "POST" => self.reply(&request),
+                _ => Reply::status(405, "Method Not Allowed"),
+            };
+            reply.write(
+                &mut writer,
+                self.requests.fetch_add(1, Ordering::Relaxed),
+                request.keep_alive,
+            )?;
+            if !request.keep_alive {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn reply(&self, request: &Request) -> Reply {
+        let operation = match request.target.as_deref() {
+            Some(target) if target.starts_with(TARGET_PREFIX) => &target[TARGET_PREFIX.len()..],
+            _ => {
+                return Reply::error(
+                    "com.amazon.coral.service#UnknownOperationException",
+                    String::from("The X-Amz-Target header is missing or unknown"),
+                )
+            }
+        };
+        let body = match &request.body {
+            Some(body) => &body[..],
+            None => return Reply::too_large(),
+        };
+
+        let mut database = lock(&self.database);
+        match operation {
+            "CreateTable" => call(body, |input| database.create_table(input)),
+            "DeleteTable" => call(body, |input| database.delete_table(input)),
+            "DescribeTable" => call(body, |input| database.describe_table(input)),
+            "ListTables" => call(body, |input| database.list_tables(input)),
+            "GetItem" => call(body, |input| database.get_item(input)),
+            "PutItem" => call(body, |input| database.put_item(input)),
+            "DeleteItem" => call(body, |input| database.delete_item(input)),
+            "UpdateItem" => call(body, |input| database.update_item(input)),
+            "Query" => call(body, |input| database.query(input)),
+            "Scan" => call(body, |input| database.scan(input)),
+            "BatchGetItem" => call(body, |input| database.batch_get_item(input)),
+            "BatchWriteItem" => call(body, |input| database.batch_write_item(input)),
+            "TransactGetItems" => call(body, |input| database.transact_get_items(input)),
+            "TransactWriteItems" => call(body, |input| database.transact_write_items(input)),
+            _ => Reply::error(
+                "com.amazon.coral.service#UnknownOperationException",
+                format!("MockDynamoDb does not support {}", operation),
+            ),
+        }
+    }
+}
+
+/// Run an operation on a JSON request body.
+fn call<I, O>(body: &[u8], operation: impl FnOnce(I) -> Result<O, Failure>) -> Reply
+where
+    I: DeserializeOwned,
+    O: Serialize,
+{
+    let input = match serde_json::from_slice(body) {
+        Ok(input) => input,
+        Err(err) => {
+            return Reply::error(
+                "com.amazon.coral.service#SerializationException",
+                err.to_string(),
+            )
+        }
+    };
+    match operation(input) {
+        Ok(output) => Reply::json(200, serde_json::to_vec(&output).expect("outputs are JSON")),
+        Err(failure) => Reply::failure(failure),
+    }
+}
+
+/// The parts of an HTTP request the server looks at.
+struct Request {
+    method: String,
+    target: Option<String>,
+    keep_alive: bool,
+    /// The body, or `None` if it is larger than the server reads
+    body: Option<Vec<u8>>,
+}
+
+impl Request {
+    /// Read the next request of a connection, or `None` if the client closed it.
+    fn read(reader: &mut impl BufRead, writer: &mut impl Write) -> io::Result<Option<Request>> {
+        let mut line = String::new();
+        if read_line(reader, &mut line)? == 0 {
+            return Ok(None);
+        }
+        let mut parts = line.split_whitespace();
+        let (method, version) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(_), Some(version)) => (method.to_string(), version.to_string()),
+            _ => return Err(invalid_request("malformed request line")),
+        };
+
+        let mut target = None;
+        let mut content_length = None;
+        let mut keep_alive = version == "HTTP/1.1";
+        let mut expect_continue = false;
+        for headers in 0.. {
+            if read_line(reader, &mut line)? == 0 {
+                return Err(invalid_request("unexpected end of headers"));
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if headers == MAX_HEADERS {
+                return Err(invalid_request("too many headers"));
+            }
+            let (name, value) = match header.find(':') {
+                Some(colon) => (&header[..colon], header[colon + 1..].trim()),
+                None => return Err(invalid_request("malformed header")),
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "x-amz-target" => target = Some(value.to_string()),
+                "content-length" => {
+                    content_length = Some(
+                        value
+                            .parse()
+                            .map_err(|_| invalid_request("invalid Content-Length"))?,
+                    )
+                }
+                "connection" => keep_alive = !value.eq_ignore_ascii_case("close"),
+                "expect" => expect_continue = value.eq_ignore_ascii_case("100-continue"),
+                "transfer-encoding" => {
+                    return Err(invalid_request("chunked request bodies aren't supported"))
+                }
+                _ => {}
+            }
+        }
+
+        let content_length = content_length.unwrap_or(0);
+        if content_length > MAX_BODY {
+            // Leave the body unread, and close the connection after replying
+            return Ok(Some(Request {
+                method,
+                target,
+                keep_alive: false,
+                body: None,
+            }));
+        }
+        if expect_continue {
+            writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        Ok(Some(Request {
+            method,
+            target,
+            keep_alive,
+            body: Some(body),
+        }))
+    }
+}
+
+/// Read a line into `line`, replacing what it held, failing if the line is longer than
+/// [`MAX_LINE`] rather than reading on until it ends.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<usize> {
+    line.clear();
+    let read = reader.take(MAX_LINE as u64 + 1).read_line(line)?;
+    if read > MAX_LINE {
+        return Err(invalid_request("request line or header too long"));
+    }
+    Ok(read)
+}
+
+fn invalid_request(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// An HTTP response.
+struct Reply {
+    status: u16,
+    reason: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn status(status: u16, reason: &'static str) -> Reply {
+        Reply {
+            status,
+            reason,
+            body: Vec::new(),
+        }
+    }
+
+    fn json(status: u16, body: Vec<u8>) -> Reply {
+        let reason = if status == 200 { "OK" } else { "Bad Request" };
+        Reply {
+            status,
+            reason,
+            body,
+        }
+    }
+
+    /// An error in the shape DynamoDB returns them, which SDKs recognize by `__type`.
+    fn error(error_type: &str, message: String) -> Reply {
+        let body = json!({ "__type": error_type, "message": message });
+        Reply::json(400, body.to_string().into_bytes())
+    }
+
+    /// DynamoDB's answer to a request larger than it accepts.
+    fn too_large() -> Reply {
+        let body = json!({
+            "__type": "com.amazon.coral.validate#ValidationException",
+            "message": format!("Request size exceeded {} bytes", MAX_BODY),
+        });
+        Reply {
+            status: 413,
+            reason: "Request Entity Too Large",
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    fn failure(failure: Failure) -> Reply {
+        let error_type = match &failure {
+            Failure::Validation(_) => "com.amazon.coral.validate#ValidationException",
+            Failure::ResourceNotFound(_) => {
+                "com.amazonaws.dynamodb.v20120810#ResourceNotFoundException"
+            }
+            Failure::ResourceInUse(_) => "com.amazonaws.dynamodb.v20120810#ResourceInUseException",
+            Failure::ConditionalCheckFailed(_) => {
+                "com.amazonaws.dynamodb.v20120810#ConditionalCheckFailedException"
+            }
+            Failure::TransactionCanceled(reasons) => {
+                let reasons: Vec<_> = reasons
+                    .iter()
+                    .map(|&reason| match reason {
+                        "None" => json!({ "Code": reason }),
+                        _ => json!({ "Code": reason, "Message": "The conditional request failed" }),
+                    })
+                    .collect();
+                let body = json!({
+                    "__type": "com.amazonaws.dynamodb.v20120810#TransactionCanceledException",
+                    "CancellationReasons": reasons,
+                    "Message": failure.to_string(),
+                });
+                return Reply::json(400, body.to_string().into_bytes());
+            }
+        };
+        Reply::error(error_type, failure.to_string())
+    }
+
+    fn write(&self, writer: &mut impl Write, request_id: u64, keep_alive: bool) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\n\
+             Content-Type: {}\r\n\
+             Content-Length: {}\r\n\
+             x-amzn-RequestId: {:032}\r\n\
+             Connection: {}\r\n\r\n",
+            self.status,
+            self.reason,
+            CONTENT_TYPE,
+            self.body.len(),
+            request_id,
+            if keep_alive { "keep-alive" } else { "close" },
+        )?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
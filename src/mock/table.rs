@@ -0,0 +1,515 @@
+use super::Failure;
+use crate::number::Number;
+use crate::Item;
+use rusoto_dynamodb::{
+    AttributeDefinition, AttributeValue, CreateTableInput, GlobalSecondaryIndexDescription,
+    KeySchemaElement, LocalSecondaryIndexDescription, Projection, TableDescription,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use std::ops::Bound;
+
+/// The types a key attribute can have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScalarType {
+    S,
+    N,
+    B,
+}
+
+impl Display for ScalarType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScalarType::S => "S",
+            ScalarType::N => "N",
+            ScalarType::B => "B",
+        })
+    }
+}
+
+/// The value of a key attribute, ordered the way DynamoDB orders sort keys.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum KeyValue {
+    S(String),
+    N(Number),
+    B(Vec<u8>),
+}
+
+impl KeyValue {
+    /// The key value stored in `value`, if it has type `scalar`.
+    ///
+    /// Fails if it holds a number DynamoDB wouldn't accept.
+    fn from_attribute(value: &AttributeValue, scalar: ScalarType) -> Result<Option<Self>, Failure> {
+        Ok(match scalar {
+            ScalarType::S => value.s.clone().map(KeyValue::S),
+            ScalarType::N => match &value.n {
+                Some(n) => Some(KeyValue::N(
+                    Number::parse_checked(n).map_err(|err| Failure::Validation(err.to_string()))?,
+                )),
+                None => None,
+            },
+            ScalarType::B => value.b.as_ref().map(|b| KeyValue::B(b.to_vec())),
+        })
+    }
+
+    /// The type actually stored in `value`, for error messages.
+    fn actual_type(value: &AttributeValue) -> &'static str {
+        if value.s.is_some() {
+            "S"
+        } else if value.n.is_some() {
+            "N"
+        } else if value.b.is_some() {
+            "B"
+        } else {
+            "non-scalar"
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            KeyValue::S(s) => s.is_empty(),
+            KeyValue::N(_) => false,
+            KeyValue::B(b) => b.is_empty(),
+        }
+    }
+}
+
+/// A partition key value and an optional sort key value.
+pub(crate) type Key = (KeyValue, Option<KeyValue>);
+
+#[derive(Debug, Clone)]
+pub(crate) struct KeyAttribute {
+    pub(crate) name: String,
+    pub(crate) scalar: ScalarType,
+}
+
+/// The key attributes of a table or an index.
+#[derive(Debug, Clone)]
+pub(crate) struct KeySchema {
+    pub(crate) partition: KeyAttribute,
+    pub(crate) sort: Option<KeyAttribute>,
+}
+
+impl KeySchema {
+    fn new(
+        elements: &[KeySchemaElement],
+        definitions: &[AttributeDefinition],
+    ) -> Result<Self, Failure> {
+        let attribute = |element: &KeySchemaElement| {
+            let definition = definitions
+                .iter()
+                .find(|definition| definition.attribute_name == element.attribute_name)
+                .ok_or_else(|| {
+                    Failure::Validation(format!(
+                        "One or more parameter values were invalid: Some index key attributes are not defined in AttributeDefinitions. Keys: [{}]",
+                        element.attribute_name
+                    ))
+                })?;
+            let scalar = match definition.attribute_type.as_str() {
+                "S" => ScalarType::S,
+                "N" => ScalarType::N,
+                "B" => ScalarType::B,
+                other => {
+                    return Err(Failure::Validation(format!(
+                        "1 validation error detected: Value '{}' at 'attributeDefinitions.member.attributeType' failed to satisfy constraint: Member must satisfy enum value set: [B, N, S]",
+                        other
+                    )))
+                }
+            };
+            Ok(KeyAttribute {
+                name: element.attribute_name.clone(),
+                scalar,
+            })
+        };
+
+        match elements {
+            [partition] if partition.key_type == "HASH" => Ok(KeySchema {
+                partition: attribute(partition)?,
+                sort: None,
+            }),
+            [partition, sort] if partition.key_type == "HASH" && sort.key_type == "RANGE" => {
+                Ok(KeySchema {
+                    partition: attribute(partition)?,
+                    sort: Some(attribute(sort)?),
+                })
+            }
+            _ => Err(Failure::Validation(String::from(
+                "1 validation error detected: Value at 'keySchema' failed to satisfy constraint: Member must have a HASH key, optionally followed by a RANGE key",
+            ))),
+        }
+    }
+
+    pub(crate) fn attributes(&self) -> impl Iterator<Item = &KeyAttribute> {
+        std::iter::once(&self.partition).chain(self.sort.as_ref())
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.attributes().any(|attribute| attribute.name == name)
+    }
+
+    /// The key of `item` under this schema, or `None` if it doesn't have one.
+    ///
+    /// Items without an index's key attributes are simply left out of the index.
+    pub(crate) fn key_of(&self, item: &Item) -> Result<Option<Key>, Failure> {
+        let value = |attribute: &KeyAttribute| match item.get(&attribute.name) {
+            Some(value) => KeyValue::from_attribute(value, attribute.scalar),
+            None => Ok(None),
+        };
+        let partition = match value(&self.partition)? {
+            Some(partition) => partition,
+            None => return Ok(None),
+        };
+        let sort = match &self.sort {
+            Some(sort) => match value(sort)? {
+                Some(sort) => Some(sort),
+                None => return Ok(None),
+            },
+            None => None,
+        };
+        Ok(Some((partition, sort)))
+    }
+
+    /// The key given in a request, which must have exactly the key attributes.
+    pub(crate) fn request_key(&self, key: &Item) -> Result<Key, Failure> {
+        let mismatch = || {
+            Failure::Validation(String::from(
+                "The provided key element does not match the schema",
+            ))
+        };
+        if key.len() != self.attributes().count() {
+            return Err(mismatch());
+        }
+        let key = self.key_of(key)?.ok_or_else(mismatch)?;
+        check_not_empty(self, &key)?;
+        Ok(key)
+    }
+
+    /// The key of an item being written, which must have the key attributes with the right types.
+    pub(crate) fn item_key(&self, item: &Item) -> Result<Key, Failure> {
+        for attribute in self.attributes() {
+            let value = item.get(&attribute.name).ok_or_else(|| {
+                Failure::Validation(format!(
+                    "One or more parameter values were invalid: Missing the key {} in the item",
+                    attribute.name
+                ))
+            })?;
+            if KeyValue::from_attribute(value, attribute.scalar)?.is_none() {
+                return Err(Failure::Validation(format!(
+                    "One or more parameter values were invalid: Type mismatch for key {} expected: {} actual: {}",
+                    attribute.name,
+                    attribute.scalar,
+                    KeyValue::actual_type(value)
+                )));
+            }
+        }
+        let key = self.key_of(item)?.expect("key attributes were checked");
+        check_not_empty(self, &key)?;
+        Ok(key)
+    }
+
+    /// Copy the key attributes out of `item`.
+    pub(crate) fn key_item(&self, item: &Item) -> Item {
+        self.attributes()
+            .filter_map(|attribute| {
+                item.get(&attribute.name)
+                    .map(|value| (attribute.name.clone(), value.clone()))
+            })
+            .collect()
+    }
+}
+
+fn check_not_empty(schema: &KeySchema, key: &Key) -> Result<(), Failure> {
+    let values = std::iter::once(&key.0).chain(key.1.as_ref());
+    for (attribute, value) in schema.attributes().zip(values) {
+        if value.is_empty() {
+            return Err(Failure::Validation(format!(
+                "One or more parameter values are not valid. The AttributeValue for a key attribute cannot contain an empty string value. Key: {}",
+                attribute.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Which attributes an index stores.
+#[derive(Debug, Clone)]
+pub(crate) enum IndexProjection {
+    All,
+    KeysOnly,
+    Include(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Index {
+    pub(crate) name: String,
+    pub(crate) schema: KeySchema,
+    pub(crate) projection: IndexProjection,
+    description: Projection,
+    key_schema: Vec<KeySchemaElement>,
+    global: bool,
+}
+
+impl Index {
+    fn new(
+        name: &str,
+        key_schema: &[KeySchemaElement],
+        projection: &Projection,
+        definitions: &[AttributeDefinition],
+        global: bool,
+    ) -> Result<Self, Failure> {
+        let index_projection = match projection.projection_type.as_deref() {
+            Some("ALL") => IndexProjection::All,
+            Some("KEYS_ONLY") => IndexProjection::KeysOnly,
+            Some("INCLUDE") => {
+                IndexProjection::Include(projection.non_key_attributes.clone().unwrap_or_default())
+            }
+            _ => {
+                return Err(Failure::Validation(String::from(
+                    "One or more parameter values were invalid: Unknown ProjectionType",
+                )))
+            }
+        };
+        Ok(Index {
+            name: name.to_string(),
+            schema: KeySchema::new(key_schema, definitions)?,
+            projection: index_projection,
+            description: projection.clone(),
+            key_schema: key_schema.to_vec(),
+            global,
+        })
+    }
+}
+
+/// A table and everything stored in it.
+#[derive(Debug, Clone)]
+pub(crate) struct Table {
+    pub(crate) name: String,
+    pub(crate) schema: KeySchema,
+    pub(crate) indexes: Vec<Index>,
+    /// Every item, by key. Written through [`insert`](Table::insert) and
+    /// [`remove`](Table::remove), which keep the indexes in step.
+    pub(crate) items: BTreeMap<Key, Item>,
+    /// For each index, the index key and table key of every item in it, in index order
+    index_entries: Vec<BTreeSet<(Key, Key)>>,
+    key_schema: Vec<KeySchemaElement>,
+    attribute_definitions: Vec<AttributeDefinition>,
+}
+
+impl Table {
+    pub(crate) fn new(input: &CreateTableInput) -> Result<Self, Failure> {
+        let definitions = &input.attribute_definitions;
+        let mut indexes = Vec::new();
+        for index in input.global_secondary_indexes.iter().flatten() {
+            indexes.push(Index::new(
+                &index.index_name,
+                &index.key_schema,
+                &index.projection,
+                definitions,
+                true,
+            )?);
+        }
+        for index in input.local_secondary_indexes.iter().flatten() {
+            indexes.push(Index::new(
+                &index.index_name,
+                &index.key_schema,
+                &index.projection,
+                definitions,
+                false,
+            )?);
+        }
+        for (idx, index) in indexes.iter().enumerate() {
+            if indexes[..idx].iter().any(|other| other.name == index.name) {
+                return Err(Failure::Validation(format!(
+                    "One or more parameter values were invalid: Duplicate index name: {}",
+                    index.name
+                )));
+            }
+        }
+
+        Ok(Table {
+            name: input.table_name.clone(),
+            schema: KeySchema::new(&input.key_schema, definitions)?,
+            index_entries: vec![BTreeSet::new(); indexes.len()],
+            indexes,
+            items: BTreeMap::new(),
+            key_schema: input.key_schema.clone(),
+            attribute_definitions: definitions.clone(),
+        })
+    }
+
+    pub(crate) fn index(&self, name: &str) -> Result<&Index, Failure> {
+        self.indexes
+            .iter()
+            .find(|index| index.name == name)
+            .ok_or_else(|| {
+                Failure::Validation(format!(
+                    "The table does not have the specified index: {}",
+                    name
+                ))
+            })
+    }
+
+    /// Check an item that is about to be stored, and return its key.
+    pub(crate) fn check_item(&self, item: &Item) -> Result<Key, Failure> {
+        let key = self.schema.item_key(item)?;
+        for index in &self.indexes {
+            for attribute in index.schema.attributes() {
+                if let Some(value) = item.get(&attribute.name) {
+                    if KeyValue::from_attribute(value, attribute.scalar)?.is_none() {
+                        return Err(Failure::Validation(format!(
+                            "One or more parameter values were invalid: Type mismatch for Index Key {} Expected: {} Actual: {} IndexName: {}",
+                            attribute.name,
+                            attribute.scalar,
+                            KeyValue::actual_type(value),
+                            index.name
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(key)
+    }
+
+    /// Store `item` at `key`, returning the item it replaces.
+    ///
+    /// `item` must have passed [`check_item`](Table::check_item).
+    pub(crate) fn insert(&mut self, key: Key, item: Item) -> Option<Item> {
+        self.unindex(&key);
+        for (index, entries) in self.indexes.iter().zip(&mut self.index_entries) {
+            if let Some(index_key) = index
+                .schema
+                .key_of(&item)
+                .expect("stored items were checked")
+            {
+                entries.insert((index_key, key.clone()));
+            }
+        }
+        self.items.insert(key, item)
+    }
+
+    /// Remove the item at `key`, returning it.
+    pub(crate) fn remove(&mut self, key: &Key) -> Option<Item> {
+        self.unindex(key);
+        self.items.remove(key)
+    }
+
+    fn unindex(&mut self, key: &Key) {
+        if let Some(old) = self.items.get(key) {
+            for (index, entries) in self.indexes.iter().zip(&mut self.index_entries) {
+                if let Some(index_key) =
+                    index.schema.key_of(old).expect("stored items were checked")
+                {
+                    entries.remove(&(index_key, key.clone()));
+                }
+            }
+        }
+    }
+
+    fn entries(&self, index: &Index) -> &BTreeSet<(Key, Key)> {
+        let position = self
+            .indexes
+            .iter()
+            .position(|i| i.name == index.name)
+            .expect("indexes belong to their table");
+        &self.index_entries[position]
+    }
+
+    /// The items of the table, or of one of its indexes, in key order or in reverse, along with
+    /// the key of each item in the table or index.
+    ///
+    /// Items are read lazily, starting after `start`. Index entries carry only the attributes
+    /// the index projects.
+    pub(crate) fn view<'a>(
+        &'a self,
+        index: Option<&'a Index>,
+        start: Option<&(Key, Key)>,
+        forward: bool,
+    ) -> Box<dyn Iterator<Item = ((Key, Key), Item)> + 'a> {
+        fn bounds<T>(start: Option<&T>, forward: bool) -> (Bound<&T>, Bound<&T>) {
+            match (start, forward) {
+                (None, _) => (Bound::Unbounded, Bound::Unbounded),
+                (Some(start), true) => (Bound::Excluded(start), Bound::Unbounded),
+                (Some(start), false) => (Bound::Unbounded, Bound::Excluded(start)),
+            }
+        }
+
+        match index {
+            None => {
+                let entry = |(key, item): (&Key, &Item)| ((key.clone(), key.clone()), item.clone());
+                let range = self
+                    .items
+                    .range(bounds(start.map(|start| &start.1), forward));
+                if forward {
+                    Box::new(range.map(entry))
+                } else {
+                    Box::new(range.rev().map(entry))
+                }
+            }
+            Some(index) => {
+                let entry = move |position: &(Key, Key)| {
+                    let item = self.project_into(index, &self.items[&position.1]);
+                    (position.clone(), item)
+                };
+                let range = self.entries(index).range(bounds(start, forward));
+                if forward {
+                    Box::new(range.map(entry))
+                } else {
+                    Box::new(range.rev().map(entry))
+                }
+            }
+        }
+    }
+
+    fn project_into(&self, index: &Index, item: &Item) -> Item {
+        let keep = |name: &str| match &index.projection {
+            IndexProjection::All => true,
+            IndexProjection::KeysOnly => false,
+            IndexProjection::Include(names) => names.iter().any(|n| n == name),
+        };
+        item.iter()
+            .filter(|(name, _)| {
+                self.schema.contains(name) || index.schema.contains(name) || keep(name)
+            })
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    pub(crate) fn description(&self) -> TableDescription {
+        let global: Vec<_> = self
+            .indexes
+            .iter()
+            .filter(|index| index.global)
+            .map(|index| GlobalSecondaryIndexDescription {
+                index_name: Some(index.name.clone()),
+                index_status: Some(String::from("ACTIVE")),
+                item_count: Some(self.entries(index).len() as i64),
+                key_schema: Some(index.key_schema.clone()),
+                projection: Some(index.description.clone()),
+                ..GlobalSecondaryIndexDescription::default()
+            })
+            .collect();
+        let local: Vec<_> = self
+            .indexes
+            .iter()
+            .filter(|index| !index.global)
+            .map(|index| LocalSecondaryIndexDescription {
+                index_name: Some(index.name.clone()),
+                item_count: Some(self.entries(index).len() as i64),
+                key_schema: Some(index.key_schema.clone()),
+                projection: Some(index.description.clone()),
+                ..LocalSecondaryIndexDescription::default()
+            })
+            .collect();
+
+        TableDescription {
+            attribute_definitions: Some(self.attribute_definitions.clone()),
+            global_secondary_indexes: Some(global).filter(|indexes| !indexes.is_empty()),
+            item_count: Some(self.items.len() as i64),
+            key_schema: Some(self.key_schema.clone()),
+            local_secondary_indexes: Some(local).filter(|indexes| !indexes.is_empty()),
+            table_name: Some(self.name.clone()),
+            table_status: Some(String::from("ACTIVE")),
+            ..TableDescription::default()
+        }
+    }
+}
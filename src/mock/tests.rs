@@ -0,0 +1,812 @@
+// The closures below return rusoto's errors as they are
+#![allow(clippy::result_large_err)]
+
+use super::*;
+use futures::executor::block_on;
+use maplit::hashmap;
+use serde_json::json;
+use std::collections::HashMap;
+
+fn item(value: serde_json::Value) -> Item {
+    crate::to_item(value).unwrap()
+}
+
+fn values(value: serde_json::Value) -> Option<HashMap<String, AttributeValue>> {
+    Some(item(value))
+}
+
+fn attribute(name: &str, attribute_type: &str) -> AttributeDefinition {
+    AttributeDefinition {
+        attribute_name: name.to_string(),
+        attribute_type: attribute_type.to_string(),
+    }
+}
+
+fn key(name: &str, key_type: &str) -> KeySchemaElement {
+    KeySchemaElement {
+        attribute_name: name.to_string(),
+        key_type: key_type.to_string(),
+    }
+}
+
+/// A table of posts keyed by author and timestamp, with an index on the topic.
+fn posts() -> MockDynamoDb {
+    let client = MockDynamoDb::new();
+    client
+        .add_table(CreateTableInput {
+            table_name: "posts".to_string(),
+            key_schema: vec![key("author", "HASH"), key("at", "RANGE")],
+            attribute_definitions: vec![
+                attribute("author", "S"),
+                attribute("at", "N"),
+                attribute("topic", "S"),
+            ],
+            global_secondary_indexes: Some(vec![GlobalSecondaryIndex {
+                index_name: "by-topic".to_string(),
+                key_schema: vec![key("topic", "HASH"), key("at", "RANGE")],
+                projection: Projection {
+                    projection_type: Some("KEYS_ONLY".to_string()),
+                    non_key_attributes: None,
+                },
+                provisioned_throughput: None,
+            }]),
+            ..CreateTableInput::default()
+        })
+        .unwrap();
+    client
+        .load_fixture(
+            "posts",
+            r#"{
+                "Items": [
+                    { "author": { "S": "arthur" }, "at": { "N": "3" }, "topic": { "S": "tea" }, "likes": { "N": "2" } },
+                    { "author": { "S": "arthur" }, "at": { "N": "1" }, "topic": { "S": "towels" }, "likes": { "N": "5" } },
+                    { "author": { "S": "arthur" }, "at": { "N": "20" }, "topic": { "S": "tea" }, "likes": { "N": "0" } },
+                    { "author": { "S": "ford" }, "at": { "N": "2" }, "topic": { "S": "towels" }, "likes": { "N": "42" } },
+                    { "author": { "S": "ford" }, "at": { "N": "4" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+    client
+}
+
+fn post_key(author: &str, at: u32) -> Item {
+    item(json!({ "author": author, "at": at }))
+}
+
+fn validation<E>(result: Result<impl std::fmt::Debug, RusotoError<E>>) -> String
+where
+    E: std::fmt::Debug,
+{
+    match result {
+        Err(RusotoError::Validation(message)) => message,
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn load_fixture() {
+    let client = posts();
+    let items = client.items("posts").unwrap();
+    assert_eq!(items.len(), 5);
+    // Key order, with numbers compared as numbers
+    assert_eq!(items[2]["at"], crate::to_attribute_value(20).unwrap());
+    assert!(client.items("users").is_none());
+
+    assert!(client.load_fixture("users", "[]").is_err());
+    assert!(client.load_fixture("posts", "{").is_err());
+    let err = client
+        .load_fixture("posts", r#"[{ "author": { "S": "zaphod" } }]"#)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "One or more parameter values were invalid: Missing the key at in the item"
+    );
+}
+
+#[test]
+fn put_and_get() {
+    let client = posts();
+    let put = |condition: &str| {
+        block_on(client.put_item(PutItemInput {
+            table_name: "posts".to_string(),
+            item: item(json!({ "author": "ford", "at": 4, "topic": "towels" })),
+            condition_expression: Some(condition.to_string()),
+            return_values: Some("ALL_OLD".to_string()),
+            ..PutItemInput::default()
+        }))
+    };
+
+    match put("attribute_not_exists(author)") {
+        Err(RusotoError::Service(PutItemError::ConditionalCheckFailed(message))) => {
+            assert_eq!(message, "The conditional request failed")
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    let output = put("attribute_not_exists(topic)").unwrap();
+    assert_eq!(output.attributes, Some(post_key("ford", 4)));
+
+    let output = block_on(client.get_item(GetItemInput {
+        table_name: "posts".to_string(),
+        key: post_key("ford", 4),
+        projection_expression: Some("topic".to_string()),
+        ..GetItemInput::default()
+    }))
+    .unwrap();
+    assert_eq!(output.item, Some(item(json!({ "topic": "towels" }))));
+
+    let output = block_on(client.get_item(GetItemInput {
+        table_name: "posts".to_string(),
+        key: post_key("ford", 5),
+        ..GetItemInput::default()
+    }))
+    .unwrap();
+    assert_eq!(output.item, None);
+
+    match block_on(client.get_item(GetItemInput {
+        table_name: "users".to_string(),
+        key: post_key("ford", 5),
+        ..GetItemInput::default()
+    })) {
+        Err(RusotoError::Service(GetItemError::ResourceNotFound(_))) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn key_validation() {
+    let client = posts();
+    let get = |key: Item| {
+        block_on(client.get_item(GetItemInput {
+            table_name: "posts".to_string(),
+            key,
+            ..GetItemInput::default()
+        }))
+    };
+    let mismatch = "The provided key element does not match the schema";
+    assert_eq!(validation(get(item(json!({ "author": "ford" })))), mismatch);
+    assert_eq!(
+        validation(get(item(json!({ "author": "ford", "at": "4" })))),
+        mismatch
+    );
+    assert_eq!(
+        validation(get(item(
+            json!({ "author": "ford", "at": 4, "topic": "x" })
+        ))),
+        mismatch
+    );
+    let mut key = item(json!({ "author": "ford" }));
+    key.insert(
+        "at".to_string(),
+        AttributeValue {
+            n: Some("1E126".to_string()),
+            ..AttributeValue::default()
+        },
+    );
+    assert_eq!(
+        validation(get(key)),
+        "Number overflow. Attempting to store a number with magnitude larger than supported range"
+    );
+
+    // Index keys are checked on writes too
+    let err = validation(block_on(client.put_item(PutItemInput {
+        table_name: "posts".to_string(),
+        item: item(json!({ "author": "ford", "at": 5, "topic": 5 })),
+        ..PutItemInput::default()
+    })));
+    assert!(err.starts_with("One or more parameter values were invalid: Type mismatch"));
+}
+
+#[test]
+fn unused_placeholders() {
+    let client = posts();
+    let err = validation(block_on(client.delete_item(DeleteItemInput {
+        table_name: "posts".to_string(),
+        key: post_key("ford", 4),
+        condition_expression: Some("attribute_exists(author)".to_string()),
+        expression_attribute_values: values(json!({ ":x": 1 })),
+        ..DeleteItemInput::default()
+    })));
+    assert_eq!(
+        err,
+        "Value provided in ExpressionAttributeValues unused in expressions: keys: {:x}"
+    );
+    assert_eq!(client.items("posts").unwrap().len(), 5);
+}
+
+#[test]
+fn update() {
+    let client = posts();
+    let update = |expression: &str, return_values: &str| {
+        block_on(client.update_item(UpdateItemInput {
+            table_name: "posts".to_string(),
+            key: post_key("arthur", 1),
+            update_expression: Some(expression.to_string()),
+            condition_expression: Some("likes >= :zero".to_string()),
+            expression_attribute_values: values(json!({ ":one": 1, ":zero": 0 })),
+            return_values: Some(return_values.to_string()),
+            ..UpdateItemInput::default()
+        }))
+    };
+
+    let output = update("SET likes = likes + :one", "UPDATED_NEW").unwrap();
+    assert_eq!(output.attributes, Some(item(json!({ "likes": 6 }))));
+    let output = update("SET likes = likes - :one", "UPDATED_OLD").unwrap();
+    assert_eq!(output.attributes, Some(item(json!({ "likes": 6 }))));
+
+    assert_eq!(
+        validation(update("SET author = :one", "NONE")),
+        "One or more parameter values were invalid: \
+         Cannot update attribute author. This attribute is part of the key"
+    );
+    assert!(validation(update("SET likes = :one", "EVERYTHING"))
+        .starts_with("1 validation error detected: Value 'EVERYTHING' at 'returnValues'"));
+
+    // Updating a missing item creates it, unless the condition fails
+    let output = block_on(client.update_item(UpdateItemInput {
+        table_name: "posts".to_string(),
+        key: post_key("zaphod", 1),
+        update_expression: Some("ADD likes :one".to_string()),
+        expression_attribute_values: values(json!({ ":one": 1 })),
+        return_values: Some("ALL_NEW".to_string()),
+        ..UpdateItemInput::default()
+    }))
+    .unwrap();
+    assert_eq!(
+        output.attributes,
+        Some(item(json!({ "author": "zaphod", "at": 1, "likes": 1 })))
+    );
+}
+
+#[test]
+fn query() {
+    let client = posts();
+    let query = |input: QueryInput| {
+        block_on(client.query(QueryInput {
+            table_name: "posts".to_string(),
+            expression_attribute_values: values(json!({ ":author": "arthur", ":at": 2 })),
+            ..input
+        }))
+        .unwrap()
+    };
+    let ats = |items: Option<Vec<Item>>| -> Vec<u32> {
+        items
+            .unwrap()
+            .into_iter()
+            .map(|item| crate::from_attribute_value(item["at"].clone()).unwrap())
+            .collect()
+    };
+
+    let output = query(QueryInput {
+        key_condition_expression: Some("author = :author AND at >= :at".to_string()),
+        ..QueryInput::default()
+    });
+    assert_eq!(ats(output.items), vec![3, 20]);
+    assert_eq!(output.count, Some(2));
+    assert_eq!(output.last_evaluated_key, None);
+
+    let output = query(QueryInput {
+        key_condition_expression: Some("author = :author".to_string()),
+        filter_expression: Some("likes > :at".to_string()),
+        scan_index_forward: Some(false),
+        ..QueryInput::default()
+    });
+    assert_eq!(ats(output.items), vec![1]);
+    assert_eq!(output.scanned_count, Some(3));
+
+    // Paging, backwards
+    let first = query(QueryInput {
+        key_condition_expression: Some("author = :author AND at > :at".to_string()),
+        scan_index_forward: Some(false),
+        limit: Some(1),
+        ..QueryInput::default()
+    });
+    assert_eq!(ats(first.items), vec![20]);
+    assert_eq!(first.last_evaluated_key, Some(post_key("arthur", 20)));
+    let second = query(QueryInput {
+        key_condition_expression: Some("author = :author AND at > :at".to_string()),
+        scan_index_forward: Some(false),
+        limit: Some(1),
+        exclusive_start_key: first.last_evaluated_key,
+        ..QueryInput::default()
+    });
+    assert_eq!(ats(second.items), vec![3]);
+    // The limit was reached, so DynamoDB can't know there's nothing left
+    assert_eq!(second.last_evaluated_key, Some(post_key("arthur", 3)));
+    let third = query(QueryInput {
+        key_condition_expression: Some("author = :author AND at > :at".to_string()),
+        scan_index_forward: Some(false),
+        limit: Some(1),
+        exclusive_start_key: second.last_evaluated_key,
+        ..QueryInput::default()
+    });
+    assert_eq!(third.count, Some(0));
+    assert_eq!(third.last_evaluated_key, None);
+
+    let err = validation(block_on(client.query(QueryInput {
+        table_name: "posts".to_string(),
+        key_condition_expression: Some("likes = :likes".to_string()),
+        expression_attribute_values: values(json!({ ":likes": 1 })),
+        ..QueryInput::default()
+    })));
+    assert_eq!(err, "Query condition missed key schema element: author");
+}
+
+#[test]
+fn query_index() {
+    let client = posts();
+    let output = block_on(client.query(QueryInput {
+        table_name: "posts".to_string(),
+        index_name: Some("by-topic".to_string()),
+        key_condition_expression: Some("topic = :topic".to_string()),
+        expression_attribute_values: values(json!({ ":topic": "tea" })),
+        ..QueryInput::default()
+    }))
+    .unwrap();
+    assert_eq!(
+        output.items,
+        Some(vec![
+            item(json!({ "author": "arthur", "at": 3, "topic": "tea" })),
+            item(json!({ "author": "arthur", "at": 20, "topic": "tea" })),
+        ])
+    );
+
+    // Items without the index key aren't in the index
+    let output = block_on(client.scan(ScanInput {
+        table_name: "posts".to_string(),
+        index_name: Some("by-topic".to_string()),
+        ..ScanInput::default()
+    }))
+    .unwrap();
+    assert_eq!(output.count, Some(4));
+
+    // The index follows writes that move, add and remove its entries
+    block_on(client.put_item(PutItemInput {
+        table_name: "posts".to_string(),
+        item: item(json!({ "author": "arthur", "at": 3, "topic": "towels" })),
+        ..PutItemInput::default()
+    }))
+    .unwrap();
+    block_on(client.put_item(PutItemInput {
+        table_name: "posts".to_string(),
+        item: item(json!({ "author": "ford", "at": 4, "topic": "tea" })),
+        ..PutItemInput::default()
+    }))
+    .unwrap();
+    block_on(client.delete_item(DeleteItemInput {
+        table_name: "posts".to_string(),
+        key: post_key("arthur", 20),
+        ..DeleteItemInput::default()
+    }))
+    .unwrap();
+    let output = block_on(client.query(QueryInput {
+        table_name: "posts".to_string(),
+        index_name: Some("by-topic".to_string()),
+        key_condition_expression: Some("topic = :topic".to_string()),
+        expression_attribute_values: values(json!({ ":topic": "tea" })),
+        ..QueryInput::default()
+    }))
+    .unwrap();
+    assert_eq!(
+        output.items,
+        Some(vec![item(
+            json!({ "author": "ford", "at": 4, "topic": "tea" })
+        )])
+    );
+    let description = block_on(client.describe_table(DescribeTableInput {
+        table_name: "posts".to_string(),
+    }))
+    .unwrap()
+    .table
+    .unwrap();
+    assert_eq!(
+        description.global_secondary_indexes.unwrap()[0].item_count,
+        Some(4)
+    );
+
+    let err = validation(block_on(client.query(QueryInput {
+        table_name: "posts".to_string(),
+        index_name: Some("by-author".to_string()),
+        key_condition_expression: Some("author = :author".to_string()),
+        expression_attribute_values: values(json!({ ":author": "ford" })),
+        ..QueryInput::default()
+    })));
+    assert_eq!(
+        err,
+        "The table does not have the specified index: by-author"
+    );
+}
+
+#[test]
+fn scan() {
+    let client = posts();
+    let output = block_on(client.scan(ScanInput {
+        table_name: "posts".to_string(),
+        filter_expression: Some("attribute_not_exists(topic) OR likes > :likes".to_string()),
+        expression_attribute_values: values(json!({ ":likes": 4 })),
+        projection_expression: Some("author, #at".to_string()),
+        expression_attribute_names: Some(hashmap! { "#at".to_string() => "at".to_string() }),
+        ..ScanInput::default()
+    }))
+    .unwrap();
+    assert_eq!(
+        output.items,
+        Some(vec![
+            post_key("arthur", 1),
+            post_key("ford", 2),
+            post_key("ford", 4)
+        ])
+    );
+    assert_eq!(output.scanned_count, Some(5));
+
+    let output = block_on(client.scan(ScanInput {
+        table_name: "posts".to_string(),
+        select: Some("COUNT".to_string()),
+        ..ScanInput::default()
+    }))
+    .unwrap();
+    assert_eq!(output.items, None);
+    assert_eq!(output.count, Some(5));
+
+    // Segments split the table between them
+    let count = (0..3)
+        .map(|segment| {
+            block_on(client.scan(ScanInput {
+                table_name: "posts".to_string(),
+                segment: Some(segment),
+                total_segments: Some(3),
+                ..ScanInput::default()
+            }))
+            .unwrap()
+            .count
+            .unwrap()
+        })
+        .sum::<i64>();
+    assert_eq!(count, 5);
+}
+
+#[test]
+fn batches() {
+    let client = posts();
+    let put = |at: u32| WriteRequest {
+        put_request: Some(PutRequest {
+            item: item(json!({ "author": "zaphod", "at": at })),
+        }),
+        delete_request: None,
+    };
+    let output = block_on(client.batch_write_item(BatchWriteItemInput {
+        request_items: hashmap! {
+            "posts".to_string() => vec![
+                put(1),
+                put(2),
+                WriteRequest {
+                    put_request: None,
+                    delete_request: Some(DeleteRequest { key: post_key("ford", 4) }),
+                },
+            ],
+        },
+        ..BatchWriteItemInput::default()
+    }))
+    .unwrap();
+    assert_eq!(output.unprocessed_items, Some(HashMap::new()));
+    assert_eq!(client.items("posts").unwrap().len(), 6);
+
+    let err = validation(block_on(client.batch_write_item(BatchWriteItemInput {
+        request_items: hashmap! { "posts".to_string() => vec![put(3), put(3)] },
+        ..BatchWriteItemInput::default()
+    })));
+    assert_eq!(err, "Provided list of item keys contains duplicates");
+    assert_eq!(client.items("posts").unwrap().len(), 6);
+
+    let output = block_on(client.batch_get_item(BatchGetItemInput {
+        request_items: hashmap! {
+            "posts".to_string() => KeysAndAttributes {
+                keys: vec![post_key("zaphod", 2), post_key("ford", 4), post_key("arthur", 1)],
+                projection_expression: Some("likes".to_string()),
+                ..KeysAndAttributes::default()
+            },
+        },
+        ..BatchGetItemInput::default()
+    }))
+    .unwrap();
+    let mut responses = output.responses.unwrap().remove("posts").unwrap();
+    responses.sort_by_key(|item| item.len());
+    assert_eq!(
+        responses,
+        vec![item(json!({})), item(json!({ "likes": 5 }))]
+    );
+
+    let err = validation(block_on(
+        client.batch_get_item(BatchGetItemInput::default()),
+    ));
+    assert!(err.contains("at 'requestItems'"), "{}", err);
+    let err = validation(block_on(client.batch_get_item(BatchGetItemInput {
+        request_items: hashmap! { "posts".to_string() => KeysAndAttributes::default() },
+        ..BatchGetItemInput::default()
+    })));
+    assert!(err.contains("at 'requestItems'"), "{}", err);
+}
+
+#[test]
+fn transactions() {
+    let client = posts();
+    let transact = |condition: &str| {
+        block_on(client.transact_write_items(TransactWriteItemsInput {
+            transact_items: vec![
+                TransactWriteItem {
+                    put: Some(Put {
+                        table_name: "posts".to_string(),
+                        item: item(json!({ "author": "zaphod", "at": 1 })),
+                        ..Put::default()
+                    }),
+                    ..TransactWriteItem::default()
+                },
+                TransactWriteItem {
+                    condition_check: Some(ConditionCheck {
+                        table_name: "posts".to_string(),
+                        key: post_key("ford", 2),
+                        condition_expression: condition.to_string(),
+                        expression_attribute_values: values(json!({ ":likes": 40 })),
+                        ..ConditionCheck::default()
+                    }),
+                    ..TransactWriteItem::default()
+                },
+            ],
+            ..TransactWriteItemsInput::default()
+        }))
+    };
+
+    match transact("likes < :likes") {
+        Err(RusotoError::Service(TransactWriteItemsError::TransactionCanceled(message))) => {
+            assert_eq!(
+                message,
+                "Transaction cancelled, please refer cancellation reasons for specific reasons \
+                 [None, ConditionalCheckFailed]"
+            )
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(client.items("posts").unwrap().len(), 5);
+
+    transact("likes > :likes").unwrap();
+    assert_eq!(client.items("posts").unwrap().len(), 6);
+
+    let output = block_on(client.transact_get_items(TransactGetItemsInput {
+        transact_items: vec![TransactGetItem {
+            get: Get {
+                table_name: "posts".to_string(),
+                key: post_key("zaphod", 1),
+                ..Get::default()
+            },
+        }],
+        ..TransactGetItemsInput::default()
+    }))
+    .unwrap();
+    assert_eq!(
+        output.responses,
+        Some(vec![ItemResponse {
+            item: Some(post_key("zaphod", 1))
+        }])
+    );
+}
+
+#[test]
+fn tables() {
+    let client = posts();
+    match block_on(client.create_table(CreateTableInput {
+        table_name: "posts".to_string(),
+        key_schema: vec![key("author", "HASH")],
+        attribute_definitions: vec![attribute("author", "S")],
+        ..CreateTableInput::default()
+    })) {
+        Err(RusotoError::Service(CreateTableError::ResourceInUse(_))) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let output = block_on(client.list_tables(ListTablesInput::default())).unwrap();
+    assert_eq!(output.table_names, Some(vec!["posts".to_string()]));
+
+    let output = block_on(client.describe_table(DescribeTableInput {
+        table_name: "posts".to_string(),
+    }))
+    .unwrap();
+    let table = output.table.unwrap();
+    assert_eq!(table.item_count, Some(5));
+    assert_eq!(table.table_status.as_deref(), Some("ACTIVE"));
+
+    block_on(client.delete_table(DeleteTableInput {
+        table_name: "posts".to_string(),
+    }))
+    .unwrap();
+    assert!(client.items("posts").is_none());
+
+    assert_eq!(
+        validation(block_on(client.describe_limits())),
+        "MockDynamoDb does not support describe_limits"
+    );
+}
+
+#[cfg(feature = "mock-server")]
+mod server {
+    use super::*;
+    use rusoto_core::credential::StaticProvider;
+    use rusoto_core::{HttpClient, Region};
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    /// Send one request and return the status line and body of the response.
+    fn send(server: &MockServer, target: &str, body: &str) -> (String, serde_json::Value) {
+        let mut stream = TcpStream::connect(server.address()).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nConnection: close\r\nX-Amz-Target: {}\r\nContent-Length: {}\r\n\r\n{}",
+            target,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response.lines().next().unwrap().to_string();
+        let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        (status, serde_json::from_str(body).unwrap_or_default())
+    }
+
+    #[test]
+    fn rusoto_client() {
+        let dynamodb = posts();
+        let server = MockServer::start(&dynamodb).unwrap();
+        let client = DynamoDbClient::new_with(
+            HttpClient::new().unwrap(),
+            StaticProvider::new_minimal(String::from("key"), String::from("secret")),
+            Region::Custom {
+                name: String::from("local"),
+                endpoint: server.endpoint(),
+            },
+        );
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let mut zaphod = post_key("zaphod", 1);
+        zaphod.insert(
+            String::from("body"),
+            crate::to_attribute_value(serde_bytes::ByteBuf::from(vec![0, 255])).unwrap(),
+        );
+
+        runtime.block_on(async {
+            client
+                .put_item(PutItemInput {
+                    table_name: "posts".to_string(),
+                    item: zaphod.clone(),
+                    ..PutItemInput::default()
+                })
+                .await
+                .unwrap();
+
+            match client
+                .put_item(PutItemInput {
+                    table_name: "posts".to_string(),
+                    item: post_key("zaphod", 1),
+                    condition_expression: Some("attribute_not_exists(author)".to_string()),
+                    ..PutItemInput::default()
+                })
+                .await
+            {
+                Err(RusotoError::Service(PutItemError::ConditionalCheckFailed(message))) => {
+                    assert_eq!(message, "The conditional request failed")
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+
+            let output = client
+                .query(QueryInput {
+                    table_name: "posts".to_string(),
+                    key_condition_expression: Some("author = :author".to_string()),
+                    expression_attribute_values: values(json!({ ":author": "zaphod" })),
+                    ..QueryInput::default()
+                })
+                .await
+                .unwrap();
+            assert_eq!(output.count, Some(1));
+        });
+
+        // The server and the mock share their tables
+        assert!(dynamodb.items("posts").unwrap().contains(&zaphod));
+    }
+
+    #[test]
+    fn errors() {
+        let server = MockServer::start(&posts()).unwrap();
+
+        // A body larger than DynamoDB accepts isn't read, let alone allocated
+        let mut stream = TcpStream::connect(server.address()).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nX-Amz-Target: DynamoDB_20120810.ListTables\r\nContent-Length: {}\r\n\r\n",
+            u64::MAX
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 413 Request Entity Too Large"));
+        assert!(response.contains("ValidationException"));
+
+        // Neither are endless headers: the connection is closed once they go past the limits
+        let endless = [
+            format!("POST /{} HTTP/1.1\r\n", "a".repeat(64 * 1024)),
+            format!("POST / HTTP/1.1\r\nX-Padding: {}", "a".repeat(64 * 1024)),
+            format!("POST / HTTP/1.1\r\n{}", "X-Padding: a\r\n".repeat(1000)),
+        ];
+        for request in endless.iter() {
+            let mut stream = TcpStream::connect(server.address()).unwrap();
+            // The server may close the connection before it has all been written
+            let _ = stream.write_all(request.as_bytes());
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+            assert!(response.is_empty());
+        }
+
+        let (status, body) = send(
+            &server,
+            "DynamoDB_20120810.GetItem",
+            r#"{ "TableName": "posts", "Key": { "author": { "S": "ford" } } }"#,
+        );
+        assert_eq!(status, "HTTP/1.1 400 Bad Request");
+        assert_eq!(
+            body,
+            json!({
+                "__type": "com.amazon.coral.validate#ValidationException",
+                "message": "The provided key element does not match the schema",
+            })
+        );
+
+        let (_, body) = send(
+            &server,
+            "DynamoDB_20120810.DescribeTable",
+            r#"{ "TableName": "users" }"#,
+        );
+        assert_eq!(
+            body["__type"],
+            "com.amazonaws.dynamodb.v20120810#ResourceNotFoundException"
+        );
+
+        let (_, body) = send(&server, "DynamoDB_20120810.GetItem", "{");
+        assert_eq!(
+            body["__type"],
+            "com.amazon.coral.service#SerializationException"
+        );
+
+        let (_, body) = send(&server, "DynamoDB_20120810.DescribeLimits", "{}");
+        assert_eq!(
+            body,
+            json!({
+                "__type": "com.amazon.coral.service#UnknownOperationException",
+                "message": "MockDynamoDb does not support DescribeLimits",
+            })
+        );
+
+        let (_, body) = send(
+            &server,
+            "DynamoDB_20120810.TransactWriteItems",
+            r#"{
+                "TransactItems": [
+                    { "Put": { "TableName": "posts", "Item": { "author": { "S": "zaphod" }, "at": { "N": "1" } } } },
+                    { "ConditionCheck": {
+                        "TableName": "posts",
+                        "Key": { "author": { "S": "ford" }, "at": { "N": "2" } },
+                        "ConditionExpression": "attribute_not_exists(author)"
+                    } }
+                ]
+            }"#,
+        );
+        assert_eq!(
+            body["CancellationReasons"],
+            json!([
+                { "Code": "None" },
+                { "Code": "ConditionalCheckFailed", "Message": "The conditional request failed" },
+            ])
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! Exact decimal arithmetic for DynamoDB's `N` type.
+//!
+//! DynamoDB numbers are decimal, carry up to 38 significant digits, and are transmitted as
+//! strings. Comparing or adding them as `f64` silently loses precision, so anything that needs
+//! DynamoDB's own number semantics goes through [`Number`] instead.
+
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+
+/// The maximum number of significant digits DynamoDB stores in a number.
+pub(crate) const MAX_PRECISION: usize = 38;
+
+/// The largest allowed decimal exponent of the most significant digit (9.99...E+125).
+const MAX_MAGNITUDE: i64 = 125;
+
+/// The smallest allowed decimal exponent of the most significant digit (1E-130).
+const MIN_MAGNITUDE: i64 = -130;
+
+/// Any written exponent beyond this is out of range whatever the mantissa, and rejecting it
+/// early keeps the exponent arithmetic below from overflowing.
+const MAX_WRITTEN_EXPONENT: i64 = i64::MAX / 4;
+
+/// An exact decimal number.
+///
+/// The value is `digits × 10^exponent`, where `digits` holds base-10 digits, most significant
+/// first, with no leading or trailing zeros. Zero is represented by empty `digits`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Number {
+    negative: bool,
+    digits: Vec<u8>,
+    exponent: i64,
+}
+
+/// Why a string could not be used as a DynamoDB number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NumberError {
+    /// The string isn't a decimal number at all
+    Invalid,
+    /// More than 38 significant digits
+    Precision,
+    /// Magnitude larger than 9.99E+125
+    Overflow,
+    /// Magnitude smaller than 1E-130
+    Underflow,
+}
+
+impl Display for NumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberError::Invalid => f.write_str("The parameter cannot be converted to a numeric value"),
+            NumberError::Precision => {
+                f.write_str("Attempting to store more than 38 significant digits in a Number")
+            }
+            NumberError::Overflow => f.write_str(
+                "Number overflow. Attempting to store a number with magnitude larger than supported range",
+            ),
+            NumberError::Underflow => f.write_str(
+                "Number underflow. Attempting to store a number with magnitude smaller than supported range",
+            ),
+        }
+    }
+}
+
+impl Number {
+    /// Zero
+    pub(crate) fn zero() -> Self {
+        Number {
+            negative: false,
+            digits: Vec::new(),
+            exponent: 0,
+        }
+    }
+
+    /// Parse a number as DynamoDB would, without checking its precision.
+    ///
+    /// Numbers outside DynamoDB's range are rejected, since no stored value can hold them.
+    pub(crate) fn parse(s: &str) -> Result<Self, NumberError> {
+        let s = s.trim();
+        let (negative, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(idx) => {
+                let exponent = s[idx + 1..]
+                    .parse::<i64>()
+                    .map_err(|_| NumberError::Invalid)?;
+                (&s[..idx], exponent)
+            }
+            None => (s, 0),
+        };
+
+        let (int_part, frac_part) = match mantissa.find('.') {
+            Some(idx) => (&mantissa[..idx], &mantissa[idx + 1..]),
+            None => (mantissa, ""),
+        };
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(NumberError::Invalid);
+        }
+
+        let mut digits = Vec::with_capacity(int_part.len() + frac_part.len());
+        for c in int_part.bytes().chain(frac_part.bytes()) {
+            if !c.is_ascii_digit() {
+                return Err(NumberError::Invalid);
+            }
+            digits.push(c - b'0');
+        }
+
+        if digits.iter().all(|d| *d == 0) {
+            return Ok(Number::zero());
+        }
+        if exponent > MAX_WRITTEN_EXPONENT {
+            return Err(NumberError::Overflow);
+        }
+        if exponent < -MAX_WRITTEN_EXPONENT {
+            return Err(NumberError::Underflow);
+        }
+
+        let n = Number::from_parts(negative, digits, exponent - frac_part.len() as i64);
+        n.check_range()?;
+        Ok(n)
+    }
+
+    /// Parse a number and check that DynamoDB is able to store it.
+    pub(crate) fn parse_checked(s: &str) -> Result<Self, NumberError> {
+        let n = Number::parse(s)?;
+        n.check()?;
+        Ok(n)
+    }
+
+    fn from_parts(negative: bool, mut digits: Vec<u8>, mut exponent: i64) -> Self {
+        let leading = digits.iter().take_while(|d| **d == 0).count();
+        digits.drain(..leading);
+        while let Some(0) = digits.last() {
+            digits.pop();
+            exponent += 1;
+        }
+        if digits.is_empty() {
+            return Number::zero();
+        }
+        Number {
+            negative,
+            digits,
+            exponent,
+        }
+    }
+
+    /// Check that DynamoDB is able to store this number.
+    pub(crate) fn check(&self) -> Result<(), NumberError> {
+        if self.digits.len() > MAX_PRECISION {
+            return Err(NumberError::Precision);
+        }
+        self.check_range()
+    }
+
+    fn check_range(&self) -> Result<(), NumberError> {
+        if self.is_zero() {
+            return Ok(());
+        }
+        let magnitude = self.magnitude();
+        if magnitude > MAX_MAGNITUDE {
+            Err(NumberError::Overflow)
+        } else if magnitude < MIN_MAGNITUDE {
+            Err(NumberError::Underflow)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Whether this number is zero
+    pub(crate) fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// The decimal exponent of the most significant digit.
+    fn magnitude(&self) -> i64 {
+        self.digits.len() as i64 - 1 + self.exponent
+    }
+
+    fn cmp_magnitude(&self, other: &Self) -> Ordering {
+        match (self.is_zero(), other.is_zero()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            (false, false) => {}
+        }
+        self.magnitude()
+            .cmp(&other.magnitude())
+            .then_with(|| self.digits.cmp(&other.digits))
+    }
+
+    /// The digits of this number, padded with trailing zeros down to `exponent`.
+    fn digits_at(&self, exponent: i64) -> Vec<u8> {
+        let mut digits = self.digits.clone();
+        digits.resize(digits.len() + (self.exponent - exponent) as usize, 0);
+        digits
+    }
+
+    /// `self + other`, exactly.
+    pub(crate) fn add(&self, other: &Self) -> Self {
+        if self.is_zero() {
+            return other.clone();
+        }
+        if other.is_zero() {
+            return self.clone();
+        }
+
+        let exponent = self.exponent.min(other.exponent);
+        let a = self.digits_at(exponent);
+        let b = other.digits_at(exponent);
+
+        if self.negative == other.negative {
+            Number::from_parts(self.negative, add_digits(&a, &b), exponent)
+        } else {
+            match self.cmp_magnitude(other) {
+                Ordering::Equal => Number::zero(),
+                Ordering::Greater => {
+                    Number::from_parts(self.negative, sub_digits(&a, &b), exponent)
+                }
+                Ordering::Less => Number::from_parts(other.negative, sub_digits(&b, &a), exponent),
+            }
+        }
+    }
+
+    /// `self - other`, exactly.
+    pub(crate) fn sub(&self, other: &Self) -> Self {
+        self.add(&other.negate())
+    }
+
+    /// `-self`
+    pub(crate) fn negate(&self) -> Self {
+        if self.is_zero() {
+            return self.clone();
+        }
+        Number {
+            negative: !self.negative,
+            ..self.clone()
+        }
+    }
+}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (
+            self.negative && !self.is_zero(),
+            other.negative && !other.is_zero(),
+        ) {
+            (false, false) => self.cmp_magnitude(other),
+            (true, true) => other.cmp_magnitude(self),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Number {
+    /// Formats the number in plain decimal notation, the way DynamoDB returns numbers.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.write_str("0");
+        }
+        if self.negative {
+            f.write_str("-")?;
+        }
+        let digits: String = self.digits.iter().map(|d| (b'0' + d) as char).collect();
+        if self.exponent >= 0 {
+            f.write_str(&digits)?;
+            for _ in 0..self.exponent {
+                f.write_str("0")?;
+            }
+        } else {
+            let frac_len = (-self.exponent) as usize;
+            if frac_len >= digits.len() {
+                f.write_str("0.")?;
+                for _ in 0..frac_len - digits.len() {
+                    f.write_str("0")?;
+                }
+                f.write_str(&digits)?;
+            } else {
+                let (int_part, frac_part) = digits.split_at(digits.len() - frac_len);
+                write!(f, "{}.{}", int_part, frac_part)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Add two equal-exponent digit strings.
+fn add_digits(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    let mut a = a.iter().rev();
+    let mut b = b.iter().rev();
+    loop {
+        let (x, y) = (a.next(), b.next());
+        if x.is_none() && y.is_none() {
+            break;
+        }
+        let sum = x.copied().unwrap_or(0) + y.copied().unwrap_or(0) + carry;
+        result.push(sum % 10);
+        carry = sum / 10;
+    }
+    if carry > 0 {
+        result.push(carry);
+    }
+    result.reverse();
+    result
+}
+
+/// Subtract two equal-exponent digit strings, where `a >= b`.
+fn sub_digits(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    let mut b = b.iter().rev();
+    for x in a.iter().rev() {
+        let y = b.next().copied().unwrap_or(0) + borrow;
+        if *x >= y {
+            result.push(x - y);
+            borrow = 0;
+        } else {
+            result.push(x + 10 - y);
+            borrow = 1;
+        }
+    }
+    result.reverse();
+    result
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,72 @@
+use super::*;
+
+fn n(s: &str) -> Number {
+    Number::parse(s).unwrap()
+}
+
+#[test]
+fn parse_and_display() {
+    assert_eq!(n("0").to_string(), "0");
+    assert_eq!(n("-0.000").to_string(), "0");
+    assert_eq!(n("007").to_string(), "7");
+    assert_eq!(n("1.50").to_string(), "1.5");
+    assert_eq!(n("-.25").to_string(), "-0.25");
+    assert_eq!(n("1E+3").to_string(), "1000");
+    assert_eq!(n("12.5e-3").to_string(), "0.0125");
+    assert!(Number::parse("").is_err());
+    assert!(Number::parse("1.2.3").is_err());
+    assert!(Number::parse("abc").is_err());
+}
+
+#[test]
+fn compare() {
+    assert_eq!(n("1"), n("1.0"));
+    assert!(n("2") > n("10e-1"));
+    assert!(n("-2") < n("-1"));
+    assert!(n("-1") < n("0"));
+    assert!(n("0.001") > n("-1000"));
+    assert!(n("100") > n("99.999"));
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(n("0.1").add(&n("0.2")).to_string(), "0.3");
+    assert_eq!(n("1").sub(&n("1.5")).to_string(), "-0.5");
+    assert_eq!(n("-5").add(&n("5")).to_string(), "0");
+    assert_eq!(n("999").add(&n("1")).to_string(), "1000");
+    assert_eq!(
+        n("12345678901234567890123456789012345678")
+            .add(&n("1"))
+            .to_string(),
+        "12345678901234567890123456789012345679"
+    );
+}
+
+#[test]
+fn limits() {
+    assert_eq!(
+        Number::parse_checked("123456789012345678901234567890123456789"),
+        Err(NumberError::Precision)
+    );
+    assert_eq!(Number::parse_checked("1E126"), Err(NumberError::Overflow));
+    assert_eq!(Number::parse_checked("1E-131"), Err(NumberError::Underflow));
+    assert!(Number::parse_checked("9.99E125").is_ok());
+}
+
+#[test]
+fn extreme_exponents() {
+    assert_eq!(
+        Number::parse("1.5e-9223372036854775808"),
+        Err(NumberError::Underflow)
+    );
+    assert_eq!(
+        Number::parse("10e9223372036854775807"),
+        Err(NumberError::Overflow)
+    );
+    assert_eq!(Number::parse("1e200"), Err(NumberError::Overflow));
+    assert_eq!(
+        Number::parse("0e9223372036854775807").unwrap(),
+        Number::zero()
+    );
+    assert!(Number::parse("1e99999999999999999999").is_err());
+}
@@ -8,7 +8,7 @@ use serde::{ser, Serialize};
 #[derive(Copy, Clone, Debug)]
 pub struct Serializer;
 
-impl<'a> ser::Serializer for Serializer {
+impl ser::Serializer for Serializer {
     type Ok = AttributeValue;
     type Error = Error;
 
@@ -112,9 +112,9 @@ impl<'a> ser::Serializer for Serializer {
             ..AttributeValue::default()
         })
     }
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
@@ -177,13 +177,13 @@ impl<'a> ser::Serializer for Serializer {
         let serializer = SerializerTupleVariant::new(variant, len);
         Ok(serializer)
     }
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
@@ -197,7 +197,7 @@ impl<'a> ser::Serializer for Serializer {
         let serializer = SerializerStructVariant::new(variant, len);
         Ok(serializer)
     }
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -205,7 +205,7 @@ impl<'a> ser::Serializer for Serializer {
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let serializer = Serializer;
         let av = value.serialize(serializer)?;
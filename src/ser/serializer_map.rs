@@ -16,32 +16,28 @@ impl SerializerMap {
     }
 }
 
-impl<'a> ser::SerializeMap for SerializerMap {
+impl ser::SerializeMap for SerializerMap {
     type Ok = AttributeValue;
     type Error = Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
 
-    fn serialize_entry<K: ?Sized, V: ?Sized>(
-        &mut self,
-        key: &K,
-        value: &V,
-    ) -> Result<(), Self::Error>
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
     where
-        K: Serialize,
-        V: Serialize,
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
     {
         let key = key.serialize(MapKeySerializer)?;
         let value = value.serialize(Serializer)?;
@@ -59,7 +55,7 @@ impl<'a> ser::SerializeMap for SerializerMap {
 
 struct MapKeySerializer;
 
-impl<'a> ser::Serializer for MapKeySerializer {
+impl ser::Serializer for MapKeySerializer {
     type Ok = String;
     type Error = Error;
 
@@ -119,9 +115,9 @@ impl<'a> ser::Serializer for MapKeySerializer {
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         unreachable!()
     }
-    fn serialize_some<T: ?Sized>(self, _value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, _value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
@@ -168,13 +164,13 @@ impl<'a> ser::Serializer for MapKeySerializer {
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
         unreachable!()
     }
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         _value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
@@ -187,7 +183,7 @@ impl<'a> ser::Serializer for MapKeySerializer {
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
         unreachable!()
     }
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -195,19 +191,19 @@ impl<'a> ser::Serializer for MapKeySerializer {
         _value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
 }
 
-impl<'a> ser::SerializeSeq for MapKeySerializer {
+impl ser::SerializeSeq for MapKeySerializer {
     type Ok = String;
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
@@ -216,13 +212,13 @@ impl<'a> ser::SerializeSeq for MapKeySerializer {
         unreachable!()
     }
 }
-impl<'a> ser::SerializeTuple for MapKeySerializer {
+impl ser::SerializeTuple for MapKeySerializer {
     type Ok = String;
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
@@ -231,13 +227,13 @@ impl<'a> ser::SerializeTuple for MapKeySerializer {
         unreachable!()
     }
 }
-impl<'a> ser::SerializeTupleStruct for MapKeySerializer {
+impl ser::SerializeTupleStruct for MapKeySerializer {
     type Ok = String;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
@@ -246,13 +242,13 @@ impl<'a> ser::SerializeTupleStruct for MapKeySerializer {
         unreachable!()
     }
 }
-impl<'a> ser::SerializeTupleVariant for MapKeySerializer {
+impl ser::SerializeTupleVariant for MapKeySerializer {
     type Ok = String;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
@@ -262,17 +258,13 @@ impl<'a> ser::SerializeTupleVariant for MapKeySerializer {
     }
 }
 
-impl<'a> ser::SerializeStructVariant for MapKeySerializer {
+impl ser::SerializeStructVariant for MapKeySerializer {
     type Ok = String;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        _key: &'static str,
-        _value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
@@ -282,32 +274,28 @@ impl<'a> ser::SerializeStructVariant for MapKeySerializer {
     }
 }
 
-impl<'a> ser::SerializeMap for MapKeySerializer {
+impl ser::SerializeMap for MapKeySerializer {
     type Ok = String;
     type Error = Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
 
-    fn serialize_entry<K: ?Sized, V: ?Sized>(
-        &mut self,
-        _key: &K,
-        _value: &V,
-    ) -> Result<(), Self::Error>
+    fn serialize_entry<K, V>(&mut self, _key: &K, _value: &V) -> Result<(), Self::Error>
     where
-        K: Serialize,
-        V: Serialize,
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
     {
         unreachable!()
     }
@@ -317,17 +305,13 @@ impl<'a> ser::SerializeMap for MapKeySerializer {
     }
 }
 
-impl<'a> ser::SerializeStruct for MapKeySerializer {
+impl ser::SerializeStruct for MapKeySerializer {
     type Ok = String;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        _key: &'static str,
-        _value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
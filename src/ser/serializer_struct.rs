@@ -13,17 +13,13 @@ impl SerializerStruct {
     }
 }
 
-impl<'a> ser::SerializeStruct for SerializerStruct {
+impl ser::SerializeStruct for SerializerStruct {
     type Ok = AttributeValue;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let serializer = Serializer;
         let value = value.serialize(serializer)?;
@@ -15,17 +15,13 @@ impl SerializerStructVariant {
     }
 }
 
-impl<'a> ser::SerializeStructVariant for SerializerStructVariant {
+impl ser::SerializeStructVariant for SerializerStructVariant {
     type Ok = AttributeValue;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let serializer = Serializer;
         let value = value.serialize(serializer)?;
@@ -15,13 +15,13 @@ impl SerializerTupleVariant {
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for SerializerTupleVariant {
+impl ser::SerializeTupleVariant for SerializerTupleVariant {
     type Ok = AttributeValue;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let serializer = Serializer;
         let value = value.serialize(serializer)?;
@@ -0,0 +1,39 @@
+use crate::{AttributeValue, Item};
+
+/// An estimate of an item's size, the way DynamoDB counts it: the lengths of its attribute names
+/// and values.
+pub(crate) fn item_size(item: &Item) -> usize {
+    item.iter()
+        .map(|(name, value)| name.len() + value_size(value))
+        .sum()
+}
+
+fn value_size(value: &AttributeValue) -> usize {
+    // Numbers are stored as two digits a byte, plus a byte
+    let number_size = |n: &String| n.len().div_ceil(2) + 1;
+
+    if let Some(s) = &value.s {
+        s.len()
+    } else if let Some(n) = &value.n {
+        number_size(n)
+    } else if let Some(b) = &value.b {
+        b.len()
+    } else if let Some(ss) = &value.ss {
+        ss.iter().map(String::len).sum()
+    } else if let Some(ns) = &value.ns {
+        ns.iter().map(number_size).sum()
+    } else if let Some(bs) = &value.bs {
+        bs.iter().map(|b| b.len()).sum()
+    } else if let Some(l) = &value.l {
+        // Lists and maps take three bytes, and a byte for each element
+        3 + l.iter().map(|v| 1 + value_size(v)).sum::<usize>()
+    } else if let Some(m) = &value.m {
+        3 + m
+            .iter()
+            .map(|(name, v)| 1 + name.len() + value_size(v))
+            .sum::<usize>()
+    } else {
+        // Booleans and nulls
+        1
+    }
+}
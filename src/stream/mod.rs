@@ -0,0 +1,274 @@
+//! Typed change events of DynamoDB Streams.
+//!
+//! Each record of a table's stream describes one change to one item. A [`StreamRecord`] is that
+//! record with its `Keys` deserialized as `K` and its images as `T`, and the change itself as a
+//! [`Change`]: an item was inserted, modified, or removed.
+//!
+//! Which images a record carries depends on the stream's [`StreamViewType`]. An image the view
+//! type leaves out is `None`; an image the view type includes, but the record lacks, is an error.
+//!
+//! Records are made from the records of the sources that deliver them: `aws_lambda_events`'
+//! with the `lambda` feature, and `rusoto_dynamodbstreams`' with the `streams` feature. Both
+//! also implement [`StreamImages`], to deserialize a single image without the rest. With the
+//! `kinesis` feature, they are also parsed from the payloads of Kinesis Data Streams for
+//! DynamoDB, so consumers stay the same whichever transport delivers the changes.
+
+use crate::error::ErrorImpl;
+use crate::{from_item, Item, Result};
+use serde::de::DeserializeOwned;
+use std::fmt::{self, Display};
+use std::time::SystemTime;
+
+#[cfg(test)]
+mod tests;
+
+/// The principal DynamoDB deletes expired items as, when Time to Live removes them.
+pub const TTL_PRINCIPAL: &str = "dynamodb.amazonaws.com";
+
+/// Stream records, whose keys and images can be deserialized with [`from_item`].
+///
+/// Which images a record has depends on the stream's `StreamViewType`, and on the change: an
+/// `INSERT` has no old image, and a `REMOVE` no new one. Asking for an image the record doesn't
+/// have fails, rather than deserializing an empty item.
+pub trait StreamImages {
+    /// The record's `Keys`: the key attributes of the item that changed.
+    fn keys<K: DeserializeOwned>(&self) -> Result<K>;
+
+    /// The record's `NewImage`: the item as it was after the change.
+    fn new_image<T: DeserializeOwned>(&self) -> Result<T>;
+
+    /// The record's `OldImage`: the item as it was before the change.
+    fn old_image<T: DeserializeOwned>(&self) -> Result<T>;
+}
+
+/// A stream record, with its keys deserialized as `K` and its images as `T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamRecord<T, K> {
+    /// A unique identifier of the record
+    pub event_id: Option<String>,
+    /// The table the item belongs to, if the source says
+    pub table_name: Option<String>,
+    /// The record's sequence number, which orders the changes to an item
+    pub sequence_number: Option<String>,
+    /// Roughly when the change was made
+    pub approximate_creation_time: Option<SystemTime>,
+    /// Who made the change, if it wasn't a user: DynamoDB itself, for Time to Live deletes
+    pub user_identity: Option<UserIdentity>,
+    /// The stream's view type, which decides the images the record carries
+    pub view_type: Option<StreamViewType>,
+    /// The key attributes of the item that changed
+    pub keys: K,
+    /// What happened to the item
+    pub change: Change<T>,
+}
+
+impl<T, K> StreamRecord<T, K> {
+    /// Whether the change was Time to Live deleting an expired item.
+    pub fn is_ttl_delete(&self) -> bool {
+        matches!(self.change, Change::Remove(_))
+            && self.user_identity.as_ref().is_some_and(|identity| {
+                identity.type_ == "Service" && identity.principal_id == TTL_PRINCIPAL
+            })
+    }
+}
+
+/// What happened to an item, with its images before and after.
+///
+/// An image is `None` when the stream's view type leaves it out.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<T> {
+    /// The item was created, and this is its new image
+    Insert(Option<T>),
+    /// The item was changed
+    Modify {
+        /// The item before the change
+        old: Option<T>,
+        /// The item after the change
+        new: Option<T>,
+    },
+    /// The item was deleted, and this is its old image
+    Remove(Option<T>),
+}
+
+impl<T> Change<T> {
+    /// The item after the change: `None` for a [`Change::Remove`], or when the view type leaves
+    /// out new images.
+    pub fn new_image(&self) -> Option<&T> {
+        match self {
+            Change::Insert(new) | Change::Modify { new, .. } => new.as_ref(),
+            Change::Remove(_) => None,
+        }
+    }
+
+    /// The item before the change: `None` for a [`Change::Insert`], or when the view type leaves
+    /// out old images.
+    pub fn old_image(&self) -> Option<&T> {
+        match self {
+            Change::Remove(old) | Change::Modify { old, .. } => old.as_ref(),
+            Change::Insert(_) => None,
+        }
+    }
+}
+
+/// Which images a stream writes to its records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamViewType {
+    /// Only the key attributes of the item
+    KeysOnly,
+    /// The item as it was after the change
+    NewImage,
+    /// The item as it was before the change
+    OldImage,
+    /// The item both before and after the change
+    NewAndOldImages,
+}
+
+impl StreamViewType {
+    /// Parse a view type as DynamoDB names it, such as `NEW_AND_OLD_IMAGES`.
+    pub fn parse(view_type: &str) -> Option<Self> {
+        match view_type {
+            "KEYS_ONLY" => Some(StreamViewType::KeysOnly),
+            "NEW_IMAGE" => Some(StreamViewType::NewImage),
+            "OLD_IMAGE" => Some(StreamViewType::OldImage),
+            "NEW_AND_OLD_IMAGES" => Some(StreamViewType::NewAndOldImages),
+            _ => None,
+        }
+    }
+
+    /// The view type as DynamoDB names it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamViewType::KeysOnly => "KEYS_ONLY",
+            StreamViewType::NewImage => "NEW_IMAGE",
+            StreamViewType::OldImage => "OLD_IMAGE",
+            StreamViewType::NewAndOldImages => "NEW_AND_OLD_IMAGES",
+        }
+    }
+
+    /// Whether records carry the item after the change.
+    pub fn has_new_image(self) -> bool {
+        matches!(
+            self,
+            StreamViewType::NewImage | StreamViewType::NewAndOldImages
+        )
+    }
+
+    /// Whether records carry the item before the change.
+    pub fn has_old_image(self) -> bool {
+        matches!(
+            self,
+            StreamViewType::OldImage | StreamViewType::NewAndOldImages
+        )
+    }
+}
+
+impl Display for StreamViewType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The identity that made a change, which stream records only have for changes DynamoDB made
+/// itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserIdentity {
+    /// The kind of identity, `Service` for DynamoDB
+    pub type_: String,
+    /// The identity, [`TTL_PRINCIPAL`] for Time to Live deletes
+    pub principal_id: String,
+}
+
+/// A stream record as a source delivers it, before it is deserialized.
+pub(crate) struct RawRecord<'a> {
+    pub event_id: Option<String>,
+    pub event_name: &'a str,
+    pub table_name: Option<String>,
+    pub sequence_number: Option<String>,
+    pub approximate_creation_time: Option<SystemTime>,
+    pub user_identity: Option<UserIdentity>,
+    pub view_type: Option<StreamViewType>,
+    pub keys: Item,
+    pub new_image: Option<Item>,
+    pub old_image: Option<Item>,
+}
+
+impl RawRecord<'_> {
+    /// Deserialize the record's keys and images, checking they are the ones its view type and
+    /// event name call for.
+    pub fn into_typed<T, K>(self) -> Result<StreamRecord<T, K>>
+    where
+        T: DeserializeOwned,
+        K: DeserializeOwned,
+    {
+        let view_type = self.view_type;
+        let (wants_new, wants_old) = match self.event_name {
+            "INSERT" => (true, false),
+            "MODIFY" => (true, true),
+            "REMOVE" => (false, true),
+            name => {
+                return Err(
+                    ErrorImpl::InvalidStreamRecord(format!("unknown event name {}", name)).into(),
+                )
+            }
+        };
+        let new = image(
+            self.new_image,
+            "NewImage",
+            wants_new,
+            view_type,
+            StreamViewType::has_new_image,
+        )?;
+        let old = image(
+            self.old_image,
+            "OldImage",
+            wants_old,
+            view_type,
+            StreamViewType::has_old_image,
+        )?;
+        let change = match self.event_name {
+            "INSERT" => Change::Insert(new),
+            "MODIFY" => Change::Modify { old, new },
+            _ => Change::Remove(old),
+        };
+
+        if self.keys.is_empty() {
+            return Err(ErrorImpl::InvalidStreamRecord(String::from("it has no Keys")).into());
+        }
+        Ok(StreamRecord {
+            event_id: self.event_id,
+            table_name: self.table_name,
+            sequence_number: self.sequence_number,
+            approximate_creation_time: self.approximate_creation_time,
+            user_identity: self.user_identity,
+            view_type,
+            keys: from_item(self.keys)?,
+            change,
+        })
+    }
+}
+
+/// Deserialize an image the change calls for, if the view type includes it.
+///
+/// Without a view type, whatever image the record has is used.
+fn image<T: DeserializeOwned>(
+    item: Option<Item>,
+    name: &str,
+    wanted: bool,
+    view_type: Option<StreamViewType>,
+    in_view: fn(StreamViewType) -> bool,
+) -> Result<Option<T>> {
+    if !wanted || !view_type.is_none_or(in_view) {
+        return Ok(None);
+    }
+    match item.filter(|item| !item.is_empty()) {
+        Some(item) => Ok(Some(from_item(item)?)),
+        None => match view_type {
+            Some(view_type) => Err(ErrorImpl::InvalidStreamRecord(format!(
+                "it has no {}, though its view type {} includes it",
+                name, view_type
+            ))
+            .into()),
+            None => Ok(None),
+        },
+    }
+}
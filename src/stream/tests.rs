@@ -0,0 +1,220 @@
+use super::*;
+use maplit::hashmap;
+use rusoto_dynamodb::AttributeValue;
+use serde_derive::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct User {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct UserKey {
+    id: String,
+}
+
+fn s(s: &str) -> AttributeValue {
+    AttributeValue {
+        s: Some(s.to_string()),
+        ..AttributeValue::default()
+    }
+}
+
+fn user(name: &str) -> Item {
+    hashmap! {
+        String::from("id") => s("arthur"),
+        String::from("name") => s(name),
+    }
+}
+
+fn raw(
+    event_name: &str,
+    view_type: Option<StreamViewType>,
+    new_image: Option<Item>,
+    old_image: Option<Item>,
+) -> RawRecord<'_> {
+    RawRecord {
+        event_id: Some(String::from("1")),
+        event_name,
+        table_name: None,
+        sequence_number: Some(String::from("100")),
+        approximate_creation_time: None,
+        user_identity: None,
+        view_type,
+        keys: hashmap! { String::from("id") => s("arthur") },
+        new_image,
+        old_image,
+    }
+}
+
+fn name(name: &str) -> User {
+    User {
+        id: String::from("arthur"),
+        name: String::from(name),
+    }
+}
+
+#[test]
+fn new_and_old_images() {
+    let view_type = Some(StreamViewType::NewAndOldImages);
+
+    let record: StreamRecord<User, UserKey> = raw("INSERT", view_type, Some(user("Arthur")), None)
+        .into_typed()
+        .unwrap();
+    assert_eq!(record.change, Change::Insert(Some(name("Arthur"))));
+    assert_eq!(record.keys.id, "arthur");
+    assert_eq!(record.sequence_number.as_deref(), Some("100"));
+
+    let record: StreamRecord<User, UserKey> = raw(
+        "MODIFY",
+        view_type,
+        Some(user("Arthur Dent")),
+        Some(user("Arthur")),
+    )
+    .into_typed()
+    .unwrap();
+    assert_eq!(
+        record.change,
+        Change::Modify {
+            old: Some(name("Arthur")),
+            new: Some(name("Arthur Dent")),
+        }
+    );
+    assert_eq!(record.change.old_image(), Some(&name("Arthur")));
+    assert_eq!(record.change.new_image(), Some(&name("Arthur Dent")));
+
+    let record: StreamRecord<User, UserKey> = raw("REMOVE", view_type, None, Some(user("Arthur")))
+        .into_typed()
+        .unwrap();
+    assert_eq!(record.change, Change::Remove(Some(name("Arthur"))));
+    assert_eq!(record.change.new_image(), None);
+}
+
+#[test]
+fn images_the_view_type_leaves_out() {
+    let record: StreamRecord<User, UserKey> = raw(
+        "MODIFY",
+        Some(StreamViewType::NewImage),
+        Some(user("Arthur Dent")),
+        Some(user("Arthur")),
+    )
+    .into_typed()
+    .unwrap();
+    assert_eq!(
+        record.change,
+        Change::Modify {
+            old: None,
+            new: Some(name("Arthur Dent")),
+        }
+    );
+
+    let record: StreamRecord<User, UserKey> =
+        raw("REMOVE", Some(StreamViewType::NewImage), None, None)
+            .into_typed()
+            .unwrap();
+    assert_eq!(record.change, Change::Remove(None));
+
+    let record: StreamRecord<User, UserKey> =
+        raw("MODIFY", Some(StreamViewType::KeysOnly), None, None)
+            .into_typed()
+            .unwrap();
+    assert_eq!(
+        record.change,
+        Change::Modify {
+            old: None,
+            new: None
+        }
+    );
+    assert_eq!(record.keys.id, "arthur");
+
+    let record: StreamRecord<User, UserKey> =
+        raw("INSERT", Some(StreamViewType::OldImage), None, None)
+            .into_typed()
+            .unwrap();
+    assert_eq!(record.change, Change::Insert(None));
+}
+
+#[test]
+fn missing_images() {
+    let err = raw("INSERT", Some(StreamViewType::NewImage), None, None)
+        .into_typed::<User, UserKey>()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid stream record: it has no NewImage, though its view type NEW_IMAGE includes it"
+    );
+
+    let err = raw(
+        "MODIFY",
+        Some(StreamViewType::NewAndOldImages),
+        Some(user("Arthur Dent")),
+        Some(Item::new()),
+    )
+    .into_typed::<User, UserKey>()
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid stream record: it has no OldImage, though its view type NEW_AND_OLD_IMAGES includes it"
+    );
+
+    // Without a view type, missing images are only missing
+    let record: StreamRecord<User, UserKey> = raw("MODIFY", None, Some(user("Arthur")), None)
+        .into_typed()
+        .unwrap();
+    assert_eq!(
+        record.change,
+        Change::Modify {
+            old: None,
+            new: Some(name("Arthur"))
+        }
+    );
+}
+
+#[test]
+fn invalid_records() {
+    let err = raw("UPSERT", None, None, None)
+        .into_typed::<User, UserKey>()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid stream record: unknown event name UPSERT"
+    );
+
+    let mut record = raw("REMOVE", None, None, None);
+    record.keys = Item::new();
+    let err = record.into_typed::<User, UserKey>().unwrap_err();
+    assert_eq!(err.to_string(), "Invalid stream record: it has no Keys");
+}
+
+#[test]
+fn ttl_deletes() {
+    let mut record = raw("REMOVE", Some(StreamViewType::KeysOnly), None, None);
+    record.user_identity = Some(UserIdentity {
+        type_: String::from("Service"),
+        principal_id: String::from(TTL_PRINCIPAL),
+    });
+    let record: StreamRecord<User, UserKey> = record.into_typed().unwrap();
+    assert!(record.is_ttl_delete());
+
+    let record: StreamRecord<User, UserKey> =
+        raw("REMOVE", Some(StreamViewType::KeysOnly), None, None)
+            .into_typed()
+            .unwrap();
+    assert!(!record.is_ttl_delete());
+}
+
+#[test]
+fn view_type_names() {
+    for view_type in [
+        StreamViewType::KeysOnly,
+        StreamViewType::NewImage,
+        StreamViewType::OldImage,
+        StreamViewType::NewAndOldImages,
+    ]
+    .iter()
+    {
+        assert_eq!(StreamViewType::parse(view_type.as_str()), Some(*view_type));
+    }
+    assert_eq!(StreamViewType::parse("ALL"), None);
+}
@@ -0,0 +1,259 @@
+use super::{to_attribute_value, AttributeValue, Error, ErrorImpl, Item, Result};
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeMap, SerializeSeq, Serializer};
+use serde::Serialize;
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// Read any serde format straight into a [rusoto_dynamodb::AttributeValue], without a Rust type
+/// in between.
+///
+/// Whatever `deserializer` reads is written with [`Serializer`](crate::Serializer), so the result
+/// is what [`to_attribute_value`] makes of the same data: strings become `S`, numbers `N`, bytes
+/// `B`, sequences `L`, maps `M`, and unit and none `NULL`. Map keys have to be strings or
+/// numbers.
+///
+/// ```
+/// use serde_dynamo::transcode_to_attribute_value;
+///
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut json = serde_json::Deserializer::from_str(r#"{ "name": "Arthur Dent", "age": 42 }"#);
+/// let value = transcode_to_attribute_value(&mut json)?;
+/// assert_eq!(value.m.unwrap()["age"].n.as_deref(), Some("42"));
+/// # Ok(())
+/// # }
+/// # test().unwrap()
+/// ```
+///
+/// [rusoto_dynamodb::AttributeValue]: https://docs.rs/rusoto_dynamodb/0.45.0/rusoto_dynamodb/struct.AttributeValue.html
+pub fn transcode_to_attribute_value<'de, D>(deserializer: D) -> Result<AttributeValue>
+where
+    D: Deserializer<'de>,
+{
+    Transcode
+        .deserialize(deserializer)
+        .map_err(<Error as de::Error>::custom)
+}
+
+/// Read any serde format straight into an [`Item`], without a Rust type in between.
+///
+/// Like [`transcode_to_attribute_value`], but what `deserializer` reads has to be a map.
+pub fn transcode_to_item<'de, D>(deserializer: D) -> Result<Item>
+where
+    D: Deserializer<'de>,
+{
+    transcode_to_attribute_value(deserializer)?
+        .m
+        .ok_or_else(|| ErrorImpl::NotMaplike.into())
+}
+
+struct Transcode;
+
+impl<'de> DeserializeSeed<'de> for Transcode {
+    type Value = AttributeValue;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+/// Serialize a scalar with [`Serializer`](crate::Serializer), in the visitor's error type.
+fn scalar<T: Serialize, E: de::Error>(value: T) -> std::result::Result<AttributeValue, E> {
+    to_attribute_value(value).map_err(E::custom)
+}
+
+impl<'de> Visitor<'de> for Transcode {
+    type Value = AttributeValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any value DynamoDB can store")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<AttributeValue, E> {
+        scalar(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<AttributeValue, E> {
+        scalar(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<AttributeValue, E> {
+        scalar(v)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<AttributeValue, E> {
+        scalar(v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<AttributeValue, E> {
+        scalar(v)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<AttributeValue, E> {
+        scalar(bytes(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<AttributeValue, E> {
+        scalar(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<AttributeValue, E> {
+        scalar(())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> std::result::Result<AttributeValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<AttributeValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<AttributeValue, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut l = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element_seed(Transcode)? {
+            l.push(value);
+        }
+        Ok(AttributeValue {
+            l: Some(l),
+            ..AttributeValue::default()
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> std::result::Result<AttributeValue, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut m = Item::new();
+        while let Some(key) = map.next_key_seed(Transcode)? {
+            let key = match (key.s, key.n) {
+                (Some(key), _) | (None, Some(key)) => key,
+                _ => return Err(de::Error::custom("Map keys have to be strings or numbers")),
+            };
+            let value = map.next_value_seed(Transcode)?;
+            m.insert(key, value);
+        }
+        Ok(AttributeValue {
+            m: Some(m),
+            ..AttributeValue::default()
+        })
+    }
+}
+
+/// Bytes that serialize as bytes, rather than as a sequence of numbers.
+fn bytes(v: &[u8]) -> impl Serialize + '_ {
+    struct Bytes<'a>(&'a [u8]);
+
+    impl Serialize for Bytes<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+
+    Bytes(v)
+}
+
+/// Serializes an [`Item`] or an attribute value the way it would be deserialized without a type:
+/// the natural representation, rather than DynamoDB's tagged one.
+///
+/// Any serde format can write it, so items can be piped straight into JSON, YAML, CBOR and the
+/// like. `S` is a string, `N` a number, `B` bytes, `BOOL` a bool, `NULL` unit, `L` and the sets
+/// sequences, and `M` a map, as [`from_item`](crate::from_item) would read them into a
+/// `serde_json::Value`.
+///
+/// ```
+/// use serde_dynamo::{to_item, Natural};
+/// # use serde_json::json;
+///
+/// # fn test() -> Result<(), Box<dyn std::error::Error>> {
+/// let item = to_item(json!({ "name": "Arthur Dent", "age": 42 }))?;
+/// let json = serde_json::to_string(&Natural(&item))?;
+/// # assert_eq!(serde_json::from_str::<serde_json::Value>(&json)?, json!({ "name": "Arthur Dent", "age": 42 }));
+/// # Ok(())
+/// # }
+/// # test().unwrap()
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Natural<'a, T: ?Sized>(pub &'a T);
+
+impl Serialize for Natural<'_, Item> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0 {
+            map.serialize_entry(key, &Natural(value))?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Natural<'_, AttributeValue> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let value = self.0;
+        if let Some(s) = &value.s {
+            serializer.serialize_str(s)
+        } else if let Some(n) = &value.n {
+            NaturalNumber(n).serialize(serializer)
+        } else if let Some(b) = &value.b {
+            serializer.serialize_bytes(b)
+        } else if let Some(bool) = value.bool {
+            serializer.serialize_bool(bool)
+        } else if value.null.is_some() {
+            serializer.serialize_unit()
+        } else if let Some(m) = &value.m {
+            Natural(m).serialize(serializer)
+        } else if let Some(l) = &value.l {
+            serializer.collect_seq(l.iter().map(Natural))
+        } else if let Some(ss) = &value.ss {
+            serializer.collect_seq(ss)
+        } else if let Some(ns) = &value.ns {
+            serializer.collect_seq(ns.iter().map(|n| NaturalNumber(n)))
+        } else if let Some(bs) = &value.bs {
+            let mut seq = serializer.serialize_seq(Some(bs.len()))?;
+            for b in bs {
+                seq.serialize_element(&bytes(b))?;
+            }
+            seq.end()
+        } else {
+            Err(ser::Error::custom("Attribute value without a value"))
+        }
+    }
+}
+
+/// A number, as the narrowest of `i64`, `u64` and `f64` that holds it, like the
+/// [`Deserializer`](crate::Deserializer) reads it.
+struct NaturalNumber<'a>(&'a str);
+
+impl Serialize for NaturalNumber<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if let Ok(i) = self.0.parse::<i64>() {
+            serializer.serialize_i64(i)
+        } else if let Ok(u) = self.0.parse::<u64>() {
+            serializer.serialize_u64(u)
+        } else {
+            match self.0.parse::<f64>() {
+                Ok(f) => serializer.serialize_f64(f),
+                Err(err) => Err(ser::Error::custom(ErrorImpl::FailedToParseFloat(
+                    self.0.to_string(),
+                    err,
+                ))),
+            }
+        }
+    }
+}
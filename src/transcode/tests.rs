@@ -0,0 +1,195 @@
+use super::*;
+use maplit::hashmap;
+use serde::de::value::{BytesDeserializer, Error as ValueError, MapDeserializer, U64Deserializer};
+use serde::de::IntoDeserializer;
+use serde_json::json;
+
+#[test]
+fn transcode_json() {
+    let json = r#"{
+        "name": "Arthur Dent",
+        "age": 42,
+        "height": 1.8,
+        "towel": true,
+        "planet": null,
+        "friends": ["ford", { "name": "trillian" }]
+    }"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let item = transcode_to_item(&mut deserializer).unwrap();
+
+    // The same as serializing the data through a Rust type
+    let value: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(item, crate::to_item(value).unwrap());
+    assert_eq!(
+        item["age"],
+        AttributeValue {
+            n: Some(String::from("42")),
+            ..AttributeValue::default()
+        }
+    );
+}
+
+#[test]
+fn transcode_scalars() {
+    let value =
+        transcode_to_attribute_value(BytesDeserializer::<ValueError>::new(&[0, 1, 255])).unwrap();
+    assert_eq!(value.b.as_deref(), Some(&[0, 1, 255][..]));
+
+    let value = transcode_to_attribute_value(U64Deserializer::<ValueError>::new(u64::MAX)).unwrap();
+    assert_eq!(
+        value,
+        AttributeValue {
+            n: Some(String::from("18446744073709551615")),
+            ..AttributeValue::default()
+        }
+    );
+
+    let value =
+        transcode_to_attribute_value(IntoDeserializer::<ValueError>::into_deserializer(-7i8))
+            .unwrap();
+    assert_eq!(
+        value,
+        AttributeValue {
+            n: Some(String::from("-7")),
+            ..AttributeValue::default()
+        }
+    );
+}
+
+#[test]
+fn transcode_numeric_keys() {
+    let map = MapDeserializer::<_, ValueError>::new(vec![(1u32, "one"), (2, "two")].into_iter());
+    let item = transcode_to_item(map).unwrap();
+    assert_eq!(
+        item,
+        hashmap! {
+            String::from("1") => AttributeValue {
+                s: Some(String::from("one")),
+                ..AttributeValue::default()
+            },
+            String::from("2") => AttributeValue {
+                s: Some(String::from("two")),
+                ..AttributeValue::default()
+            },
+        }
+    );
+
+    let map = MapDeserializer::<_, ValueError>::new(vec![(true, "yes")].into_iter());
+    assert_eq!(
+        transcode_to_item(map).unwrap_err().to_string(),
+        "Map keys have to be strings or numbers"
+    );
+
+    let mut deserializer = serde_json::Deserializer::from_str("[1, 2]");
+    assert_eq!(
+        transcode_to_item(&mut deserializer).unwrap_err(),
+        ErrorImpl::NotMaplike.into()
+    );
+}
+
+#[test]
+fn natural_item() {
+    let item = hashmap! {
+        String::from("name") => AttributeValue {
+            s: Some(String::from("Arthur Dent")),
+            ..AttributeValue::default()
+        },
+        String::from("age") => AttributeValue {
+            n: Some(String::from("42")),
+            ..AttributeValue::default()
+        },
+        String::from("big") => AttributeValue {
+            n: Some(String::from("18446744073709551615")),
+            ..AttributeValue::default()
+        },
+        String::from("height") => AttributeValue {
+            n: Some(String::from("1.80")),
+            ..AttributeValue::default()
+        },
+        String::from("towel") => AttributeValue {
+            bool: Some(true),
+            ..AttributeValue::default()
+        },
+        String::from("planet") => AttributeValue {
+            null: Some(true),
+            ..AttributeValue::default()
+        },
+        String::from("tags") => AttributeValue {
+            ss: Some(vec![String::from("human")]),
+            ..AttributeValue::default()
+        },
+        String::from("scores") => AttributeValue {
+            ns: Some(vec![String::from("1"), String::from("2.5")]),
+            ..AttributeValue::default()
+        },
+        String::from("friends") => AttributeValue {
+            l: Some(vec![AttributeValue {
+                s: Some(String::from("ford")),
+                ..AttributeValue::default()
+            }]),
+            ..AttributeValue::default()
+        },
+        String::from("address") => AttributeValue {
+            m: Some(hashmap! {
+                String::from("city") => AttributeValue {
+                    s: Some(String::from("Cottington")),
+                    ..AttributeValue::default()
+                },
+            }),
+            ..AttributeValue::default()
+        },
+    };
+
+    assert_eq!(
+        serde_json::to_value(Natural(&item)).unwrap(),
+        json!({
+            "name": "Arthur Dent",
+            "age": 42,
+            "big": 18446744073709551615u64,
+            "height": 1.8,
+            "towel": true,
+            "planet": null,
+            "tags": ["human"],
+            "scores": [1, 2.5],
+            "friends": ["ford"],
+            "address": { "city": "Cottington" }
+        })
+    );
+
+    // The same as deserializing the item without a type
+    let untyped: serde_json::Value = crate::from_item(item.clone()).unwrap();
+    assert_eq!(serde_json::to_value(Natural(&item)).unwrap(), untyped);
+}
+
+#[test]
+fn natural_bytes() {
+    let value = AttributeValue {
+        bs: Some(vec![vec![1].into()]),
+        ..AttributeValue::default()
+    };
+    // JSON writes bytes as arrays of numbers
+    assert_eq!(serde_json::to_value(Natural(&value)).unwrap(), json!([[1]]));
+    // Formats with bytes keep them, as the Serializer does
+    assert_eq!(
+        to_attribute_value(Natural(&value)).unwrap().l.unwrap()[0]
+            .b
+            .as_deref(),
+        Some(&[1][..])
+    );
+
+    let invalid = AttributeValue {
+        n: Some(String::from("forty-two")),
+        ..AttributeValue::default()
+    };
+    assert!(serde_json::to_value(Natural(&invalid)).is_err());
+    assert!(serde_json::to_value(Natural(&AttributeValue::default())).is_err());
+}
+
+#[test]
+fn round_trip_through_another_format() {
+    let item =
+        crate::to_item(json!({ "name": "Arthur Dent", "age": 42, "friends": ["ford"] })).unwrap();
+    let json = serde_json::to_string(&Natural(&item)).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    assert_eq!(transcode_to_item(&mut deserializer).unwrap(), item);
+}